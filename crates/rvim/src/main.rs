@@ -2,7 +2,7 @@
 //        * Store key command buffer in VimContext
 //        * Try to parse on each input, consume on success
 //        * Display buffer in UI
-//        * Change cursor to underline when pending buffer matches "c", "d"
+//        * Change cursor to underline when an operator is pending
 //
 // TODO: [ ] Fix word motion edge cases
 //        * Line 5 in leading whitespace, normal mode, b
@@ -24,11 +24,11 @@
 //
 // TODO: [ ] Implement visual mode
 //
-// TODO: [ ] Implement registers
+// TODO: [✓] Implement registers
 //
-// TODO: [ ] Implement undo/redo
+// TODO: [✓] Implement undo/redo
 //
-// TODO: [ ] Implement internally mutable message buffer
+// TODO: [✓] Implement internally mutable message buffer
 //        * Need access from vim context methods as well as calling code
 //        * Ex. 'Pattern not found' errors from vim context, command mode errors from calling code
 //
@@ -37,7 +37,15 @@
 //
 pub mod render;
 
-use vimbed::{command::*, context::Context, nom::Err as NomErr};
+use vimbed::{
+    context::Context,
+    ex::ex_command,
+    key::{Key, KeyCode, Modifiers},
+    message::Message,
+    mode::Mode,
+    ropey::Rope,
+    window::Rect,
+};
 
 use render::render;
 
@@ -46,42 +54,120 @@ use std::{
     error::Error,
     rc::Rc,
     sync::atomic::{AtomicBool, Ordering},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crossterm::{
     cursor,
-    event::{poll, read, Event, KeyCode},
+    event::{poll, read, Event, KeyEvent, KeyModifiers},
     terminal, ExecutableCommand, QueueableCommand,
 };
 use std::io::{stdout, Write};
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let running = Rc::new(AtomicBool::new(true));
+/// Convert a terminal key event into a vimbed key
+fn key(event: KeyEvent) -> Option<Key> {
+    use crossterm::event::KeyCode as Code;
+
+    let mut modifiers = Modifiers {
+        shift: event.modifiers.contains(KeyModifiers::SHIFT),
+        ctrl: event.modifiers.contains(KeyModifiers::CONTROL),
+        alt: event.modifiers.contains(KeyModifiers::ALT),
+        meta: false,
+    };
+
+    let code = match event.code {
+        // Terminals send Ctrl-^ as Ctrl-6
+        Code::Char('6') if modifiers.ctrl => KeyCode::Char('^'),
+        Code::Char(c) => KeyCode::Char(c),
+        Code::Backspace => KeyCode::Backspace,
+        Code::Enter => KeyCode::Enter,
+        Code::Left => KeyCode::Left,
+        Code::Right => KeyCode::Right,
+        Code::Up => KeyCode::Up,
+        Code::Down => KeyCode::Down,
+        Code::Home => KeyCode::Home,
+        Code::End => KeyCode::End,
+        Code::PageUp => KeyCode::PageUp,
+        Code::PageDown => KeyCode::PageDown,
+        Code::Tab => KeyCode::Tab,
+        Code::BackTab => {
+            modifiers.shift = true;
+            KeyCode::Tab
+        }
+        Code::Delete => KeyCode::Delete,
+        Code::Insert => KeyCode::Insert,
+        Code::F(n) => KeyCode::F(n),
+        Code::Esc => KeyCode::Escape,
+        Code::Null => return None,
+    };
 
-    let tabs = " ".repeat(4);
+    Some(Key::new(code, modifiers))
+}
+
+/// Keep the latest message from the context, clearing it once the command line opens
+fn update_message(ctx: &mut Context, message: &mut Option<Message>) {
+    if let Some(latest) = ctx.drain_messages().last() {
+        *message = Some(latest);
+    }
+    if let Mode::Command(_) = ctx.mode {
+        *message = None;
+    }
+}
+
+/// Give the windows all of the screen but the command line, and the tabline when there is
+/// more than one tab page
+fn set_window_area(ctx: &mut Context, (width, height): (u16, u16)) {
+    let tabline = usize::from(ctx.tab_pages.len() > 1);
+    ctx.set_window_area(Rect {
+        x: 0,
+        y: tabline,
+        width: width as usize,
+        height: (height as usize).saturating_sub(1 + tabline),
+    });
+}
 
-    let mut buffer_edit =
-        "Testing One Two\nThree Four\n\tFive\n\n\t\tLorem Ipsum Dolor\n\t\tSit Amet"
-            .replace('\t', &tabs);
-    let mut buffer_command = Default::default();
-    let mut buffer_search = Default::default();
+fn main() -> Result<(), Box<dyn Error>> {
+    let running = Rc::new(AtomicBool::new(true));
+    // Set by `:w`, as the command callback can't reach the buffer to mark it saved
+    let saved = Rc::new(AtomicBool::new(false));
+    // Set by `:wq` and `:x`, which close the current window or quit if it is the last one
+    let closing = Rc::new(AtomicBool::new(false));
 
-    let mut ctx = Context::new(&mut buffer_edit, &mut buffer_command, &mut buffer_search)
+    let mut ctx = Context::new()
+        .with_text(Rope::from_str(
+            "Testing One Two\nThree Four\n\tFive\n\n\t\tLorem Ipsum Dolor\n\t\tSit Amet",
+        ))
         .with_command_callback({
             let mut stdout = stdout();
             let running = running.clone();
-            move |command| {
-                match command {
-                    "write" | "w" => write!(stdout, "Write").unwrap(),
-                    "edit" | "e" => write!(stdout, "Edit").unwrap(),
-                    "quit" | "q" => running.store(false, Ordering::Relaxed),
-                    _ => (),
+            let saved = saved.clone();
+            let closing = closing.clone();
+            move |line| {
+                let Ok((_, command)) = ex_command(line) else {
+                    return;
+                };
+                if command.is("write", 1) || command.is("wq", 2) || command.is("xit", 1) {
+                    write!(stdout, "Write").unwrap();
+                    saved.store(true, Ordering::Relaxed);
+                }
+                if command.is("edit", 1) {
+                    write!(stdout, "Edit").unwrap();
+                }
+                // Quitting with unsaved changes or other windows open is handled by vimbed,
+                // so `:q` only reaches here to quit
+                if command.is("quit", 1) {
+                    running.store(false, Ordering::Relaxed);
+                }
+                if command.is("wq", 2) || command.is("xit", 1) {
+                    closing.store(true, Ordering::Relaxed);
                 }
                 stdout.flush().unwrap();
             }
         });
 
+    ctx.options.set("tabstop", 4i64)?;
+    ctx.options.set("shiftwidth", 4i64)?;
+
     let (mut width, mut height) = terminal::size()?;
 
     let mut so = stdout();
@@ -109,62 +195,62 @@ fn main() -> Result<(), Box<dyn Error>> {
         );
     }));
 
-    let mut input_buffer = String::default();
+    // Most recent message, shown until the command line is opened
+    let mut message: Option<Message> = None;
 
-    render(&mut so, &ctx, &input_buffer, (width, height))?;
+    set_window_area(&mut ctx, (width, height));
+    render(&mut so, &ctx, message.as_ref(), (width, height))?;
 
     loop {
         if !running.load(Ordering::Relaxed) {
             break;
         }
 
-        if poll(Duration::default())? {
-            match read()? {
-                Event::Key(event) => match event {
-                    crossterm::event::KeyEvent {
-                        code: KeyCode::Char('c'),
-                        modifiers: crossterm::event::KeyModifiers::CONTROL,
-                    } => break,
-                    crossterm::event::KeyEvent { code, .. } => {
-                        let input: Cow<'static, str> = match code {
-                            KeyCode::Char(c) => c.to_string().into(),
-                            KeyCode::Backspace => COMMAND_BACKSPACE.into(),
-                            KeyCode::Enter => COMMAND_CARRIAGE_RETURN.into(),
-                            KeyCode::F(n) => format!("<F{}>", n).into(),
-                            KeyCode::Esc => COMMAND_ESCAPE.into(),
-                            KeyCode::Delete => COMMAND_DELETE.into(),
-                            KeyCode::Left => COMMAND_LEFT.into(),
-                            KeyCode::Right => COMMAND_RIGHT.into(),
-                            KeyCode::Up => COMMAND_UP.into(),
-                            KeyCode::Down => COMMAND_DOWN.into(),
-                            KeyCode::Home => COMMAND_HOME.into(),
-                            KeyCode::End => COMMAND_END.into(),
-                            _ => continue,
-                        };
-
-                        input_buffer += &input;
-
-                        match ctx.input_str(&input_buffer) {
-                            Ok(_) => {
-                                input_buffer.clear();
-                            }
-                            Err(e) => match e {
-                                NomErr::Incomplete(_) => (),              // Incomplete input, do nothing
-                                NomErr::Error(_) => input_buffer.clear(), // Unhandled input, discard
-                                NomErr::Failure(e) => panic!("{}", e), // Unrecoverable error, panic
-                            },
-                        };
-                    }
-                },
-                Event::Resize(w, h) => {
-                    width = w;
-                    height = h;
+        // Wake in time to resolve keys held for a longer mapping
+        let deadline = ctx.next_tick();
+        let timeout = deadline.map_or(Duration::from_millis(100), |deadline| {
+            deadline.saturating_duration_since(Instant::now())
+        });
+
+        if !poll(timeout)? {
+            if deadline.is_some() {
+                ctx.tick(Instant::now());
+                update_message(&mut ctx, &mut message);
+                set_window_area(&mut ctx, (width, height));
+                render(&mut so, &ctx, message.as_ref(), (width, height))?;
+            }
+            continue;
+        }
+
+        match read()? {
+            Event::Key(event) => match key(event) {
+                None => continue,
+                Some(key) if key == Key::ctrl('c') => break,
+                Some(key) => {
+                    ctx.input_key(key, Instant::now());
                 }
-                _ => (),
+            },
+            Event::Resize(w, h) => {
+                width = w;
+                height = h;
             }
+            _ => (),
+        }
 
-            render(&mut so, &ctx, &input_buffer, (width, height))?;
+        if saved.swap(false, Ordering::Relaxed) {
+            ctx.buffer_edit.set_saved();
         }
+        if closing.swap(false, Ordering::Relaxed) {
+            if ctx.windows.len() > 1 || ctx.tab_pages.len() > 1 {
+                ctx.close_window(ctx.windows.current());
+            } else {
+                break;
+            }
+        }
+
+        update_message(&mut ctx, &mut message);
+        set_window_area(&mut ctx, (width, height));
+        render(&mut so, &ctx, message.as_ref(), (width, height))?;
     }
 
     finalize()?;
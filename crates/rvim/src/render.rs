@@ -2,87 +2,170 @@ use std::io::{Stdout, Write};
 
 use crossterm::{
     cursor::{self, CursorShape},
-    terminal, QueueableCommand, Result as CrosstermResult,
+    style, terminal, QueueableCommand, Result as CrosstermResult,
 };
 
 use vimbed::{
     char_len::CharLen,
     context::{BufferId, Context},
-    mode::{CommandMode, Mode},
+    grapheme,
+    message::Message,
+    mode::{self, CommandMode, Mode},
+    tab::TabSettings,
 };
 
 pub fn render(
     stdout: &mut Stdout,
     ctx: &Context,
-    input_buffer: &String,
+    message: Option<&Message>,
     (width, height): (u16, u16),
 ) -> CrosstermResult<()> {
     // Clear
     stdout.queue(terminal::Clear(terminal::ClearType::All))?;
 
-    let mut last_line = 0;
-
     let buffer_edit = ctx.buffer(BufferId::Edit);
     let buffer_command = ctx.buffer(BufferId::Command);
     let buffer_search = ctx.buffer(BufferId::Search);
 
-    // Draw text
-    for (line, text) in buffer_edit.as_str().split('\n').enumerate() {
-        stdout.queue(cursor::MoveTo(0, line as u16))?;
-        let mut text = text.to_string();
-        text.truncate(width as usize);
-        write!(stdout, " {:4} {}", line + 1, text)?;
-        last_line = line;
-    }
-
-    // Fill remaining buffer with markers
-    for line in last_line.saturating_add(1)..height.saturating_sub(2) as usize {
-        stdout.queue(cursor::MoveTo(0, line as u16))?;
-        write!(stdout, "~")?;
+    // Draw tabline, with the current tab page's label highlighted
+    if ctx.tab_pages.len() > 1 {
+        stdout.queue(cursor::MoveTo(0, 0))?;
+        let mut used = 0;
+        for label in ctx.tabline() {
+            let text = format!(" {} ", label);
+            let text = grapheme::truncate(&text, (width as usize).saturating_sub(used));
+            used += text.char_len();
+            if label.current {
+                write!(stdout, "{}", text)?;
+            } else {
+                stdout.queue(style::SetAttribute(style::Attribute::Reverse))?;
+                write!(stdout, "{}", text)?;
+                stdout.queue(style::SetAttribute(style::Attribute::Reset))?;
+            }
+        }
+        stdout.queue(style::SetAttribute(style::Attribute::Reverse))?;
+        write!(
+            stdout,
+            "{}",
+            " ".repeat((width as usize).saturating_sub(used))
+        )?;
+        stdout.queue(style::SetAttribute(style::Attribute::Reset))?;
     }
 
-    // Draw status bar
-    let left_status = format!("{} >", ctx.mode,);
-    let right_status = format!(
-        "< {:3} < {:3}% < {:3}:{:2}",
-        buffer_edit.cursor_offset(),
-        ((buffer_edit.cursor.row as f32 / (buffer_edit.lines().count() - 1) as f32) * 100.0)
-            as usize,
-        buffer_edit.cursor.row + 1,
-        buffer_edit.cursor.column + 1,
-    );
+    let current = ctx.windows.current();
+    for (id, rect) in ctx.windows.rects() {
+        let Some(window) = ctx.windows.get(id) else {
+            continue;
+        };
+        let Some(buffer) = ctx.listed_buffer(window.buffer) else {
+            continue;
+        };
+        let text_height = rect.height.saturating_sub(1);
+        // The edit buffer's settings follow options set since it was last edited
+        let tabs = if window.buffer == ctx.buffers.current() {
+            TabSettings::from(&ctx.options)
+        } else {
+            buffer.tabs
+        };
+
+        // Draw the lines that fit above the window's status line, and markers past the end
+        let mut lines = buffer.lines_from(window.top.min(buffer.line_count()));
+        for row in 0..text_height {
+            stdout.queue(cursor::MoveTo(rect.x as u16, (rect.y + row) as u16))?;
+            let line = match lines.next() {
+                Some(text) => {
+                    let text = tabs.expand(&text);
+                    format!(" {:4} {}", window.top + row + 1, text)
+                }
+                None => "~".into(),
+            };
+            write!(stdout, "{}", grapheme::truncate(&line, rect.width))?;
+        }
 
-    stdout.queue(cursor::MoveTo(0, height - 2))?;
-    write!(stdout, "{}", left_status)?;
+        // Draw status line, with the mode and cursor position in the current window's
+        let name = ctx
+            .buffers
+            .get(window.buffer)
+            .map_or("[No Name]", |entry| entry.display_name());
+        let modified = if buffer.modified() { " [+]" } else { "" };
+        let (left_status, right_status) = if id == current {
+            // Vim-style column, with the virtual column when tabs make them differ
+            let screen_column = ctx.cursor_screen_column() + 1;
+            let column = if screen_column == buffer_edit.cursor.column + 1 {
+                format!("{}", screen_column)
+            } else {
+                format!("{}-{}", buffer_edit.cursor.column + 1, screen_column)
+            };
+            (
+                format!("{} {}{} >", ctx.mode, name, modified),
+                format!(
+                    "< {:3} < {:3}% < {:3}:{:2}",
+                    buffer_edit.cursor_offset(),
+                    ((buffer_edit.cursor.row as f32 / (buffer_edit.line_count() - 1) as f32)
+                        * 100.0) as usize,
+                    buffer_edit.cursor.row + 1,
+                    column,
+                ),
+            )
+        } else {
+            (
+                format!("{}{}", name, modified),
+                format!("{:3}:{:2}", window.cursor.row + 1, window.cursor.column + 1),
+            )
+        };
+        let padding = rect
+            .width
+            .saturating_sub(left_status.char_len() + right_status.char_len());
+        let status = format!("{}{}{}", left_status, " ".repeat(padding), right_status);
+
+        stdout.queue(cursor::MoveTo(rect.x as u16, (rect.y + text_height) as u16))?;
+        if id == current {
+            stdout.queue(style::SetAttribute(style::Attribute::Reverse))?;
+        }
+        write!(stdout, "{}", grapheme::truncate(&status, rect.width))?;
+        stdout.queue(style::SetAttribute(style::Attribute::Reset))?;
+    }
 
-    stdout.queue(cursor::MoveTo(
-        width - right_status.char_len() as u16,
-        height - 2,
-    ))?;
-    write!(stdout, "{}", right_status)?;
+    // Draw separators between side by side windows
+    for separator in ctx.windows.separators() {
+        for row in 0..separator.height {
+            stdout.queue(cursor::MoveTo(
+                separator.x as u16,
+                (separator.y + row) as u16,
+            ))?;
+            write!(stdout, "|")?;
+        }
+    }
 
     // Draw command bar
     stdout.queue(cursor::MoveTo(0, height - 1))?;
-    write!(
-        stdout,
-        "{}{}",
-        match ctx.mode {
-            Mode::Command(CommandMode::Command) => ":",
-            Mode::Command(CommandMode::Search) => "/",
-            _ => "",
-        },
-        match ctx.mode {
-            Mode::Command(CommandMode::Command) => buffer_command.cursor_line(),
-            Mode::Command(CommandMode::Search) => buffer_search.cursor_line(),
-            _ => "",
-        }
-    )?;
+    if let Some(substitute_confirm) = &ctx.substitute_confirm {
+        write!(stdout, "{}", substitute_confirm.prompt())?;
+    } else if let (Mode::Normal, Some(message)) = (ctx.mode, message) {
+        write!(stdout, "{}", message)?;
+    } else {
+        write!(
+            stdout,
+            "{}{}",
+            match ctx.mode {
+                Mode::Command(CommandMode::Command) => ":",
+                Mode::Command(CommandMode::Search) => "/",
+                _ => "",
+            },
+            match ctx.mode {
+                Mode::Command(CommandMode::Command) => buffer_command.cursor_line(),
+                Mode::Command(CommandMode::Search) => buffer_search.cursor_line(),
+                _ => "".into(),
+            }
+        )?;
+    }
 
+    let showcmd = ctx.showcmd();
     stdout.queue(cursor::MoveTo(
-        width - input_buffer.char_len() as u16 - 9,
+        width - showcmd.char_len() as u16 - 9,
         height - 1,
     ))?;
-    write!(stdout, "{}", input_buffer)?;
+    write!(stdout, "{}", showcmd)?;
 
     // Move cursor to context position and set shape
     match ctx.mode {
@@ -92,29 +175,27 @@ pub fn render(
                 CommandMode::Search => ctx.buffer(BufferId::Search),
             };
 
-            stdout.queue(cursor::MoveTo(1 + buffer.cursor.column as u16, height - 1))?;
-
-            stdout.queue(cursor::SetCursorShape(
-                if buffer.cursor.column == buffer.lines().last().unwrap_or("").char_len() {
-                    CursorShape::Block
-                } else {
-                    CursorShape::Line
-                },
+            stdout.queue(cursor::MoveTo(
+                1 + buffer.cursor_virtual_column() as u16,
+                height - 1,
             ))?;
         }
         _ => {
+            let rect = ctx.windows.rect(current).unwrap_or_default();
+            let top = ctx.windows.get(current).map_or(0, |window| window.top);
             stdout.queue(cursor::MoveTo(
-                6 + buffer_edit.cursor.column as u16,
-                buffer_edit.cursor.row as u16,
+                (rect.x + 6 + ctx.cursor_screen_column()) as u16,
+                (rect.y + buffer_edit.cursor.row.saturating_sub(top)) as u16,
             ))?;
-
-            stdout.queue(cursor::SetCursorShape(match ctx.mode {
-                Mode::Insert => CursorShape::Line,
-                _ => CursorShape::Block,
-            }))?;
         }
     }
 
+    stdout.queue(cursor::SetCursorShape(match ctx.cursor_shape() {
+        mode::CursorShape::Block => CursorShape::Block,
+        mode::CursorShape::Bar => CursorShape::Line,
+        mode::CursorShape::Underline => CursorShape::UnderScore,
+    }))?;
+
     // Flush commands
     stdout.flush()
 }
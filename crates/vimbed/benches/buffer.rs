@@ -0,0 +1,108 @@
+use std::{hint::black_box, num::NonZeroUsize};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use vimbed::{
+    buffer::Buffer,
+    motion::{
+        left_right::LeftRightMotion, up_down::UpDownMotion, word::WordMotion, Motion, MotionVariant,
+    },
+    operator::{Operator, OperatorVariant},
+    ropey::Rope,
+    storage::TextStorage,
+};
+
+const ONE: NonZeroUsize = NonZeroUsize::MIN;
+
+/// Line counts of the buffers measured
+const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+fn text(lines: usize) -> String {
+    (0..lines)
+        .map(|i| format!("line {} of a log file with several words\n", i))
+        .collect()
+}
+
+/// Buffer of `text` with the cursor on its middle line
+fn buffer<S: TextStorage>(text: S) -> Buffer<S> {
+    let mut buffer = Buffer::from(text);
+    buffer.set_cursor_line(buffer.line_count() / 2);
+    buffer
+}
+
+fn motion(variant: impl Into<MotionVariant>) -> Motion {
+    Motion::new_one(variant.into())
+}
+
+/// Run `f` on a buffer of each size, held in both a `String` and a `Rope`
+fn bench_storages(
+    c: &mut Criterion,
+    name: &str,
+    mut f: impl FnMut(&mut Buffer<String>),
+    mut g: impl FnMut(&mut Buffer<Rope>),
+) {
+    let mut group = c.benchmark_group(name);
+    for lines in SIZES {
+        let text = text(lines);
+
+        let mut string = buffer(text.clone());
+        group.bench_function(BenchmarkId::new("string", lines), |b| {
+            b.iter(|| f(black_box(&mut string)))
+        });
+
+        let mut rope = buffer(Rope::from_str(&text));
+        group.bench_function(BenchmarkId::new("rope", lines), |b| {
+            b.iter(|| g(black_box(&mut rope)))
+        });
+    }
+    group.finish();
+}
+
+/// Down then up again, so the cursor stays in the middle of the buffer
+fn down_up<S: TextStorage>(buffer: &mut Buffer<S>) {
+    buffer.motion(motion(UpDownMotion::Down(ONE)), true);
+    buffer.motion(motion(UpDownMotion::Up(ONE)), true);
+}
+
+fn word<S: TextStorage>(buffer: &mut Buffer<S>) {
+    buffer.motion(motion(WordMotion::Forward(ONE)), true);
+    buffer.motion(motion(WordMotion::Backward(ONE)), true);
+}
+
+/// Insert a character and undo it, leaving the text as it was
+fn insert<S: TextStorage>(buffer: &mut Buffer<S>) {
+    buffer.insert("x");
+    buffer.commit_undo();
+    buffer.undo();
+}
+
+/// Delete a character and undo it, leaving the text as it was
+fn delete<S: TextStorage>(buffer: &mut Buffer<S>) {
+    buffer.operator(Operator::new_one(
+        OperatorVariant::Delete,
+        motion(LeftRightMotion::Right(ONE)),
+    ));
+    buffer.commit_undo();
+    buffer.undo();
+}
+
+/// Delete the cursor line and undo it, leaving the text as it was
+fn delete_line<S: TextStorage>(buffer: &mut Buffer<S>) {
+    buffer.delete_lines(buffer.cursor.row, buffer.cursor.row);
+    buffer.commit_undo();
+    buffer.undo();
+}
+
+fn motions(c: &mut Criterion) {
+    bench_storages(c, "down_up", down_up, down_up);
+    bench_storages(c, "word", word, word);
+}
+
+fn edits(c: &mut Criterion) {
+    bench_storages(c, "insert", insert, insert);
+    bench_storages(c, "delete", delete, delete);
+    bench_storages(c, "delete_line", delete_line, delete_line);
+}
+
+criterion_group!(benches, motions, edits);
+criterion_main!(benches);
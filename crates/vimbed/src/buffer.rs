@@ -1,121 +1,755 @@
-use std::ops::{Deref, DerefMut};
+use std::{borrow::Cow, ops::Range};
+
+use ropey::Rope;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
-    char_len::CharLen,
-    motion::{left_right::LeftRightMotion, up_down::UpDownMotion, Motion, MotionTrait},
+    event::Change,
+    grapheme,
+    line_index::LineIndex,
+    mark::{LineEdit, LineMarks},
+    motion::{Motion, MotionTrait},
     operator::*,
+    register::Register,
+    storage::TextStorage,
+    tab::TabSettings,
+    undo::{Edit, History},
 };
 
+#[cfg(feature = "serde")]
+use crate::session::BufferSession;
+
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cursor {
+    /// Byte offset into the line, always on a grapheme cluster boundary
     pub column: usize,
     pub row: usize,
+    /// Virtual column that vertical motions try to keep the cursor at
     pub target_column: usize,
 }
 
+/// Replacement of a byte range made outside the editor, as by a formatter or collaborator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// How a batch of external edits enters the undo history
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UndoStep {
+    /// Undo the batch on its own
+    #[default]
+    Separate,
+    /// Undo the batch together with the previous change, like `:undojoin`
+    Join,
+}
+
+/// Text of a buffer, either owned or borrowed from the embedder
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Buffer<'a> {
-    pub buffer: &'a mut String,
-    pub cursor: Cursor,
+pub enum BufferText<'a> {
+    Owned(String),
+    Borrowed(&'a mut String),
+    Rope(Rope),
+}
+
+impl BufferText<'_> {
+    fn storage(&self) -> &dyn TextStorage {
+        match self {
+            BufferText::Owned(text) => text,
+            BufferText::Borrowed(text) => *text,
+            BufferText::Rope(text) => text,
+        }
+    }
+
+    fn storage_mut(&mut self) -> &mut dyn TextStorage {
+        match self {
+            BufferText::Owned(text) => text,
+            BufferText::Borrowed(text) => *text,
+            BufferText::Rope(text) => text,
+        }
+    }
+}
+
+impl TextStorage for BufferText<'_> {
+    fn len(&self) -> usize {
+        self.storage().len()
+    }
+
+    fn line_count(&self) -> usize {
+        self.storage().line_count()
+    }
+
+    fn line(&self, row: usize) -> Option<Cow<'_, str>> {
+        self.storage().line(row)
+    }
+
+    fn lines_from(&self, row: usize) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        self.storage().lines_from(row)
+    }
+
+    fn line_offset(&self, row: usize) -> usize {
+        self.storage().line_offset(row)
+    }
+
+    fn offset_line(&self, offset: usize) -> usize {
+        self.storage().offset_line(offset)
+    }
+
+    fn slice(&self, range: Range<usize>) -> Cow<'_, str> {
+        self.storage().slice(range)
+    }
+
+    fn splice(&mut self, range: Range<usize>, text: &str) {
+        self.storage_mut().splice(range, text)
+    }
+
+    fn is_char_boundary(&self, offset: usize) -> bool {
+        self.storage().is_char_boundary(offset)
+    }
+}
+
+impl<'a> From<&'a mut String> for BufferText<'a> {
+    fn from(text: &'a mut String) -> Self {
+        BufferText::Borrowed(text)
+    }
 }
 
-impl<'a> Deref for Buffer<'a> {
-    type Target = String;
+impl From<String> for BufferText<'_> {
+    fn from(text: String) -> Self {
+        BufferText::Owned(text)
+    }
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.buffer
+impl From<&str> for BufferText<'_> {
+    fn from(text: &str) -> Self {
+        BufferText::Owned(text.to_owned())
     }
 }
 
-impl<'a> DerefMut for Buffer<'a> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.buffer
+impl From<Rope> for BufferText<'_> {
+    fn from(text: Rope) -> Self {
+        BufferText::Rope(text)
     }
 }
 
-impl<'a> From<&'a mut String> for Buffer<'a> {
-    fn from(buffer: &'a mut String) -> Self {
+/// Text being edited, with its cursor, marks and undo history
+///
+/// Every motion and operator goes through the [`TextStorage`] the buffer is generic over,
+/// so the text may equally be a [`String`] or a [`Rope`].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Buffer<S: TextStorage = String> {
+    pub text: S,
+    pub cursor: Cursor,
+    pub line_marks: LineMarks,
+    pub history: History,
+    pub tabs: TabSettings,
+    /// Kept in step with `text` by every edit made through the buffer
+    line_index: LineIndex,
+    /// Changes made since they were last drained, if they are being tracked
+    changes: Option<Vec<Change>>,
+    /// Number of changes made to the text, including undo and redo
+    change_tick: u64,
+    /// History state last saved, if undo and redo can still reach it
+    saved: Option<u64>,
+    modified: bool,
+}
+
+impl<S: TextStorage> From<S> for Buffer<S> {
+    fn from(text: S) -> Self {
         Buffer {
-            buffer,
+            line_index: LineIndex::new(&text),
+            text,
             cursor: Default::default(),
+            line_marks: Default::default(),
+            history: Default::default(),
+            tabs: Default::default(),
+            changes: None,
+            change_tick: 0,
+            saved: Some(0),
+            modified: false,
         }
     }
 }
 
-impl<'a> Buffer<'a> {
-    fn lines(&self) -> impl Iterator<Item = &str> + Clone {
-        self.split('\n')
+/// Whether the word motions treat byte `column` of a line as the start of a word
+///
+/// Words are separated by spaces, and the line break ending an empty line or one
+/// with trailing spaces counts as a word of its own.
+fn is_word_head(line: &str, column: usize, last_line: bool) -> bool {
+    let bytes = line.as_bytes();
+    let after_space = column == 0 || bytes[column - 1] == b' ';
+    match bytes.get(column) {
+        Some(&c) => c != b' ' && after_space,
+        None => !last_line && after_space,
+    }
+}
+
+impl<S: TextStorage> Buffer<S> {
+    pub fn lines(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        self.lines_from(0)
+    }
+
+    /// Lines from `row` onward, without stepping through the ones before it
+    pub fn lines_from(&self, row: usize) -> impl Iterator<Item = Cow<'_, str>> {
+        (row..self.line_count()).map(|row| self.line(row).unwrap_or_default())
+    }
+
+    pub fn line(&self, line: usize) -> Option<Cow<'_, str>> {
+        (line < self.line_count()).then(|| {
+            self.text
+                .slice(self.line_index.line_offset(line)..self.line_index.line_end(line))
+        })
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_index.line_count()
+    }
+
+    /// Byte offset of the first character of the given line
+    pub fn line_offset(&self, line: usize) -> usize {
+        self.line_index.line_offset(line)
+    }
+
+    /// Virtual column at which the cluster at byte `column` of `row` is displayed,
+    /// expanding tabs and counting wide characters as two cells
+    pub fn virtual_column(&self, row: usize, column: usize) -> usize {
+        let line = self.line(row).unwrap_or_default();
+        let end = grapheme::floor_boundary(&line, column);
+        self.tabs.advance(&line[..end], 0)
+    }
+
+    /// Byte column of the cluster in `row` displayed over virtual column `virtual_column`,
+    /// or the line length if it lies past the end
+    pub fn column_at_virtual(&self, row: usize, virtual_column: usize) -> usize {
+        let line = self.line(row).unwrap_or_default();
+        let mut current = 0;
+        for (column, g) in line.grapheme_indices(true) {
+            current += self.tabs.width(g, current);
+            if current > virtual_column {
+                return column;
+            }
+        }
+        line.len()
+    }
+
+    /// Byte offset of the cluster following `offset`, stepping over line breaks
+    pub fn next_offset(&self, offset: usize) -> usize {
+        let (column, row) = self.offset_position(offset);
+        let line = self.line(row).unwrap_or_default();
+        if column < line.len() {
+            offset - column + grapheme::next_boundary(&line, column)
+        } else {
+            (offset + 1).min(self.text.len())
+        }
+    }
+
+    /// Byte offset of the cluster preceding `offset`, stepping over line breaks
+    pub fn prev_offset(&self, offset: usize) -> usize {
+        let (column, row) = self.offset_position(offset);
+        let line = self.line(row).unwrap_or_default();
+        if column > 0 {
+            offset - column + grapheme::prev_boundary(&line, column)
+        } else {
+            offset.saturating_sub(1)
+        }
+    }
+
+    /// Virtual column of the cursor
+    pub fn cursor_virtual_column(&self) -> usize {
+        self.virtual_column(self.cursor.row, self.cursor.column)
     }
 
-    fn line(&self, line: usize) -> Option<&str> {
-        self.lines().skip(line).next()
+    /// Keep the cursor on a character rather than past the end of its line, as in normal mode
+    pub fn clamp_cursor(&mut self) {
+        let last = grapheme::last_boundary(&self.cursor_line());
+        if self.cursor.column > last {
+            self.cursor.column = last;
+        }
     }
 
-    fn words(&self) -> impl Iterator<Item = &str> + Clone {
-        self.split_inclusive(' ')
-            .flat_map(|w| w.split_inclusive('\n'))
+    /// Move the cursor to `column` in the current line, making it the target for vertical motions
+    pub fn set_column(&mut self, column: usize) {
+        self.cursor.column = column;
+        self.cursor.target_column = self.cursor_virtual_column();
     }
 
-    pub fn word_offsets(&self) -> Vec<usize> {
-        let mut word_offsets = vec![];
-        let mut word_head = 0usize;
-        for word in self.words() {
-            if !word.starts_with(' ') && (!word.starts_with('\n') || word == "\n") {
-                word_offsets.push(word_head);
+    /// Byte offset of the first word start after `offset`
+    pub fn next_word(&self, offset: usize) -> Option<usize> {
+        let (column, row) = self.offset_position(offset);
+        let last_row = self.line_count() - 1;
+        let mut line_offset = self.line_offset(row);
+
+        for (i, line) in self.lines_from(row).enumerate() {
+            let start = if i == 0 { column + 1 } else { 0 };
+            let last_line = row + i == last_row;
+            if let Some(column) = (start..=line.len()).find(|&c| is_word_head(&line, c, last_line))
+            {
+                return Some(line_offset + column);
             }
-            word_head += word.char_len();
+            line_offset += line.len() + 1;
         }
-        word_offsets
+        None
     }
 
-    pub fn cursor_word_offset(&self) -> usize {
-        let word_offsets = self.word_offsets();
-        word_offsets
-            .iter()
-            .position(|i| *i > self.cursor_offset())
-            .unwrap_or_else(|| *word_offsets.last().unwrap())
-            .saturating_sub(1)
+    /// Byte offset of the last word start before `offset`
+    pub fn prev_word(&self, offset: usize) -> Option<usize> {
+        let (column, start_row) = self.offset_position(offset);
+        let last_row = self.line_count() - 1;
+
+        for row in (0..=start_row).rev() {
+            let line = self.line(row).unwrap_or_default();
+            let end = if row == start_row {
+                column
+            } else {
+                line.len() + 1
+            };
+            let last_line = row == last_row;
+            if let Some(column) = (0..end).rev().find(|&c| is_word_head(&line, c, last_line)) {
+                return Some(self.line_offset(row) + column);
+            }
+        }
+        None
     }
 
-    pub fn cursor_line(&self) -> &str {
-        self.line(self.cursor.row).unwrap()
+    pub fn cursor_line(&self) -> Cow<'_, str> {
+        self.line(self.cursor.row).unwrap_or_default()
     }
 
+    /// Byte offset of the cursor in the buffer
     pub fn cursor_offset(&self) -> usize {
-        let chars_before = self
-            .lines()
-            .take(self.cursor.row)
-            .map(|line| line.char_len() + 1)
-            .sum::<usize>();
-        let chars_current = self.cursor_line().char_len();
-        chars_before + self.cursor.column.min(chars_current)
+        let line_offset = self.line_offset(self.cursor.row);
+        line_offset + grapheme::floor_boundary(&self.cursor_line(), self.cursor.column)
     }
 
+    /// Byte column and row of a byte offset, rounded down to a char boundary
     pub fn offset_position(&self, offset: usize) -> (usize, usize) {
-        let y = self[0..offset].chars().filter(|b| *b == '\n').count();
+        let offset = self.floor_offset(offset);
+        let row = self.line_index.offset_line(offset);
+        (offset - self.line_offset(row), row)
+    }
+
+    /// Round a byte offset down to a char boundary, clamping it to the text
+    fn floor_offset(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+
+    /// Replace the given byte range with `text`, recording the change in the undo history
+    pub fn splice(&mut self, range: Range<usize>, text: &str) {
+        let start = self.floor_offset(range.start);
+        let range = start..self.floor_offset(range.end).max(start);
+        if range.is_empty() && text.is_empty() {
+            return;
+        }
+        let removed = self.text.slice(range.clone()).into_owned();
+        let undo_group = self.history.record(
+            Edit {
+                offset: range.start,
+                removed,
+                inserted: text.to_owned(),
+            },
+            self.cursor,
+        );
+        if self.saved == Some(undo_group) {
+            // The saved change is being extended, so undo can no longer return to it
+            self.saved = None;
+        }
+        self.modified = true;
+
+        self.replace(range, text, undo_group);
+    }
+
+    fn replace(&mut self, range: Range<usize>, text: &str, undo_group: u64) {
+        let (start_column, start_row) = self.offset_position(range.start);
+        let (end_column, end_row) = self.offset_position(range.end);
+        let inserted = text.matches('\n').count();
+        self.change_tick += 1;
+
+        if let Some(changes) = &mut self.changes {
+            changes.push(Change {
+                offset: range.start,
+                removed: self.text.slice(range.clone()).into_owned(),
+                inserted: text.to_owned(),
+                line_delta: inserted as isize - (end_row - start_row) as isize,
+                cursor_before: self.cursor,
+                cursor_after: self.cursor,
+                undo_group,
+            });
+        }
+
+        let line_edit =
+            if start_column == 0 && end_column == 0 && (text.is_empty() || text.ends_with('\n')) {
+                // Whole lines replaced
+                LineEdit {
+                    start: start_row,
+                    removed: end_row - start_row,
+                    inserted,
+                }
+            } else {
+                // The first line is kept, any others are joined onto it
+                LineEdit {
+                    start: start_row + 1,
+                    removed: end_row - start_row,
+                    inserted,
+                }
+            };
+
+        self.text.splice(range.clone(), text);
+        self.line_index.splice(range, text);
+
+        self.line_marks.adjust(&line_edit);
+    }
+
+    /// Text of the given inclusive line range, with each line terminated by a newline
+    pub fn lines_text(&self, start: usize, end: usize) -> String {
+        self.lines_from(start)
+            .take(end + 1 - start)
+            .map(|line| line.into_owned() + "\n")
+            .collect()
+    }
+
+    /// Delete an inclusive range of lines, returning their text
+    pub fn delete_lines(&mut self, start: usize, end: usize) -> String {
+        let text = self.lines_text(start, end);
+
+        if end + 1 < self.line_count() {
+            self.splice(self.line_offset(start)..self.line_offset(end + 1), "");
+        } else if start > 0 {
+            // Removing the last line takes the preceding newline with it
+            self.splice(self.line_offset(start) - 1..self.text.len(), "");
+        } else {
+            self.splice(0..self.text.len(), "");
+        }
+
+        text
+    }
+
+    /// Insert newline-terminated lines of text before `row`, or after the last line
+    pub fn insert_lines(&mut self, row: usize, text: &str) {
+        if row < self.line_count() {
+            let offset = self.line_offset(row);
+            self.splice(offset..offset, text);
+        } else {
+            let offset = self.text.len();
+            let text = "\n".to_owned() + text.strip_suffix('\n').unwrap_or(text);
+            self.splice(offset..offset, &text);
+        }
+    }
+
+    /// Put register text `count` times after the cursor, or before it with `before`
+    ///
+    /// Lines go below or above the cursor line, leaving the cursor on the first of them.
+    /// Other text leaves the cursor on its last character, or its start if it spans lines.
+    pub fn put(&mut self, register: &Register, before: bool, count: usize) {
+        let text = register.text.repeat(count);
+        if text.is_empty() {
+            return;
+        }
+
+        if register.linewise {
+            let row = if before {
+                self.cursor.row
+            } else {
+                self.cursor.row + 1
+            };
+            self.insert_lines(row, &text);
+            self.set_cursor_line(row);
+            return;
+        }
+
+        let line = self.cursor_line();
+        let column = if before || line.is_empty() {
+            self.cursor.column
+        } else {
+            grapheme::next_boundary(&line, self.cursor.column)
+        };
+        let offset = self.line_offset(self.cursor.row) + column;
+        self.splice(offset..offset, &text);
+
+        let end = if text.contains('\n') {
+            offset
+        } else {
+            self.prev_offset(offset + text.len())
+        };
+        let (column, row) = self.offset_position(end);
+        self.cursor.row = row;
+        self.set_column(column);
+    }
+
+    /// Byte column of the first non-whitespace character in a line
+    pub fn first_non_blank(&self, row: usize) -> usize {
+        self.line(row)
+            .unwrap_or_default()
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or_default()
+    }
+
+    /// Move the cursor to the first non-blank character of a line
+    pub fn set_cursor_line(&mut self, row: usize) {
+        let row = row.min(self.line_count().saturating_sub(1));
+        self.cursor.row = row;
+        self.set_column(self.first_non_blank(row));
+    }
+
+    /// Replace the whole text, as when loading a file, discarding undo history and line marks
+    pub fn set_text(&mut self, text: &str) {
+        let len = self.text.len();
+        let undo_group = self.history.new_id();
+        self.replace(0..len, text, undo_group);
+        self.history.clear();
+        self.line_marks.clear();
+        self.set_saved();
+
+        self.cursor.row = self.cursor.row.min(self.line_count() - 1);
+        let line = self.line(self.cursor.row).unwrap_or_default();
+        self.cursor.column = grapheme::floor_boundary(&line, self.cursor.column);
+    }
+
+    /// Apply edits made outside the editor, keeping the cursor on the same text
+    ///
+    /// Edits are applied in order, the range of each being in the text left by the
+    /// previous ones. The cursor stays before text inserted at it, moves to the start
+    /// of a range replaced around it, and keeps its distance to any target column
+    /// past the end of its line.
+    pub fn apply_edits(&mut self, edits: &[ExternalEdit], undo: UndoStep) {
+        let virtual_column = self.cursor_virtual_column();
+        let mut offset = self.cursor_offset();
+
+        let joined = match undo {
+            UndoStep::Separate => {
+                self.history.commit();
+                false
+            }
+            UndoStep::Join => self.history.join(),
+        };
+
+        for edit in edits {
+            let start = self.floor_offset(edit.range.start);
+            let end = self.floor_offset(edit.range.end).max(start);
+            if offset >= end && offset > start {
+                offset = offset - (end - start) + edit.text.len();
+            } else if offset > start {
+                offset = start;
+            }
+            self.splice(start..end, &edit.text);
+        }
+
+        if undo == UndoStep::Separate || joined {
+            self.history.commit();
+        }
+
+        let (column, row) = self.offset_position(offset);
+        let column = grapheme::floor_boundary(&self.line(row).unwrap_or_default(), column);
+        self.cursor.row = row;
+        self.cursor.column = column;
+        let shift = self.cursor_virtual_column() as isize - virtual_column as isize;
+        self.cursor.target_column = self.cursor.target_column.saturating_add_signed(shift);
+    }
+
+    /// Start or stop keeping the changes made to the text, to be drained by listeners
+    pub fn track_changes(&mut self, track: bool) {
+        if track != self.changes.is_some() {
+            self.changes = track.then(Vec::new);
+        }
+    }
 
-        let x = self[0..offset].split('\n').last().unwrap().char_len();
+    /// Take the changes made since the last call, oldest first, if they are tracked
+    ///
+    /// Each change is given the current cursor as the one after it.
+    pub fn drain_changes(&mut self) -> impl Iterator<Item = Change> + '_ {
+        let cursor = self.cursor;
+        self.changes
+            .iter_mut()
+            .flat_map(|changes| changes.drain(..))
+            .map(move |change| Change {
+                cursor_after: cursor,
+                ..change
+            })
+    }
+
+    /// Number of changes made to the text, increasing with each edit, undo and redo
+    pub fn change_tick(&self) -> u64 {
+        self.change_tick
+    }
+
+    /// Whether the text changed since it was last saved, or loaded with `set_text`
+    pub fn modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Mark the text as saved, so it is unmodified until changed again or until undo
+    /// or redo leave this state
+    pub fn set_saved(&mut self) {
+        self.history.commit();
+        self.saved = Some(self.history.state());
+        self.modified = false;
+    }
+
+    /// Mark the text as changed since it was saved, until it is saved again
+    pub fn set_modified(&mut self) {
+        self.saved = None;
+        self.modified = true;
+    }
+
+    /// Save the text, cursor, marks and undo history
+    #[cfg(feature = "serde")]
+    pub fn session(&self) -> BufferSession {
+        BufferSession {
+            text: self.text.slice(0..self.text.len()).into_owned(),
+            cursor: self.cursor,
+            line_marks: self.line_marks.clone(),
+            history: self.history.clone(),
+            saved: self.saved,
+            modified: self.modified,
+        }
+    }
+
+    /// Restore saved text, cursor, marks and undo history
+    #[cfg(feature = "serde")]
+    pub fn restore(&mut self, session: BufferSession) {
+        self.set_text(&session.text);
+        self.history = session.history;
+        self.line_marks = session.line_marks;
+        self.saved = session.saved;
+        self.modified = session.modified;
+
+        let row = session.cursor.row.min(self.line_count() - 1);
+        let line = self.line(row).unwrap_or_default();
+        let column = grapheme::floor_boundary(&line, session.cursor.column);
+        self.cursor = Cursor {
+            column,
+            row,
+            ..session.cursor
+        };
+    }
+
+    /// Close the pending change so it undoes separately from later edits
+    pub fn commit_undo(&mut self) {
+        self.history.commit();
+    }
+
+    /// Revert the last change, returning false if there is none
+    pub fn undo(&mut self) -> bool {
+        let Some(group) = self.history.pop_undo() else {
+            return false;
+        };
+
+        for edit in group.edits.iter().rev() {
+            let end = edit.offset + edit.inserted.len();
+            self.replace(edit.offset..end, &edit.removed, group.id);
+        }
+
+        let row = group.cursor.row.min(self.line_count().saturating_sub(1));
+        self.cursor = group.cursor;
+        self.cursor.row = row;
+        let line = self.line(row).unwrap_or_default();
+        self.cursor.column = grapheme::floor_boundary(&line, self.cursor.column);
 
-        (x, y)
+        self.history.push_redo(group);
+        self.modified = self.saved != Some(self.history.state());
+        true
     }
 
-    pub fn insert(&mut self, text: &str, block_newline: bool) {
+    /// Reapply the last undone change, returning false if there is none
+    pub fn redo(&mut self) -> bool {
+        let Some(group) = self.history.pop_redo() else {
+            return false;
+        };
+
+        for edit in group.edits.iter() {
+            let end = edit.offset + edit.removed.len();
+            self.replace(edit.offset..end, &edit.inserted, group.id);
+        }
+
+        if let Some(edit) = group.edits.first() {
+            let (column, row) = self.offset_position(edit.offset);
+            self.cursor.row = row;
+            self.set_column(column);
+        }
+
+        self.history.push_undo(group);
+        self.modified = self.saved != Some(self.history.state());
+        true
+    }
+
+    /// Insert `text` at the cursor, leaving the cursor after it
+    pub fn insert(&mut self, text: &str) {
         let ofs = self.cursor_offset();
-        let (buffer_l, buffer_r) = self.buffer.split_at(ofs);
-        *self.buffer = buffer_l.to_owned() + text + buffer_r;
+        self.splice(ofs..ofs, text);
+
+        let (column, row) = self.offset_position(ofs + text.len());
+        self.cursor.row = row;
+        self.set_column(column);
+    }
 
-        if text == "\n" {
-            self.motion(
-                Motion::new_one(UpDownMotion::down(1).unwrap().into()),
-                block_newline,
-            );
-            self.motion(
-                Motion::new_one(LeftRightMotion::FirstCharacter.into()),
-                block_newline,
-            );
-        } else if let Some(motion) = LeftRightMotion::right(text.char_len()) {
-            self.motion(Motion::new_one(motion.into()), block_newline);
+    /// Insert `<Tab>`, as whitespace up to the next 'softtabstop' column
+    ///
+    /// With 'expandtab' only spaces are inserted, otherwise the whitespace before the
+    /// cursor is rewritten to use as many tabs as fit.
+    pub fn insert_tab(&mut self) {
+        let tabs = self.tabs;
+        if !tabs.expand_tab && tabs.soft_tab() == tabs.tab_stop {
+            self.insert("\t");
+            return;
         }
+
+        let column = self.cursor_virtual_column();
+        let soft_tab = tabs.soft_tab();
+        let target = (column / soft_tab + 1) * soft_tab;
+
+        let start = if tabs.expand_tab {
+            self.cursor.column
+        } else {
+            self.whitespace_before_cursor()
+        };
+        self.replace_before_cursor(start, target);
+    }
+
+    /// Delete before the cursor in insert mode, back to the previous 'softtabstop' column
+    /// when only whitespace precedes it
+    pub fn backspace(&mut self) {
+        let tabs = self.tabs;
+        let start = self.whitespace_before_cursor();
+        if tabs.soft_tab() != tabs.tab_stop && start < self.cursor.column {
+            let column = self.cursor_virtual_column();
+            let soft_tab = tabs.soft_tab();
+            let target = (column - 1) / soft_tab * soft_tab;
+            let from = self.virtual_column(self.cursor.row, start);
+            self.replace_before_cursor(start, target.max(from));
+            return;
+        }
+
+        let offset = self.cursor_offset();
+        if offset == 0 {
+            return;
+        }
+
+        let prev = self.prev_offset(offset);
+        let (column, row) = self.offset_position(prev);
+        self.splice(prev..offset, "");
+        self.cursor.row = row;
+        self.set_column(column);
+    }
+
+    /// Byte column at which the run of whitespace ending at the cursor starts
+    fn whitespace_before_cursor(&self) -> usize {
+        let line = self.cursor_line();
+        let column = grapheme::floor_boundary(&line, self.cursor.column);
+        line[..column].trim_end_matches([' ', '\t']).len()
+    }
+
+    /// Replace the text from `start` to the cursor with whitespace reaching `target`
+    fn replace_before_cursor(&mut self, start: usize, target: usize) {
+        let from = self.virtual_column(self.cursor.row, start);
+        let whitespace = self.tabs.indent(from, target);
+        let offset = self.line_offset(self.cursor.row);
+        self.splice(offset + start..offset + self.cursor.column, &whitespace);
+        self.set_column(start + whitespace.len());
     }
 
     pub fn motion<M>(&mut self, motion: M, block_newline: bool)
@@ -126,7 +760,8 @@ impl<'a> Buffer<'a> {
         motion.apply(self, block_newline);
     }
 
-    pub fn operator<O>(&mut self, operator: O)
+    /// Apply an operator, returning any text it removed
+    pub fn operator<O>(&mut self, operator: O) -> Option<Register>
     where
         O: Into<Operator>,
     {
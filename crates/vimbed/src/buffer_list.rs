@@ -0,0 +1,188 @@
+use crate::{
+    buffer::{Buffer, BufferText},
+    option::LocalOptions,
+};
+
+/// Document in the buffer list
+#[derive(Debug)]
+pub struct ListedBuffer<'a> {
+    pub number: usize,
+    /// Name shown by `:ls` and matched by `:buffer {name}`, usually a file name
+    pub name: Option<String>,
+    /// Text and editing state, held by the context's `buffer_edit` while the buffer is current
+    pub buffer: Option<Buffer<BufferText<'a>>>,
+    /// Values of buffer-local options, held by the context's options while the buffer is current
+    pub(crate) local_options: LocalOptions,
+}
+
+impl ListedBuffer<'_> {
+    /// Name as shown by `:ls`
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("[No Name]")
+    }
+}
+
+/// Documents open in a context, numbered from one in the order they were added
+#[derive(Debug)]
+pub struct BufferList<'a> {
+    buffers: Vec<ListedBuffer<'a>>,
+    current: usize,
+    alternate: Option<usize>,
+    next_number: usize,
+}
+
+impl Default for BufferList<'_> {
+    /// List holding only buffer 1, which is current
+    fn default() -> Self {
+        BufferList {
+            buffers: vec![ListedBuffer {
+                number: 1,
+                name: None,
+                buffer: None,
+                local_options: Default::default(),
+            }],
+            current: 1,
+            alternate: None,
+            next_number: 2,
+        }
+    }
+}
+
+impl<'a> BufferList<'a> {
+    /// Number of the buffer being edited
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Number of the buffer edited before the current one, as used by `Ctrl-^` and `#`
+    pub fn alternate(&self) -> Option<usize> {
+        self.alternate
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ListedBuffer<'a>> {
+        self.buffers.iter()
+    }
+
+    pub fn get(&self, number: usize) -> Option<&ListedBuffer<'a>> {
+        self.buffers.iter().find(|entry| entry.number == number)
+    }
+
+    pub fn get_mut(&mut self, number: usize) -> Option<&mut ListedBuffer<'a>> {
+        self.buffers.iter_mut().find(|entry| entry.number == number)
+    }
+
+    /// Numbers of the buffers whose name contains `pattern`, or only the one whose name
+    /// is `pattern` if there is one
+    pub fn find(&self, pattern: &str) -> Vec<usize> {
+        let names = || {
+            self.buffers
+                .iter()
+                .filter_map(|entry| Some((entry.number, entry.name.as_deref()?)))
+        };
+        if let Some((number, _)) = names().find(|(_, name)| *name == pattern) {
+            return vec![number];
+        }
+        names()
+            .filter(|(_, name)| name.contains(pattern))
+            .map(|(number, _)| number)
+            .collect()
+    }
+
+    /// Number of the buffer `count` places after `number`, or before it if not `forward`,
+    /// wrapping around the list
+    pub fn step(&self, number: usize, count: usize, forward: bool) -> usize {
+        let len = self.buffers.len();
+        let index = self
+            .buffers
+            .iter()
+            .position(|entry| entry.number == number)
+            .unwrap_or_default();
+        let offset = count % len;
+        let index = if forward {
+            (index + offset) % len
+        } else {
+            (index + len - offset) % len
+        };
+        self.buffers[index].number
+    }
+
+    /// List of `buffers`, whose numbers are distinct, with the current one held elsewhere
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_buffers(
+        buffers: Vec<ListedBuffer<'a>>,
+        current: usize,
+        alternate: Option<usize>,
+    ) -> Self {
+        let next_number = buffers.iter().map(|entry| entry.number).max().unwrap_or(0) + 1;
+        BufferList {
+            buffers,
+            current,
+            alternate,
+            next_number,
+        }
+    }
+
+    /// Add a buffer to the end of the list, returning its number
+    pub(crate) fn add(&mut self, buffer: Buffer<BufferText<'a>>) -> usize {
+        let number = self.next_number;
+        self.next_number += 1;
+        self.buffers.push(ListedBuffer {
+            number,
+            name: None,
+            buffer: Some(buffer),
+            local_options: Default::default(),
+        });
+        number
+    }
+
+    /// Make `number` current, swapping its text and local options with those given, which
+    /// are stored as the previous buffer's, or return false if there is no such buffer
+    pub(crate) fn switch(
+        &mut self,
+        number: usize,
+        buffer: &mut Buffer<BufferText<'a>>,
+        local_options: &mut LocalOptions,
+    ) -> bool {
+        let current = self.current;
+        let Some(entry) = self.get_mut(number).filter(|_| number != current) else {
+            return false;
+        };
+        let Some(next) = entry.buffer.take() else {
+            return false;
+        };
+        let next_options = std::mem::take(&mut entry.local_options);
+
+        let previous = std::mem::replace(buffer, next);
+        let previous_options = std::mem::replace(local_options, next_options);
+        if let Some(entry) = self.get_mut(current) {
+            entry.buffer = Some(previous);
+            entry.local_options = previous_options;
+        }
+        self.alternate = Some(current);
+        self.current = number;
+        true
+    }
+
+    /// Remove a buffer other than the current one
+    pub(crate) fn remove(&mut self, number: usize) -> Option<ListedBuffer<'a>> {
+        if number == self.current {
+            return None;
+        }
+        let index = self
+            .buffers
+            .iter()
+            .position(|entry| entry.number == number)?;
+        if self.alternate == Some(number) {
+            self.alternate = None;
+        }
+        Some(self.buffers.remove(index))
+    }
+}
@@ -1,8 +1,9 @@
-use std::str::FromStr;
+use std::num::NonZeroUsize;
 
 use nom::IResult;
 
 use crate::{
+    key::key,
     mode::Mode,
     motion::{
         character::CharacterMotion, left_right::LeftRightMotion, up_down::UpDownMotion, Motion,
@@ -13,22 +14,49 @@ use crate::{
 
 use super::motion::motion;
 
-pub const COMMAND_LEFT: &'static str = "<Left>";
-pub const COMMAND_DOWN: &'static str = "<Down>";
-pub const COMMAND_UP: &'static str = "<Up>";
-pub const COMMAND_RIGHT: &'static str = "<Right>";
-pub const COMMAND_HOME: &'static str = "<Home>";
-pub const COMMAND_END: &'static str = "<End>";
-pub const COMMAND_CARRIAGE_RETURN: &'static str = "<CR>";
-pub const COMMAND_ESCAPE: &'static str = "<ESC>";
-pub const COMMAND_BACKSPACE: &'static str = "<BS>";
-pub const COMMAND_LEADER: &'static str = "<Leader>";
-pub const COMMAND_DELETE: &'static str = "<Delete>";
+pub const COMMAND_LEFT: &str = "<Left>";
+pub const COMMAND_DOWN: &str = "<Down>";
+pub const COMMAND_UP: &str = "<Up>";
+pub const COMMAND_RIGHT: &str = "<Right>";
+pub const COMMAND_HOME: &str = "<Home>";
+pub const COMMAND_END: &str = "<End>";
+pub const COMMAND_CARRIAGE_RETURN: &str = "<CR>";
+pub const COMMAND_ESCAPE: &str = "<Esc>";
+pub const COMMAND_BACKSPACE: &str = "<BS>";
+pub const COMMAND_TAB: &str = "<Tab>";
+pub const COMMAND_LEADER: &str = "<Leader>";
+pub const COMMAND_DELETE: &str = "<Del>";
+pub const COMMAND_CTRL_R: &str = "<C-r>";
+pub const COMMAND_LESS_THAN: &str = "<lt>";
+pub const COMMAND_CTRL_CARET: &str = "<C-^>";
+pub const COMMAND_CTRL_W: &str = "<C-w>";
 
 #[derive(Debug, Clone)]
 pub enum ContextCommand {
     ChangeMode(Mode),
     RunCommand,
+    Ex(&'static str),
+    /// Execute an ex command with a count argument, as in `:delete 3`
+    ExCount(&'static str, usize),
+    /// Run a `Ctrl-W` window command, with the count typed before it if any
+    Window(char, Option<usize>),
+    /// Start recording typed keys into a register
+    Record(char),
+    /// Execute the keys stored in a register a number of times
+    Execute(char, usize),
+    /// Put the unnamed register a number of times after the cursor, or before it
+    Put {
+        before: bool,
+        count: usize,
+    },
+    /// Begin recording inserted text, to be inserted `count` times in all on `<Esc>`,
+    /// each on a new line if `newline`
+    StartInsert {
+        count: usize,
+        newline: bool,
+    },
+    /// Repeat the text inserted since `StartInsert` for its count
+    FinishInsert,
 }
 
 impl From<Mode> for ContextCommand {
@@ -42,6 +70,12 @@ pub enum BufferCommand<'a> {
     Insert(&'a str),
     Motion(Motion),
     Operator(Operator),
+    /// Insert mode `<Tab>`, honouring 'expandtab' and 'softtabstop'
+    InsertTab,
+    /// Insert mode `<BS>`, honouring 'softtabstop'
+    Backspace,
+    Undo(usize),
+    Redo(usize),
 }
 
 impl<'a> From<&'a str> for BufferCommand<'a> {
@@ -102,6 +136,28 @@ impl<'a> From<Vec<Command<'a>>> for Command<'a> {
 }
 
 impl Command<'_> {
+    /// Whether the command changes the text of the buffer it applies to, or begins
+    /// inserting into it
+    ///
+    /// Raw input is parsed before it runs, so it is checked then.
+    pub fn modifies(&self) -> bool {
+        match self {
+            Command::Context(c) => matches!(
+                c,
+                ContextCommand::ChangeMode(Mode::Insert)
+                    | ContextCommand::Put { .. }
+                    | ContextCommand::StartInsert { .. }
+            ),
+            Command::Buffer(c) => match c {
+                BufferCommand::Motion(_) => false,
+                BufferCommand::Operator(o) => o.variant != OperatorVariant::Yank,
+                _ => true,
+            },
+            Command::Raw(_) => false,
+            Command::Multi(m) => m.iter().any(Command::modifies),
+        }
+    }
+
     pub fn motion(repeat: usize, variant: MotionVariant) -> Self {
         BufferCommand::Motion(Motion::new(repeat, variant)).into()
     }
@@ -117,12 +173,12 @@ impl Command<'_> {
 }
 
 // nom parsers
-pub fn command_insert(input: &str) -> IResult<&str, Command> {
+pub fn command_insert(input: &str) -> IResult<&str, Command<'_>> {
     let (input, output) = nom::bytes::complete::take_till1(|input| input == '<')(input)?;
     Ok((input, BufferCommand::Insert(output).into()))
 }
 
-pub fn command_raw(input: &str) -> IResult<&str, Command> {
+pub fn command_raw(input: &str) -> IResult<&str, Command<'_>> {
     let (input, output) = nom::bytes::complete::take_till1(|input| input == '<')(input)?;
     Ok((input, output.into()))
 }
@@ -140,12 +196,12 @@ where
     }
 }
 
-pub fn command_motion(input: &str) -> IResult<&str, Command> {
+pub fn command_motion(input: &str) -> IResult<&str, Command<'_>> {
     let (input, output) = nom::branch::alt((
-        motion(COMMAND_LEFT, LeftRightMotion::left(1).unwrap()),
-        motion(COMMAND_DOWN, UpDownMotion::down(1).unwrap()),
-        motion(COMMAND_UP, UpDownMotion::up(1).unwrap()),
-        motion(COMMAND_RIGHT, LeftRightMotion::right(1).unwrap()),
+        motion(COMMAND_LEFT, LeftRightMotion::Left(NonZeroUsize::MIN)),
+        motion(COMMAND_DOWN, UpDownMotion::Down(NonZeroUsize::MIN)),
+        motion(COMMAND_UP, UpDownMotion::Up(NonZeroUsize::MIN)),
+        motion(COMMAND_RIGHT, LeftRightMotion::Right(NonZeroUsize::MIN)),
         motion(COMMAND_HOME, LeftRightMotion::FirstCharacter),
         motion(COMMAND_END, LeftRightMotion::LastCharacter),
     ))(input)?;
@@ -153,28 +209,33 @@ pub fn command_motion(input: &str) -> IResult<&str, Command> {
     Ok((input, BufferCommand::from(output).into()))
 }
 
-/// Matches angle bracket delimited commands with no corresponding BaseCommand
-fn command_special_raw(input: &str) -> IResult<&str, Command> {
-    let (input, output) = nom::combinator::recognize(nom::sequence::delimited(
-        nom::bytes::complete::tag("<"),
-        nom::character::complete::alphanumeric0,
-        nom::bytes::complete::tag(">"),
-    ))(input)?;
-
-    Ok((input, output.into()))
+/// Matches input beginning with a key, passing all of it on to the mode's parser
+///
+/// Plain characters and `<...>` notation may be mixed, as in `d2<Right>`.
+pub fn command(input: &str) -> IResult<&str, Command<'_>> {
+    key(input)?;
+    Ok(("", input.into()))
 }
 
-pub fn command(input: &str) -> IResult<&str, Command> {
-    nom::branch::alt((command_special_raw, command_raw))(input)
+/// Creates a nom parser from a given string tag followed by a register name
+pub fn command_register<'a>(
+    tag: &'a str,
+    f: impl Fn(char) -> ContextCommand + 'a,
+) -> impl FnMut(&str) -> IResult<&str, Command<'_>> + 'a {
+    move |input| {
+        let (input, _) = nom::bytes::streaming::tag(tag)(input)?;
+        let (input, name) = nom::character::streaming::anychar(input)?;
+        Ok((input, f(name).into()))
+    }
 }
 
-pub fn command_delete(input: &str) -> IResult<&str, Command> {
+pub fn command_delete(input: &str) -> IResult<&str, Command<'_>> {
     command_variant(
         COMMAND_DELETE,
         Command::operator(
             1,
             OperatorVariant::Delete,
-            Motion::new_one(CharacterMotion::forward(1).unwrap().into()),
+            Motion::new_one(CharacterMotion::Forward(NonZeroUsize::MIN).into()),
         ),
     )(input)
 }
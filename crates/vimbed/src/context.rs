@@ -1,32 +1,175 @@
-use std::fmt::Debug;
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fmt::Debug,
+    time::{Duration, Instant},
+};
 
-use nom::{error::Error, Err};
+use nom::Err;
 
 use crate::{
-    buffer::Buffer,
-    command::{command, BufferCommand, Command, ContextCommand},
-    mode::{command::command_command, insert::insert_command, normal::normal_command, Mode},
+    buffer::{Buffer, BufferText, Cursor, ExternalEdit, UndoStep},
+    buffer_list::BufferList,
+    command::{
+        command, BufferCommand, Command, ContextCommand, COMMAND_BACKSPACE, COMMAND_LEADER,
+        COMMAND_LESS_THAN, COMMAND_TAB,
+    },
+    error::Error,
+    event::{Event, EventCallback},
+    ex::{
+        self,
+        substitute::{substitute_confirm_input, Substitute, Substitution},
+    },
+    grapheme,
+    key::{self, Key, KeyCode},
+    map::{MapMatch, MapMode, MapTarget, Mapping, Mappings},
+    message::{Message, Messages},
+    mode::{
+        command::command_command,
+        insert::insert_command,
+        normal::{normal_command, normal_operator_pending},
+        CommandMode, CursorShape, Mode,
+    },
+    option::Options,
+    register::{Register, Registers},
+    storage::TextStorage,
+    tab::TabSettings,
+    tab_page::{TabLabel, TabPages},
+    window::{Neighbour, Rect, SplitDirection, Window, Windows},
 };
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+/// Nesting limit for keys executed by `:normal` and `@`, which may invoke each other
+const MAX_INPUT_DEPTH: usize = 100;
+
+/// Longest text a counted put or insert may produce
+const MAX_TEXT_LEN: usize = i32::MAX as usize;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum BufferId {
     Edit,
     Command,
     Search,
 }
 
+impl BufferId {
+    pub const ALL: [BufferId; 3] = [BufferId::Edit, BufferId::Command, BufferId::Search];
+}
+
+/// Callback invoked with the text of an executed command line
+pub type CommandCallback = Box<dyn FnMut(&str)>;
+
+/// Key waiting in the typeahead buffer to be mapped or executed
+#[derive(Debug, Copy, Clone)]
+struct TypedKey {
+    key: Key,
+    /// Whether mappings may apply to the key
+    remap: bool,
+    /// Whether the key came from a mapping rather than being typed
+    mapped: bool,
+    /// Whether the key came from a `<silent>` mapping
+    silent: bool,
+}
+
+/// Keys fed to the context but not yet executed
+#[derive(Debug, Default)]
+struct Typeahead {
+    keys: VecDeque<TypedKey>,
+    /// Executed keys forming an incomplete command, in notation
+    pending: String,
+    /// Mappings expanded since the typeahead last held only typed keys
+    depth: i64,
+    /// When the most recent key was typed
+    since: Option<Instant>,
+    /// Whether the last executed key ended a sequence that isn't a command
+    rejected: bool,
+}
+
+/// Text typed after a counted insert command such as `3i`
+#[derive(Debug)]
+struct InsertRepeat {
+    count: usize,
+    /// Whether each repetition starts a new line, as for `o`
+    newline: bool,
+    /// Inserted text in key notation
+    text: String,
+}
+
+impl InsertRepeat {
+    fn record(&mut self, command: &BufferCommand) {
+        match command {
+            BufferCommand::Insert(s) => self.text.push_str(&s.replace('<', COMMAND_LESS_THAN)),
+            BufferCommand::InsertTab => self.text.push_str(COMMAND_TAB),
+            BufferCommand::Backspace => self.text.push_str(COMMAND_BACKSPACE),
+            _ => {}
+        }
+    }
+}
+
+/// What became of a key passed to [`Context::input_key`], or keys to [`Context::input_str`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyOutcome {
+    /// The key completed a command, which has run
+    Consumed,
+    /// The key is held until later keys complete a command or mapping
+    Pending,
+    /// The key ended a sequence that isn't a command, which was discarded
+    Rejected,
+}
+
 /// Result type for handling nom errors
-type Result<'i> = std::result::Result<(), Err<Error<&'i str>>>;
+type Result<'i> = std::result::Result<(), Err<nom::error::Error<&'i str>>>;
 
 // Vim application context
 pub struct Context<'a> {
     pub mode: Mode,
 
-    pub buffer_edit: Buffer<'a>,
-    pub buffer_command: Buffer<'a>,
-    pub buffer_search: Buffer<'a>,
+    pub buffer_edit: Buffer<BufferText<'a>>,
+    pub buffer_command: Buffer<BufferText<'a>>,
+    pub buffer_search: Buffer<BufferText<'a>>,
+    /// Documents that may be edited, the current one being held by `buffer_edit`
+    pub buffers: BufferList<'a>,
+    /// Views of the listed buffers laid out on the screen
+    pub windows: Windows,
+    /// Layouts of windows, the current one being held by `windows`
+    pub tab_pages: TabPages,
+
+    pub fn_command: Option<CommandCallback>,
+
+    pub options: Options,
 
-    pub fn_command: Option<Box<dyn FnMut(&str)>>,
+    pub mappings: Mappings,
+
+    pub registers: Registers,
+    /// Register receiving typed keys, if recording
+    pub recording: Option<char>,
+    recorded: String,
+    last_executed: Option<char>,
+
+    /// Messages for the embedder to display, such as ex command errors
+    pub messages: Messages,
+
+    /// Most recently used search pattern
+    pub last_pattern: Option<String>,
+    pub last_substitute: Option<Substitute>,
+    /// Active `:s///c` prompt, which receives all input until answered
+    pub substitute_confirm: Option<Substitution>,
+
+    /// Typed keys awaiting a mapping match, and the command they have begun
+    typeahead: Typeahead,
+    /// Text typed since a counted insert command, to repeat on `<Esc>`
+    insert_repeat: Option<InsertRepeat>,
+
+    /// Whether a `:global` command is executing
+    pub(crate) global_busy: bool,
+    /// Nesting depth of `parse_input` calls
+    input_depth: usize,
+    /// Nesting depth of `<silent>` mappings executing, during which only errors are shown
+    silent_depth: usize,
+
+    subscribers: Vec<EventCallback>,
+    /// Mode and cursors as last reported to subscribers
+    reported_mode: Mode,
+    reported_cursors: [Cursor; 3],
 }
 
 impl Debug for Context<'_> {
@@ -36,29 +179,121 @@ impl Debug for Context<'_> {
             .field("buffer_edit", &self.buffer_edit)
             .field("buffer_command", &self.buffer_command)
             .field("buffer_search", &self.buffer_search)
+            .field("buffers", &self.buffers)
+            .field("windows", &self.windows)
+            .field("tab_pages", &self.tab_pages)
+            .field("options", &self.options)
+            .field("mappings", &self.mappings)
+            .field("registers", &self.registers)
+            .field("recording", &self.recording)
+            .field("messages", &self.messages)
+            .field("last_pattern", &self.last_pattern)
+            .field("last_substitute", &self.last_substitute)
+            .field("substitute_confirm", &self.substitute_confirm)
             .finish()
     }
 }
 
+impl Context<'static> {
+    /// Create a context owning its edit, command line and search buffers
+    pub fn new() -> Self {
+        Context::from_buffers(
+            BufferText::from(String::new()).into(),
+            BufferText::from(String::new()).into(),
+            BufferText::from(String::new()).into(),
+        )
+    }
+
+    /// Set the initial text of the edit buffer, which may be a [`ropey::Rope`] for large files
+    pub fn with_text(mut self, text: impl Into<BufferText<'static>>) -> Self {
+        self.buffer_edit = text.into().into();
+        self.buffer_edit.track_changes(!self.subscribers.is_empty());
+        self
+    }
+}
+
+impl Default for Context<'static> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> Context<'a> {
-    pub fn new(
+    /// Create a context editing strings owned by the caller
+    pub fn borrowed(
         buffer_edit: &'a mut String,
         buffer_command: &'a mut String,
         buffer_search: &'a mut String,
     ) -> Self {
-        let buffer_edit = buffer_edit.into();
-        let buffer_command = buffer_command.into();
-        let buffer_search = buffer_search.into();
+        Context::from_buffers(
+            BufferText::from(buffer_edit).into(),
+            BufferText::from(buffer_command).into(),
+            BufferText::from(buffer_search).into(),
+        )
+    }
 
+    fn from_buffers(
+        buffer_edit: Buffer<BufferText<'a>>,
+        buffer_command: Buffer<BufferText<'a>>,
+        buffer_search: Buffer<BufferText<'a>>,
+    ) -> Self {
         Context {
             mode: Default::default(),
             buffer_edit,
             buffer_command,
             buffer_search,
+            buffers: Default::default(),
+            windows: Default::default(),
+            tab_pages: Default::default(),
             fn_command: Default::default(),
+            options: Default::default(),
+            mappings: Default::default(),
+            registers: Default::default(),
+            recording: Default::default(),
+            recorded: Default::default(),
+            last_executed: Default::default(),
+            messages: Default::default(),
+            last_pattern: Default::default(),
+            last_substitute: Default::default(),
+            substitute_confirm: Default::default(),
+            typeahead: Default::default(),
+            insert_repeat: Default::default(),
+            global_busy: Default::default(),
+            input_depth: Default::default(),
+            silent_depth: Default::default(),
+            subscribers: Default::default(),
+            reported_mode: Default::default(),
+            reported_cursors: Default::default(),
         }
     }
 
+    /// Text of the edit buffer
+    pub fn text(&self) -> Cow<'_, str> {
+        let text = &self.buffer_edit.text;
+        text.slice(0..text.len())
+    }
+
+    /// Replace the text of the edit buffer, discarding its undo history
+    pub fn set_text(&mut self, text: &str) {
+        self.buffer_edit.set_text(text);
+        if self.block_newline() {
+            self.buffer_edit.clamp_cursor();
+        }
+        self.emit_events();
+    }
+
+    /// Apply edits made outside the editor to the edit buffer, keeping its cursor on the same text
+    pub fn apply_edits(&mut self, edits: &[ExternalEdit], undo: UndoStep) {
+        if !self.check_modifiable() {
+            return;
+        }
+        self.buffer_edit.apply_edits(edits, undo);
+        if self.block_newline() {
+            self.buffer_edit.clamp_cursor();
+        }
+        self.emit_events();
+    }
+
     pub fn with_command_callback<F>(mut self, f: F) -> Self
     where
         F: FnMut(&str) + 'static,
@@ -68,21 +303,523 @@ impl<'a> Context<'a> {
     }
 
     pub fn command(&mut self) {
-        if let Some(ref mut f) = self.fn_command {
-            f(self
-                .buffer_command
-                .lines()
-                .skip(self.buffer_command.lines().count() - 1)
-                .next()
-                .unwrap());
+        let text = &self.buffer_command.text;
+        let line = text
+            .slice(0..text.len())
+            .lines()
+            .next_back()
+            .unwrap_or_default()
+            .to_owned();
+
+        self.ex(&line);
+    }
+
+    /// Execute an ex command line, passing it to the command callback if it isn't built-in
+    pub fn ex(&mut self, line: &str) {
+        self.buffer_edit.tabs = TabSettings::from(&self.options);
+        if !ex::execute(self, line) {
+            match self.fn_command {
+                Some(ref mut f) => f(line),
+                None if !line.trim_start_matches([':', ' ']).is_empty() => {
+                    self.error(Error::new(492, format!("Not an editor command: {}", line)));
+                }
+                None => (),
+            }
+        }
+        // Called from outside any input, the line is undone as a single change
+        if self.input_depth == 0 && self.mode == Mode::Normal && self.substitute_confirm.is_none() {
+            self.buffer_edit.commit_undo();
+        }
+        self.emit_events();
+    }
+
+    /// Register a callback invoked with each text change, mode change and cursor move
+    ///
+    /// Events are sent once the command causing them finishes, so a cursor moving within a
+    /// command is reported once, and each change carries the cursor it finished with.
+    pub fn subscribe<F>(&mut self, f: F)
+    where
+        F: FnMut(&Event) + 'static,
+    {
+        if self.subscribers.is_empty() {
+            for id in BufferId::ALL {
+                self.buffer_mut(id).track_changes(true);
+            }
+            self.reported_mode = self.mode;
+            self.reported_cursors = BufferId::ALL.map(|id| self.buffer(id).cursor);
+        }
+        self.subscribers.push(Box::new(f));
+    }
+
+    /// Run `f` as part of the command executing, so that its changes are undone with the
+    /// command and reported once it finishes
+    pub(crate) fn nested(&mut self, f: impl FnOnce(&mut Self)) {
+        self.input_depth += 1;
+        f(self);
+        self.input_depth -= 1;
+    }
+
+    /// Scroll the current window to its cursor and send subscribers the changes made since
+    /// they were last sent any, unless a command is still executing
+    fn emit_events(&mut self) {
+        if self.input_depth == 0 {
+            self.windows.scroll_to(self.buffer_edit.cursor.row);
+            self.send_events();
+        }
+    }
+
+    fn send_events(&mut self) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+
+        let mut events = vec![];
+        for id in BufferId::ALL {
+            let changes = self.buffer_mut(id).drain_changes();
+            events.extend(changes.map(|change| Event::Change { buffer: id, change }));
+        }
+
+        if self.mode != self.reported_mode {
+            events.push(Event::Mode {
+                old: self.reported_mode,
+                new: self.mode,
+            });
+            self.reported_mode = self.mode;
+        }
+
+        for (i, id) in BufferId::ALL.into_iter().enumerate() {
+            let cursor = self.buffer(id).cursor;
+            if cursor != self.reported_cursors[i] {
+                events.push(Event::CursorMove {
+                    buffer: id,
+                    old: self.reported_cursors[i],
+                    new: cursor,
+                });
+                self.reported_cursors[i] = cursor;
+            }
+        }
+
+        for event in &events {
+            for subscriber in self.subscribers.iter_mut() {
+                subscriber(event);
+            }
+        }
+    }
+
+    /// Add a buffer holding `text` to the buffer list, returning its number
+    ///
+    /// The buffer is unnamed until its entry in `buffers` is given a name.
+    pub fn add_buffer(&mut self, text: impl Into<BufferText<'a>>) -> usize {
+        self.buffers.add(text.into().into())
+    }
+
+    /// Editing state of a listed buffer, whether it is current or not
+    pub fn listed_buffer(&self, number: usize) -> Option<&Buffer<BufferText<'a>>> {
+        if number == self.buffers.current() {
+            Some(&self.buffer_edit)
+        } else {
+            self.buffers.get(number)?.buffer.as_ref()
+        }
+    }
+
+    /// Make a listed buffer the one being edited
+    ///
+    /// Events for changes made before the switch are sent first, even if a command is
+    /// executing, so that they are reported for the buffer they were made in.
+    pub fn switch_buffer(&mut self, number: usize) {
+        let old = self.buffers.current();
+        if number == old {
+            return;
+        }
+        if self.buffers.get(number).is_none() {
+            self.error(Error::new(86, format!("Buffer {} does not exist", number)));
+            return;
+        }
+
+        self.send_events();
+        if let Some(window) = self.windows.get_mut(self.windows.current()) {
+            window.buffer = number;
+            window.top = 0;
+        }
+        self.buffer_edit.track_changes(false);
+        if !self
+            .buffers
+            .switch(number, &mut self.buffer_edit, self.options.locals_mut())
+        {
+            return;
+        }
+        self.buffer_edit.track_changes(!self.subscribers.is_empty());
+        self.buffer_edit.tabs = TabSettings::from(&self.options);
+        if self.block_newline() {
+            self.buffer_edit.clamp_cursor();
+        }
+
+        self.reported_cursors[0] = self.buffer_edit.cursor;
+        let event = Event::Buffer { old, new: number };
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber(&event);
+        }
+    }
+
+    /// Remove a buffer from the list, refusing if it has unsaved changes unless `force`
+    ///
+    /// The current buffer is replaced by the alternate buffer, or the next one if there is
+    /// no alternate, or an empty buffer if it was the only one.
+    pub fn delete_buffer(&mut self, number: usize, force: bool) {
+        let Some(buffer) = self.listed_buffer(number) else {
+            self.error(Error::new(516, "No buffers were deleted"));
+            return;
+        };
+        if buffer.modified() && !force {
+            self.error(Error::new(
+                89,
+                format!(
+                    "No write since last change for buffer {} (add ! to override)",
+                    number
+                ),
+            ));
+            return;
+        }
+
+        // Windows showing the buffer are closed while others remain
+        self.tab_pages.close_buffer(number);
+        let showing = self.windows.iter().filter(|window| window.buffer == number);
+        for id in showing.map(|window| window.id).collect::<Vec<_>>() {
+            if self.windows.len() > 1 {
+                self.close_window(id);
+            }
+        }
+
+        if number == self.buffers.current() {
+            let next = match self.buffers.alternate() {
+                Some(alternate) => alternate,
+                None if self.buffers.len() > 1 => self.buffers.step(number, 1, true),
+                None => self.add_buffer(String::new()),
+            };
+            self.switch_buffer(next);
+        }
+        self.buffers.remove(number);
+    }
+
+    /// Share `area` of the screen between the windows of each tab page, keeping their
+    /// proportions
+    pub fn set_window_area(&mut self, area: Rect) {
+        self.windows.set_area(area);
+        self.tab_pages.set_area(area);
+        self.windows.scroll_to(self.buffer_edit.cursor.row);
+    }
+
+    /// Make a window the one being edited in, switching to the buffer it shows
+    ///
+    /// The window's cursor is kept within the text, which may have been changed through
+    /// another window since it was last current.
+    pub fn switch_window(&mut self, id: usize) {
+        let old = self.windows.current();
+        let Some(window) = self.windows.get(id).filter(|_| id != old).cloned() else {
+            return;
+        };
+
+        self.send_events();
+        self.save_window_cursor();
+        self.windows.set_current(id);
+        self.enter_window(window);
+
+        let event = Event::Window { old, new: id };
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber(&event);
+        }
+    }
+
+    /// Store the edit buffer's cursor as the current window's, before leaving the window
+    fn save_window_cursor(&mut self) {
+        if let Some(window) = self.windows.get_mut(self.windows.current()) {
+            window.cursor = self.buffer_edit.cursor;
+        }
+    }
+
+    /// Edit the buffer shown by `window`, which has just become current, at its cursor
+    fn enter_window(&mut self, window: Window) {
+        self.switch_buffer(window.buffer);
+        if let Some(current) = self.windows.get_mut(window.id) {
+            current.top = window.top;
+        }
+
+        let buffer = &mut self.buffer_edit;
+        buffer.cursor = window.cursor;
+        buffer.cursor.row = buffer.cursor.row.min(buffer.line_count() - 1);
+        buffer.cursor.column =
+            grapheme::floor_boundary(&buffer.cursor_line(), buffer.cursor.column);
+        if self.block_newline() {
+            self.buffer_edit.clamp_cursor();
+        }
+    }
+
+    /// Split the current window in two showing the same buffer, and edit in the new one
+    pub fn split_window(&mut self, direction: SplitDirection) {
+        self.save_window_cursor();
+        match self.windows.split(direction) {
+            Some(id) => self.switch_window(id),
+            None => self.error(Error::new(36, "Not enough room")),
+        }
+    }
+
+    /// Remove a window from the screen, leaving its buffer listed
+    ///
+    /// Closing the current window makes the one before it current, or the one after it if
+    /// it was first. Closing the last window of a tab page closes the page.
+    pub fn close_window(&mut self, id: usize) {
+        if self.windows.get(id).is_none() {
+            return;
+        }
+        if self.windows.len() == 1 && self.tab_pages.len() > 1 {
+            self.close_tab_page(self.tab_pages.current());
+            return;
+        }
+        if self.windows.len() == 1 {
+            self.error(Error::new(444, "Cannot close last window"));
+            return;
+        }
+        if id == self.windows.current() {
+            let ids = self.windows.ids();
+            let next = match ids.iter().position(|&i| i == id) {
+                Some(0) | None => ids[1],
+                Some(index) => ids[index - 1],
+            };
+            self.switch_window(next);
+        }
+        self.windows.close(id);
+    }
+
+    /// Close every window but the current one
+    pub fn only_window(&mut self) {
+        let current = self.windows.current();
+        for id in self.windows.ids() {
+            if id != current {
+                self.windows.close(id);
+            }
+        }
+    }
+
+    /// Move to the window beside the current one in `direction`, `count` times
+    pub fn neighbour_window(&mut self, direction: Neighbour, count: usize) {
+        for _ in 0..count {
+            let current = self.windows.current();
+            let Some(window) = self.windows.get(current) else {
+                return;
+            };
+            let Some(rect) = self.windows.rect(current) else {
+                return;
+            };
+            let row = self.buffer_edit.cursor.row.saturating_sub(window.top);
+            let position = (
+                rect.x + self.cursor_screen_column(),
+                rect.y + row.min(rect.height.saturating_sub(2)),
+            );
+            match self.windows.neighbour(current, direction, position) {
+                Some(id) => self.switch_window(id),
+                None => return,
+            }
+        }
+    }
+
+    /// Add a tab page after the current one, holding a window on a new empty buffer, and
+    /// edit in it
+    pub fn new_tab_page(&mut self) {
+        let buffer = self.add_buffer(String::new());
+        let number = self
+            .tab_pages
+            .add(Windows::new(buffer, self.windows.area()));
+        self.switch_tab_page(number);
+    }
+
+    /// Make a tab page the one being edited in, entering its current window
+    pub fn switch_tab_page(&mut self, number: usize) {
+        let old = self.tab_pages.current();
+        if number == old || self.tab_pages.get(number).is_none() {
+            return;
+        }
+
+        self.send_events();
+        self.save_window_cursor();
+        if !self.tab_pages.switch(number, &mut self.windows) {
+            return;
+        }
+        if let Some(window) = self.windows.get(self.windows.current()).cloned() {
+            self.enter_window(window);
+        }
+
+        let event = Event::TabPage { old, new: number };
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber(&event);
+        }
+    }
+
+    /// Remove a tab page and its windows, leaving their buffers listed
+    ///
+    /// Closing the current tab page makes the one after it current, or the one before it
+    /// if it was last.
+    pub fn close_tab_page(&mut self, number: usize) {
+        if number == 0 || number > self.tab_pages.len() {
+            self.error(Error::new(16, "Invalid range"));
+            return;
+        }
+        if self.tab_pages.len() == 1 {
+            self.error(Error::new(784, "Cannot close last tab page"));
+            return;
+        }
+        if number == self.tab_pages.current() {
+            let next = match number < self.tab_pages.len() {
+                true => number + 1,
+                false => number - 1,
+            };
+            self.switch_tab_page(next);
+        }
+        self.tab_pages.remove(number);
+    }
+
+    /// Move the current tab page to `index`, counting from zero among the others
+    pub fn move_tab_page(&mut self, index: usize) {
+        self.tab_pages.move_current(index);
+    }
+
+    /// Labels of the tab pages, in order, for drawing a tabline
+    pub fn tabline(&self) -> Vec<TabLabel> {
+        (1..=self.tab_pages.len())
+            .filter_map(|number| {
+                let current = number == self.tab_pages.current();
+                let windows = match current {
+                    true => &self.windows,
+                    false => self.tab_pages.get(number)?,
+                };
+                let window = windows.get(windows.current())?;
+                let name = self.buffers.get(window.buffer)?.display_name().to_owned();
+                let modified = windows.iter().any(|window| {
+                    let buffer = self.listed_buffer(window.buffer);
+                    buffer.is_some_and(|buffer| buffer.modified())
+                });
+                Some(TabLabel {
+                    number,
+                    current,
+                    name,
+                    windows: windows.len(),
+                    modified,
+                })
+            })
+            .collect()
+    }
+
+    /// Revert the last `count` changes
+    pub fn undo(&mut self, count: usize) {
+        if !self.check_modifiable() {
+            return;
+        }
+        for _ in 0..count {
+            if !self.buffer_edit.undo() {
+                self.info("Already at oldest change");
+                break;
+            }
+        }
+        self.emit_events();
+    }
+
+    /// Reapply the last `count` undone changes
+    pub fn redo(&mut self, count: usize) {
+        if !self.check_modifiable() {
+            return;
+        }
+        for _ in 0..count {
+            if !self.buffer_edit.redo() {
+                self.info("Already at newest change");
+                break;
+            }
+        }
+        self.emit_events();
+    }
+
+    /// Put the unnamed register `count` times after the cursor, or before it
+    pub fn put(&mut self, before: bool, count: usize) {
+        if !self.check_modifiable() {
+            return;
+        }
+        let Some(register) = self.registers.get('"').cloned() else {
+            self.error(Error::new(353, "Nothing in register \""));
+            return;
+        };
+
+        if register.text.len().saturating_mul(count) > MAX_TEXT_LEN {
+            self.error(Error::new(1240, "Resulting text too long"));
+            return;
+        }
+        self.buffer_edit.put(&register, before, count);
+        self.emit_events();
+    }
+
+    /// Insert the text typed since a counted insert command the rest of its count times
+    fn finish_insert(&mut self) {
+        let Some(repeat) = self.insert_repeat.take() else {
+            return;
+        };
+        if repeat.count <= 1 || (repeat.text.is_empty() && !repeat.newline) {
+            return;
+        }
+        if (repeat.text.len() + 1).saturating_mul(repeat.count) > MAX_TEXT_LEN {
+            self.error(Error::new(1240, "Resulting text too long"));
+            return;
+        }
+
+        for _ in 1..repeat.count {
+            if repeat.newline {
+                self.buffer_edit.insert("\n");
+            }
+            self.feed_keys(key::keys(&repeat.text), false);
         }
     }
 
     pub fn set_mode(&mut self, mode: Mode) {
-        self.mode = mode
+        self.mode = mode;
+        self.emit_events();
+    }
+
+    /// Whether the edit buffer may be changed, reporting an error if 'modifiable' is off
+    pub fn check_modifiable(&mut self) -> bool {
+        let modifiable = self.options.bool("modifiable").unwrap_or(true);
+        if !modifiable {
+            self.error(Error::new(21, "Cannot make changes, 'modifiable' is off"));
+        }
+        modifiable
+    }
+
+    /// Show an error, keeping it for `:messages`
+    pub fn error(&mut self, error: Error) {
+        self.push_message(error.into());
+    }
+
+    /// Show an informational message, keeping it for `:messages`
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push_message(Message::Info(text.into()));
     }
 
-    pub fn buffer(&self, buffer: BufferId) -> &Buffer<'a> {
+    /// Show command output, such as a listing, without keeping it for `:messages`
+    pub fn output(&mut self, text: impl Into<String>) {
+        if self.silent_depth == 0 {
+            self.messages.show(Message::Info(text.into()));
+        }
+    }
+
+    fn push_message(&mut self, message: Message) {
+        if self.silent_depth > 0 && !message.is_error() {
+            return;
+        }
+        let limit = self.options.number("msghistory").unwrap_or(500).max(0);
+        self.messages.push(message, limit as usize);
+    }
+
+    /// Take the messages waiting to be displayed, oldest first
+    pub fn drain_messages(&mut self) -> impl Iterator<Item = Message> + '_ {
+        self.messages.drain()
+    }
+
+    pub fn buffer(&self, buffer: BufferId) -> &Buffer<BufferText<'a>> {
         match buffer {
             BufferId::Edit => &self.buffer_edit,
             BufferId::Command => &self.buffer_command,
@@ -90,7 +827,7 @@ impl<'a> Context<'a> {
         }
     }
 
-    pub fn buffer_mut(&mut self, buffer: BufferId) -> &mut Buffer<'a> {
+    pub fn buffer_mut(&mut self, buffer: BufferId) -> &mut Buffer<BufferText<'a>> {
         match buffer {
             BufferId::Edit => &mut self.buffer_edit,
             BufferId::Command => &mut self.buffer_command,
@@ -100,59 +837,565 @@ impl<'a> Context<'a> {
 
     pub fn active_buffer_id(&self) -> BufferId {
         match self.mode {
-            Mode::Normal | Mode::Insert => BufferId::Edit,
+            Mode::Normal | Mode::OperatorPending { .. } | Mode::Insert => BufferId::Edit,
             Mode::Command(command_mode) => match command_mode {
-                crate::mode::CommandMode::Command => BufferId::Command,
-                crate::mode::CommandMode::Search => BufferId::Search,
+                CommandMode::Command => BufferId::Command,
+                CommandMode::Search => BufferId::Search,
             },
         }
     }
 
-    pub fn active_buffer(&self) -> &Buffer<'a> {
+    pub fn active_buffer(&self) -> &Buffer<BufferText<'a>> {
         self.buffer(self.active_buffer_id())
     }
 
-    pub fn active_buffer_mut(&mut self) -> &mut Buffer<'a> {
+    pub fn active_buffer_mut(&mut self) -> &mut Buffer<BufferText<'a>> {
         self.buffer_mut(self.active_buffer_id())
     }
 
     pub fn block_newline(&self) -> bool {
         match self.mode {
-            Mode::Normal => true,
+            Mode::Normal | Mode::OperatorPending { .. } => {
+                !self.options.contains("virtualedit", "onemore")
+                    && !self.options.contains("virtualedit", "all")
+            }
             Mode::Insert => false,
             Mode::Command(_) => false,
         }
     }
 
-    pub fn input_str<'i>(&mut self, input: &'i str) -> Result<'i> {
+    /// Virtual column at which to draw the edit buffer's cursor
+    ///
+    /// In normal mode the cursor sits at the end of a tab, as in Vim, otherwise at its start.
+    pub fn cursor_screen_column(&self) -> usize {
+        let buffer = &self.buffer_edit;
+        let tabs = TabSettings::from(&self.options);
+        let line = buffer.line(buffer.cursor.row).unwrap_or_default();
+        let column = grapheme::floor_boundary(&line, buffer.cursor.column);
+        let virtual_column = tabs.advance(&line[..column], 0);
+        match (self.mode, line[column..].starts_with('\t')) {
+            (Mode::Normal | Mode::OperatorPending { .. }, true) => {
+                virtual_column + tabs.width("\t", virtual_column) - 1
+            }
+            _ => virtual_column,
+        }
+    }
+
+    /// Shape to draw the cursor in for the current mode
+    ///
+    /// A bar sits between characters where text would be inserted, and an underline
+    /// shows an operator awaiting its motion.
+    pub fn cursor_shape(&self) -> CursorShape {
+        match self.mode {
+            Mode::Normal => CursorShape::Block,
+            Mode::OperatorPending { .. } => CursorShape::Underline,
+            Mode::Insert => CursorShape::Bar,
+            Mode::Command(_) => {
+                let buffer = self.active_buffer();
+                let line = buffer.line(buffer.line_count() - 1).unwrap_or_default();
+                if buffer.cursor.column == line.len() {
+                    CursorShape::Block
+                } else {
+                    CursorShape::Bar
+                }
+            }
+        }
+    }
+
+    /// Whether `pattern` should match case-insensitively under 'ignorecase' and 'smartcase'
+    pub fn ignore_case(&self, pattern: &str) -> bool {
+        self.options.bool("ignorecase").unwrap_or_default()
+            && !(self.options.bool("smartcase").unwrap_or_default()
+                && pattern.chars().any(char::is_uppercase))
+    }
+
+    /// Whether a change to `lines` lines exceeds the 'report' threshold
+    pub fn report(&self, lines: usize) -> bool {
+        lines as i64 > self.options.number("report").unwrap_or(2)
+    }
+
+    /// Execute keys written in notation as a single command, without applying mappings
+    ///
+    /// Keys that begin but don't complete a command are pending, and are not kept. Key
+    /// names are matched without regard to case or alias, so `<ESC>` is `<Esc>`.
+    pub fn input_str(&mut self, input: &str) -> KeyOutcome {
+        let input = key::notation(key::keys(input));
+        match self.parse_input(&input) {
+            Ok(()) => KeyOutcome::Consumed,
+            Err(Err::Incomplete(_)) => KeyOutcome::Pending,
+            Err(_) => KeyOutcome::Rejected,
+        }
+    }
+
+    fn parse_input<'i>(&mut self, input: &'i str) -> Result<'i> {
+        if self.input_depth >= MAX_INPUT_DEPTH {
+            self.error(Error::new(169, "Command too recursive"));
+            return Ok(());
+        }
+
+        let was_recording = self.recording.is_some();
+
+        self.input_depth += 1;
+        let result = self.input(input);
+        self.input_depth -= 1;
+
+        if self.input_depth > 0 {
+            return result;
+        }
+
+        if was_recording && self.recording.is_some() && !matches!(result, Err(Err::Incomplete(_))) {
+            self.recorded.push_str(input);
+        }
+
+        // Each normal mode command or ex command line is undone as a single change,
+        // while insert mode edits accumulate until returning to normal mode
+        if self.mode == Mode::Normal && self.substitute_confirm.is_none() {
+            self.buffer_edit.commit_undo();
+        }
+        self.emit_events();
+
+        result
+    }
+
+    fn input<'i>(&mut self, input: &'i str) -> Result<'i> {
+        if self.substitute_confirm.is_some() {
+            substitute_confirm_input(self, input);
+            return Ok(());
+        }
+
+        if self.mode == Mode::Normal && self.recording.is_some() && input == "q" {
+            self.stop_recording();
+            return Ok(());
+        }
+
         let (_, command) = command(input)?;
         self.input_command(command)
     }
 
+    /// Feed keys written in notation, such as `"d2w"` or `"ihello<Esc>"`, as if typed
+    ///
+    /// Keys completing a command are consumed, keys that can't start one are discarded.
+    pub fn input_keys(&mut self, keys_str: &str) {
+        self.input_key_stream(key::keys(keys_str));
+    }
+
+    /// Feed a sequence of keys one at a time, as if typed
+    pub fn input_key_stream<I>(&mut self, keys: I)
+    where
+        I: IntoIterator<Item = Key>,
+    {
+        self.feed_keys(keys, true);
+    }
+
+    /// Feed a single key typed at `now`
+    ///
+    /// Keys that could begin a longer mapping are held until the next key decides, or
+    /// until they time out in [`Context::tick`].
+    pub fn input_key(&mut self, key: Key, now: Instant) -> KeyOutcome {
+        self.tick(now);
+
+        let mut typeahead = std::mem::take(&mut self.typeahead);
+        typeahead.keys.push_back(TypedKey {
+            key,
+            remap: true,
+            mapped: false,
+            silent: false,
+        });
+        typeahead.since = Some(now);
+        typeahead.rejected = false;
+        self.run_typeahead(&mut typeahead, false);
+        self.typeahead = typeahead;
+
+        if !self.typeahead.keys.is_empty() || !self.typeahead.pending.is_empty() {
+            KeyOutcome::Pending
+        } else if self.typeahead.rejected {
+            KeyOutcome::Rejected
+        } else {
+            KeyOutcome::Consumed
+        }
+    }
+
+    /// Keys typed towards an incomplete command or mapping, in notation, for display
+    /// as with 'showcmd'
+    pub fn showcmd(&self) -> String {
+        let held = self.typeahead.keys.iter().map(|typed| typed.key);
+        self.typeahead.pending.clone() + &key::notation(held)
+    }
+
+    /// Resolve keys held for a longer mapping once they have waited past the timeout,
+    /// using the longest mapping they complete or else the keys themselves
+    pub fn tick(&mut self, now: Instant) {
+        if self.next_tick().is_none_or(|deadline| now < deadline) {
+            return;
+        }
+
+        let mut typeahead = std::mem::take(&mut self.typeahead);
+        self.run_typeahead(&mut typeahead, true);
+        self.typeahead = typeahead;
+    }
+
+    /// When held keys will time out, for event loops deciding how long to wait
+    pub fn next_tick(&self) -> Option<Instant> {
+        let first = self.typeahead.keys.front()?;
+        let since = self.typeahead.since?;
+        Some(since + self.timeout_len(first.key == Key::from(KeyCode::Escape))?)
+    }
+
+    /// How long held keys wait, following 'timeout', 'ttimeout' and their lengths
+    ///
+    /// Embedders reading raw terminal input can't tell `<Esc>` from the start of a key
+    /// code, so keys starting with `<Esc>` count as a key code, as in Vim.
+    fn timeout_len(&self, key_code: bool) -> Option<Duration> {
+        let timeout = self.options.bool("timeout").unwrap_or(true);
+        let ttimeout = self.options.bool("ttimeout").unwrap_or_default();
+        let timeout_len = self.options.number("timeoutlen").unwrap_or(1000);
+        let ttimeout_len = self.options.number("ttimeoutlen").unwrap_or(-1);
+
+        let len = match (key_code, timeout, ttimeout) {
+            (false, false, _) | (true, false, false) => return None,
+            (false, true, _) => timeout_len,
+            (true, _, _) if ttimeout_len >= 0 => ttimeout_len,
+            (true, _, _) => timeout_len,
+        };
+        Some(Duration::from_millis(len.max(0) as u64))
+    }
+
+    /// Feed keys, then end any insert or command line they left pending
+    ///
+    /// Mappings apply to the keys only if `remap` is set, as with `:normal` and `:normal!`.
+    pub fn input_keys_complete(&mut self, keys_str: &str, remap: bool) {
+        self.feed_keys(key::keys(keys_str), remap);
+        if self.mode != Mode::Normal || self.substitute_confirm.is_some() {
+            self.feed_keys([Key::from(KeyCode::Escape)], false);
+        }
+    }
+
+    fn feed_keys<I>(&mut self, keys: I, remap: bool)
+    where
+        I: IntoIterator<Item = Key>,
+    {
+        let mut typeahead = Typeahead::default();
+        for key in keys {
+            typeahead.keys.push_back(TypedKey {
+                key,
+                remap,
+                mapped: false,
+                silent: false,
+            });
+            self.run_typeahead(&mut typeahead, false);
+        }
+        self.run_typeahead(&mut typeahead, true);
+
+        // Keys left pending are dropped, and with them any operator they started
+        if let Mode::OperatorPending { .. } = self.mode {
+            self.mode = Mode::Normal;
+        }
+    }
+
+    /// Mapping mode matching the current mode
+    pub fn map_mode(&self) -> MapMode {
+        match self.mode {
+            Mode::Normal => MapMode::Normal,
+            Mode::OperatorPending { .. } => MapMode::OperatorPending,
+            Mode::Insert => MapMode::Insert,
+            Mode::Command(_) => MapMode::CommandLine,
+        }
+    }
+
+    /// Expand mappings at the front of the typeahead and execute the keys they produce
+    ///
+    /// Stops while the keys could still begin a longer mapping, unless `complete` says no
+    /// more keys are coming.
+    fn run_typeahead(&mut self, typeahead: &mut Typeahead, complete: bool) {
+        while let Some(first) = typeahead.keys.front().copied() {
+            if first.remap {
+                let len = typeahead.keys.iter().take_while(|k| k.remap).count();
+                let keys = typeahead
+                    .keys
+                    .iter()
+                    .take(len)
+                    .map(|k| k.key)
+                    .collect::<Vec<_>>();
+                let complete = complete || len < typeahead.keys.len();
+
+                match self.mappings.find(self.map_mode(), &keys, complete) {
+                    MapMatch::Pending => return,
+                    MapMatch::Found(len, mapping) => {
+                        typeahead.depth += 1;
+                        if typeahead.depth > self.options.number("maxmapdepth").unwrap_or(1000) {
+                            self.error(Error::new(223, "Recursive mapping"));
+                            *typeahead = Typeahead {
+                                rejected: true,
+                                ..Default::default()
+                            };
+                            return;
+                        }
+
+                        typeahead.keys.drain(..len);
+                        self.expand_mapping(typeahead, &keys[..len], mapping, first.silent);
+                        continue;
+                    }
+                    MapMatch::None => (),
+                }
+            }
+
+            typeahead.keys.pop_front();
+            if !typeahead.keys.iter().any(|k| k.mapped) {
+                typeahead.depth = 0;
+            }
+            typeahead.rejected = !self.execute_key(first, &mut typeahead.pending);
+        }
+    }
+
+    /// Replace a mapping's keys with its right-hand side at the front of the typeahead
+    fn expand_mapping(
+        &mut self,
+        typeahead: &mut Typeahead,
+        lhs: &[Key],
+        mapping: Mapping,
+        silent: bool,
+    ) {
+        let silent = silent || mapping.silent;
+        match mapping.rhs {
+            MapTarget::Keys(rhs) => {
+                // A right-hand side starting with its own keys doesn't map them again
+                let own = if rhs.starts_with(lhs) { lhs.len() } else { 0 };
+                for (i, key) in rhs.into_iter().enumerate().rev() {
+                    typeahead.keys.push_front(TypedKey {
+                        key,
+                        remap: !mapping.noremap && i >= own,
+                        mapped: true,
+                        silent,
+                    });
+                }
+            }
+            MapTarget::Callback(f) => {
+                if self.input_depth >= MAX_INPUT_DEPTH {
+                    self.error(Error::new(169, "Command too recursive"));
+                    return;
+                }
+
+                self.input_depth += 1;
+                self.silent_depth += usize::from(silent);
+                f(self);
+                self.silent_depth -= usize::from(silent);
+                self.input_depth -= 1;
+            }
+        }
+    }
+
+    /// Execute a single key, completing or extending the pending command
+    ///
+    /// Returns false if the key ended a sequence that isn't a command.
+    fn execute_key(&mut self, typed: TypedKey, pending: &mut String) -> bool {
+        if let Mode::OperatorPending { .. } = self.mode {
+            self.mode = Mode::Normal;
+        }
+
+        pending.push_str(&typed.key.to_string());
+        self.silent_depth += usize::from(typed.silent);
+        let result = self.parse_input(pending);
+        self.silent_depth -= usize::from(typed.silent);
+        let accepted = matches!(result, Ok(_) | Err(Err::Incomplete(_)));
+        if matches!(result, Err(Err::Incomplete(_))) {
+            // Keys after the operator are its motion, so the operator stays pending
+            if let (Mode::Normal, Ok((_, mode))) = (self.mode, normal_operator_pending(pending)) {
+                self.mode = mode;
+            }
+        } else {
+            pending.clear();
+        }
+        self.emit_events();
+        accepted
+    }
+
+    /// Parse key notation for a mapping, expanding `<Leader>` to 'mapleader'
+    pub fn map_keys(&self, notation: &str) -> Vec<Key> {
+        let leader = self.options.string("mapleader").unwrap_or("\\");
+        let lower = notation.to_ascii_lowercase();
+        let target = COMMAND_LEADER.to_ascii_lowercase();
+
+        let mut expanded = String::new();
+        let mut rest = 0;
+        while let Some(i) = lower[rest..].find(&target) {
+            expanded.push_str(&notation[rest..rest + i]);
+            expanded.push_str(leader);
+            rest += i + target.len();
+        }
+        expanded.push_str(&notation[rest..]);
+
+        if expanded.eq_ignore_ascii_case("<Nop>") {
+            return vec![];
+        }
+        key::keys(&expanded).collect()
+    }
+
+    /// Map keys to other keys in each of `modes`, mapping the replacement again
+    pub fn map(&mut self, modes: &[MapMode], lhs: &str, rhs: &str) {
+        let mapping = Mapping::keys(self.map_keys(rhs));
+        self.mappings.insert(modes, self.map_keys(lhs), mapping);
+    }
+
+    /// Map keys to other keys in each of `modes`, using the replacement as-is
+    pub fn noremap(&mut self, modes: &[MapMode], lhs: &str, rhs: &str) {
+        let mapping = Mapping::keys(self.map_keys(rhs)).with_noremap(true);
+        self.mappings.insert(modes, self.map_keys(lhs), mapping);
+    }
+
+    /// Map keys to a closure in each of `modes`
+    pub fn map_callback<F>(&mut self, modes: &[MapMode], lhs: &str, f: F)
+    where
+        F: Fn(&mut Context<'_>) + 'static,
+    {
+        let mapping = Mapping::callback(f);
+        self.mappings.insert(modes, self.map_keys(lhs), mapping);
+    }
+
+    /// Remove a mapping from each of `modes`, returning false if there was none
+    pub fn unmap(&mut self, modes: &[MapMode], lhs: &str) -> bool {
+        let lhs = self.map_keys(lhs);
+        self.mappings.remove(modes, &lhs)
+    }
+
+    fn start_recording(&mut self, name: char) {
+        if !(name.is_ascii_alphanumeric() || name == '"') {
+            return;
+        }
+        self.recording = Some(name);
+        self.recorded.clear();
+    }
+
+    fn stop_recording(&mut self) {
+        if let Some(name) = self.recording.take() {
+            let text = std::mem::take(&mut self.recorded);
+            self.registers.record(name, Register::charwise(text));
+        }
+    }
+
+    /// Execute the contents of a register as typed keys
+    pub fn execute_register(&mut self, name: char) {
+        let name = if name == '@' {
+            match self.last_executed {
+                Some(name) => name,
+                None => {
+                    self.error(Error::new(748, "No previously used register"));
+                    return;
+                }
+            }
+        } else {
+            name
+        };
+
+        let Some(register) = self.registers.get(name) else {
+            return;
+        };
+
+        let keys = register.text.clone();
+        self.last_executed = Some(name);
+        self.input_keys(&keys);
+    }
+
     fn input_command<'i>(&mut self, command: Command<'i>) -> Result<'i> {
+        if command.modifies()
+            && self.active_buffer_id() == BufferId::Edit
+            && !self.check_modifiable()
+        {
+            return Ok(());
+        }
+
         match command {
             Command::Context(c) => match c {
                 ContextCommand::RunCommand => {
                     self.command();
-                    return Ok(());
+                    Ok(())
                 }
                 ContextCommand::ChangeMode(mode) => {
                     self.set_mode(mode);
-                    return Ok(());
+                    Ok(())
+                }
+                ContextCommand::Ex(line) => {
+                    self.ex(line);
+                    Ok(())
+                }
+                ContextCommand::Window(key, count) => {
+                    ex::windows::wincmd(self, key, count);
+                    Ok(())
+                }
+                ContextCommand::Record(name) => {
+                    self.start_recording(name);
+                    Ok(())
+                }
+                ContextCommand::ExCount(name, count) => {
+                    self.ex(&format!("{} {}", name, count));
+                    Ok(())
+                }
+                ContextCommand::Execute(name, count) => {
+                    // Stop at a run that fails or does nothing, as it would the next time too
+                    let state = |ctx: &Self| {
+                        let buffer = &ctx.buffer_edit;
+                        (ctx.buffers.current(), buffer.change_tick(), buffer.cursor)
+                    };
+                    for _ in 0..count {
+                        let (mark, before) = (self.messages.mark(), state(self));
+                        self.execute_register(name);
+                        if self.messages.error_since(mark) || state(self) == before {
+                            break;
+                        }
+                    }
+                    Ok(())
+                }
+                ContextCommand::Put { before, count } => {
+                    self.put(before, count);
+                    Ok(())
+                }
+                ContextCommand::StartInsert { count, newline } => {
+                    self.insert_repeat = Some(InsertRepeat {
+                        count,
+                        newline,
+                        text: String::new(),
+                    });
+                    Ok(())
+                }
+                ContextCommand::FinishInsert => {
+                    self.finish_insert();
+                    Ok(())
                 }
             },
             Command::Buffer(c) => {
+                if let (Mode::Insert, Some(repeat)) = (self.mode, &mut self.insert_repeat) {
+                    repeat.record(&c);
+                }
+
                 let block_newline = self.block_newline();
+                self.buffer_edit.tabs = TabSettings::from(&self.options);
+                let id = self.active_buffer_id();
                 let buffer = self.active_buffer_mut();
                 match c {
                     BufferCommand::Motion(m) => buffer.motion(m, block_newline),
-                    BufferCommand::Insert(s) => buffer.insert(s, block_newline),
-                    BufferCommand::Operator(o) => buffer.operator(o),
+                    BufferCommand::Insert(s) => buffer.insert(s),
+                    BufferCommand::Operator(o) => {
+                        // Editing the command line doesn't touch the registers
+                        let register = buffer.operator(o).filter(|_| id == BufferId::Edit);
+                        if let Some(register) = register {
+                            if !register.text.is_empty() {
+                                self.registers.delete(None, register);
+                            }
+                        }
+                    }
+                    BufferCommand::InsertTab => buffer.insert_tab(),
+                    BufferCommand::Backspace => buffer.backspace(),
+                    BufferCommand::Undo(count) => self.undo(count),
+                    BufferCommand::Redo(count) => self.redo(count),
                 };
-                return Ok(());
+                if block_newline {
+                    self.buffer_edit.clamp_cursor();
+                }
+                Ok(())
             }
             Command::Raw(input) => match self.mode {
-                Mode::Normal => self.input_command(normal_command(input)?.1),
+                Mode::Normal | Mode::OperatorPending { .. } => {
+                    self.input_command(normal_command(input)?.1)
+                }
                 Mode::Insert => self.input_command(insert_command(input)?.1),
                 Mode::Command(_) => self.input_command(command_command(input)?.1),
             },
@@ -160,7 +1403,7 @@ impl<'a> Context<'a> {
                 for command in m {
                     self.input_command(command)?;
                 }
-                return Ok(());
+                Ok(())
             }
         }
     }
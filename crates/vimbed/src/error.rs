@@ -0,0 +1,26 @@
+use std::fmt::Display;
+
+/// Error reported to the user, numbered as in Vim
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Error {
+    /// Vim's error number, such as 486 for `E486: Pattern not found`
+    pub code: u32,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new(code: u32, message: impl Into<String>) -> Self {
+        Error {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "E{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
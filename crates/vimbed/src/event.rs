@@ -0,0 +1,63 @@
+use std::ops::Range;
+
+use crate::{buffer::Cursor, context::BufferId, mode::Mode};
+
+/// Text replaced in a buffer by an edit, undo or redo
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Change {
+    /// Byte offset of the replaced text, in the text as it was before the change
+    pub offset: usize,
+    pub removed: String,
+    pub inserted: String,
+    /// Number of lines added, or removed if negative
+    pub line_delta: isize,
+    /// Cursor when the change was made
+    pub cursor_before: Cursor,
+    /// Cursor once the command making the change finished
+    pub cursor_after: Cursor,
+    /// Undo group the change belongs to, or reverts and reapplies for undo and redo
+    pub undo_group: u64,
+}
+
+impl Change {
+    /// Byte range replaced, in the text as it was before the change
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.removed.len()
+    }
+}
+
+/// Notification of a change to the editor state, sent once the command causing it finishes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Change {
+        buffer: BufferId,
+        change: Change,
+    },
+    Mode {
+        old: Mode,
+        new: Mode,
+    },
+    CursorMove {
+        buffer: BufferId,
+        old: Cursor,
+        new: Cursor,
+    },
+    /// Another listed buffer became the edit buffer, given by number
+    Buffer {
+        old: usize,
+        new: usize,
+    },
+    /// Another window became current, given by id
+    Window {
+        old: usize,
+        new: usize,
+    },
+    /// Another tab page became current, given by number
+    TabPage {
+        old: usize,
+        new: usize,
+    },
+}
+
+/// Callback invoked with each event
+pub type EventCallback = Box<dyn FnMut(&Event)>;
@@ -0,0 +1,148 @@
+use crate::{char_len::CharLen, context::Context, error::Error, option::OptionValue};
+
+use super::ExCommand;
+
+/// Whether switching away from the current buffer would hide unsaved changes that
+/// 'hidden' doesn't allow, reporting an error if so
+fn refuse_hide(ctx: &mut Context, command: &ExCommand) -> bool {
+    let refuse =
+        !command.bang && ctx.buffer_edit.modified() && ctx.options.bool("hidden") != Some(true);
+    if refuse {
+        ctx.error(Error::new(
+            37,
+            "No write since last change (add ! to override)",
+        ));
+    }
+    refuse
+}
+
+/// Value of a boolean buffer-local option for a listed buffer, current or not
+fn buffer_option(ctx: &Context, number: usize, name: &str) -> bool {
+    let local = ctx
+        .buffers
+        .get(number)
+        .filter(|_| number != ctx.buffers.current())
+        .and_then(|entry| entry.local_options.get(name));
+    match local.or_else(|| ctx.options.get(name)) {
+        Some(OptionValue::Bool(b)) => *b,
+        _ => false,
+    }
+}
+
+/// `:ls`, `:buffers` and `:files`
+///
+/// Lists each buffer with its number, flags, name and cursor line as Vim does.
+pub fn ex_ls(ctx: &mut Context) {
+    let current = ctx.buffers.current();
+    let alternate = ctx.buffers.alternate();
+
+    let mut lines = vec![];
+    for entry in ctx.buffers.iter() {
+        let Some(buffer) = ctx.listed_buffer(entry.number) else {
+            continue;
+        };
+        let which = match entry.number {
+            n if n == current => '%',
+            n if Some(n) == alternate => '#',
+            _ => ' ',
+        };
+        let active = if entry.number == current { 'a' } else { 'h' };
+        let protection = if !buffer_option(ctx, entry.number, "modifiable") {
+            '-'
+        } else if buffer_option(ctx, entry.number, "readonly") {
+            '='
+        } else {
+            ' '
+        };
+        let modified = if buffer.modified() { '+' } else { ' ' };
+
+        let mut line = format!(
+            "{:3} {}{}{}{} \"{}\"",
+            entry.number,
+            which,
+            active,
+            protection,
+            modified,
+            entry.display_name()
+        );
+        let padding = 40usize.saturating_sub(line.char_len()).max(1);
+        line.push_str(&" ".repeat(padding));
+        line.push_str(&format!("line {}", buffer.cursor.row + 1));
+        lines.push(line);
+    }
+
+    ctx.output(lines.join("\n"));
+}
+
+/// `:b[uffer][!] [N]`, `:[N]b[uffer][!]`, `:b #` and `:b {name}`
+///
+/// A name may be any part of a buffer's name that matches no other.
+pub fn ex_buffer(ctx: &mut Context, command: &ExCommand) {
+    let arg = command.args.trim();
+    let number = match arg {
+        "" => match command.range.number() {
+            Some(number) => number,
+            None => return,
+        },
+        "%" => return,
+        "#" => match ctx.buffers.alternate() {
+            Some(number) => number,
+            None => {
+                ctx.error(Error::new(23, "No alternate file"));
+                return;
+            }
+        },
+        arg => match arg.parse::<usize>() {
+            Ok(number) => number,
+            Err(_) => match ctx.buffers.find(arg)[..] {
+                [number] => number,
+                [] => {
+                    ctx.error(Error::new(94, format!("No matching buffer for {}", arg)));
+                    return;
+                }
+                _ => {
+                    ctx.error(Error::new(93, format!("More than one match for {}", arg)));
+                    return;
+                }
+            },
+        },
+    };
+
+    if ctx.buffers.get(number).is_none() {
+        ctx.error(Error::new(86, format!("Buffer {} does not exist", number)));
+        return;
+    }
+    if number != ctx.buffers.current() && !refuse_hide(ctx, command) {
+        ctx.switch_buffer(number);
+    }
+}
+
+/// `:bn[ext][!] [N]`, and `:bp[revious][!] [N]` or `:bN[ext][!] [N]` if not `forward`
+pub fn ex_bnext(ctx: &mut Context, command: &ExCommand, forward: bool) {
+    let count = command.args.trim().parse().unwrap_or(1);
+    let number = ctx.buffers.step(ctx.buffers.current(), count, forward);
+    if number != ctx.buffers.current() && !refuse_hide(ctx, command) {
+        ctx.switch_buffer(number);
+    }
+}
+
+/// `:bd[elete][!] [N]...` and `:[N]bd[elete][!]`, deleting the current buffer if no
+/// numbers are given
+pub fn ex_bdelete(ctx: &mut Context, command: &ExCommand) {
+    let args = command.args.split_whitespace().collect::<Vec<_>>();
+    if args.is_empty() {
+        let number = command.range.number().unwrap_or(ctx.buffers.current());
+        ctx.delete_buffer(number, command.bang);
+        return;
+    }
+
+    for arg in args {
+        match arg.parse() {
+            Ok(number) => ctx.delete_buffer(number, command.bang),
+            Err(_) => {
+                ctx.error(Error::new(516, format!("No buffers were deleted: {}", arg)));
+                return;
+            }
+        }
+    }
+}
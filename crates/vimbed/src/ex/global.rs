@@ -0,0 +1,105 @@
+use crate::{context::Context, error::Error, pattern};
+
+use super::{split_delimited, ExCommand};
+
+/// `:[range]g[lobal][!]/{pattern}/[cmd]` and `:[range]v[global]/{pattern}/[cmd]`
+///
+/// Marks every line in the range matching (or with `invert`, not matching) the pattern,
+/// then executes `cmd` with the cursor on each marked line in turn. Marks follow their
+/// lines through edits, so lines deleted by an earlier command are skipped.
+pub fn ex_global(ctx: &mut Context, command: &ExCommand, invert: bool) {
+    if ctx.global_busy {
+        ctx.error(Error::new(147, "Cannot do :global recursive"));
+        return;
+    }
+
+    let args = command.args;
+    let Some(delim) = args.chars().next() else {
+        ctx.error(Error::new(148, "Regular expression missing from :global"));
+        return;
+    };
+
+    if delim.is_alphanumeric() || "\\\"|".contains(delim) {
+        ctx.error(Error::new(
+            146,
+            "Regular expressions can't be delimited by letters",
+        ));
+        return;
+    }
+
+    let (pattern, cmd) = split_delimited(&args[delim.len_utf8()..], delim);
+    let cmd = cmd.unwrap_or_default().trim_start();
+
+    let pattern = if pattern.is_empty() {
+        match &ctx.last_pattern {
+            Some(pattern) => pattern.clone(),
+            None => {
+                ctx.error(Error::new(35, "No previous regular expression"));
+                return;
+            }
+        }
+    } else {
+        pattern
+    };
+    ctx.last_pattern = Some(pattern.clone());
+
+    let regex = match pattern::compile(&pattern, ctx.ignore_case(&pattern)) {
+        Ok(regex) => regex,
+        Err(_) => {
+            ctx.error(Error::new(
+                383,
+                format!("Invalid search string: {}", pattern),
+            ));
+            return;
+        }
+    };
+
+    let buffer = &mut ctx.buffer_edit;
+    let last = buffer.line_count().saturating_sub(1);
+    let Some((start, end)) = command.range.resolve(buffer, buffer.cursor.row, (0, last)) else {
+        ctx.error(Error::new(16, "Invalid range"));
+        return;
+    };
+
+    let marked = (start..=end)
+        .filter(|row| regex.is_match(&buffer.line(*row).unwrap_or_default()) != invert)
+        .collect::<Vec<_>>();
+
+    buffer.line_marks.clear();
+    for row in marked {
+        buffer.line_marks.insert(row);
+    }
+
+    if buffer.line_marks.is_empty() {
+        ctx.info(if invert {
+            format!("Pattern found in every line: {}", pattern)
+        } else {
+            format!("Pattern not found: {}", pattern)
+        });
+        return;
+    }
+
+    // Without a command, matching lines are printed
+    let print = cmd.is_empty() || cmd == "p" || cmd == "print";
+    let mut printed = vec![];
+
+    ctx.global_busy = true;
+    ctx.nested(|ctx| {
+        while let Some(row) = ctx.buffer_edit.line_marks.pop_first() {
+            let buffer = &mut ctx.buffer_edit;
+            buffer.cursor.row = row;
+            buffer.set_column(0);
+
+            if print {
+                printed.push(buffer.line(row).unwrap_or_default().into_owned());
+            } else {
+                ctx.ex(cmd);
+            }
+        }
+    });
+    ctx.global_busy = false;
+
+    if print {
+        ctx.output(printed.join("\n"));
+    }
+}
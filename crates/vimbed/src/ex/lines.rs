@@ -0,0 +1,393 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    context::Context,
+    error::Error,
+    grapheme,
+    option::TABSTOP_MAX,
+    register::{Register, Registers},
+    tab::TabSettings,
+};
+
+use super::{range::address, ExCommand};
+
+/// Report a line count in the message area when it exceeds 'report'
+fn report(ctx: &mut Context, lines: usize, what: &str) {
+    if ctx.report(lines) {
+        ctx.info(format!("{} {}", lines, what));
+    }
+}
+
+/// Resolve the command's range, defaulting to the current line
+fn line_range(ctx: &mut Context, command: &ExCommand) -> Option<(usize, usize)> {
+    let buffer = &ctx.buffer_edit;
+    let current = buffer.cursor.row;
+    let range = command.range.resolve(buffer, current, (current, current));
+    if range.is_none() {
+        ctx.error(Error::new(16, "Invalid range"));
+    }
+    range
+}
+
+/// Replace a range with `count` lines starting at its last line
+fn count_range(ctx: &Context, (_, end): (usize, usize), count: usize) -> (usize, usize) {
+    let last = ctx.buffer_edit.line_count().saturating_sub(1);
+    (end, end.saturating_add(count - 1).min(last))
+}
+
+/// Parse a trailing `{count}`
+fn count(ctx: &mut Context, args: &str) -> Result<Option<usize>, ()> {
+    let args = args.trim();
+    if args.is_empty() {
+        return Ok(None);
+    }
+
+    match args.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(Some(count)),
+        Ok(_) => {
+            ctx.error(Error::new(939, "Positive count required"));
+            Err(())
+        }
+        Err(_) => {
+            ctx.error(Error::new(488, format!("Trailing characters: {}", args)));
+            Err(())
+        }
+    }
+}
+
+/// Parse `[x] [count]` arguments
+fn register_count(ctx: &mut Context, args: &str) -> Result<(Option<char>, Option<usize>), ()> {
+    let args = args.trim();
+    match args.chars().next() {
+        Some(name) if !name.is_ascii_digit() && Registers::is_valid(name) => {
+            Ok((Some(name), count(ctx, &args[1..])?))
+        }
+        _ => Ok((None, count(ctx, args)?)),
+    }
+}
+
+/// Parse the destination of `:m` and `:t`, returning the row to insert lines before
+fn destination(ctx: &mut Context, args: &str) -> Option<usize> {
+    let args = args.trim();
+    let buffer = &ctx.buffer_edit;
+
+    let row = match address(args) {
+        Ok(("", address)) if address.is_zero() => Some(0),
+        Ok(("", address)) => address
+            .resolve(buffer, buffer.cursor.row)
+            .map(|row| row + 1),
+        _ => None,
+    };
+
+    if row.is_none() {
+        ctx.error(Error::new(14, "Invalid address"));
+    }
+    row
+}
+
+/// `:[range]d[elete] [x] {count}`
+pub fn ex_delete(ctx: &mut Context, command: &ExCommand) {
+    let Ok((name, count)) = register_count(ctx, command.args) else {
+        return;
+    };
+    let Some(mut range) = line_range(ctx, command) else {
+        return;
+    };
+    if let Some(count) = count {
+        range = count_range(ctx, range, count);
+    }
+
+    let (start, end) = range;
+    let text = ctx.buffer_edit.delete_lines(start, end);
+    ctx.registers.delete(name, Register::linewise(text));
+    ctx.buffer_edit.set_cursor_line(start);
+
+    report(ctx, end + 1 - start, "fewer lines");
+}
+
+/// `:[range]y[ank] [x] {count}`
+pub fn ex_yank(ctx: &mut Context, command: &ExCommand) {
+    let Ok((name, count)) = register_count(ctx, command.args) else {
+        return;
+    };
+    let Some(mut range) = line_range(ctx, command) else {
+        return;
+    };
+    if let Some(count) = count {
+        range = count_range(ctx, range, count);
+    }
+
+    let (start, end) = range;
+    let text = ctx.buffer_edit.lines_text(start, end);
+    ctx.registers.yank(name, Register::linewise(text));
+
+    report(ctx, end + 1 - start, "lines yanked");
+}
+
+/// `:[range]m[ove] {address}`
+pub fn ex_move(ctx: &mut Context, command: &ExCommand) {
+    let Some((start, end)) = line_range(ctx, command) else {
+        return;
+    };
+    let Some(row) = destination(ctx, command.args) else {
+        return;
+    };
+
+    if row > start && row <= end {
+        ctx.error(Error::new(134, "Cannot move a range of lines into itself"));
+        return;
+    }
+
+    let count = end + 1 - start;
+    let buffer = &mut ctx.buffer_edit;
+    if row != start && row != end + 1 {
+        let text = buffer.delete_lines(start, end);
+        let row = if row > end { row - count } else { row };
+        buffer.insert_lines(row, &text);
+        buffer.set_cursor_line(row + count - 1);
+    } else {
+        buffer.set_cursor_line(end);
+    }
+
+    report(ctx, count, "lines moved");
+}
+
+/// `:[range]co[py] {address}` and `:[range]t {address}`
+pub fn ex_copy(ctx: &mut Context, command: &ExCommand) {
+    let Some((start, end)) = line_range(ctx, command) else {
+        return;
+    };
+    let Some(row) = destination(ctx, command.args) else {
+        return;
+    };
+
+    let count = end + 1 - start;
+    let buffer = &mut ctx.buffer_edit;
+    let text = buffer.lines_text(start, end);
+    buffer.insert_lines(row, &text);
+    buffer.set_cursor_line(row + count - 1);
+
+    report(ctx, count, "more lines");
+}
+
+/// `:[range]j[oin][!] {count}`
+///
+/// Joins with a single space in place of the line break and leading whitespace,
+/// unless `!` is given.
+pub fn ex_join(ctx: &mut Context, command: &ExCommand) {
+    let Ok(count) = count(ctx, command.args) else {
+        return;
+    };
+    let Some(range) = line_range(ctx, command) else {
+        return;
+    };
+
+    let last = ctx.buffer_edit.line_count().saturating_sub(1);
+    let (start, end) = match count {
+        Some(count) => (range.1, range.1.saturating_add(count - 1)),
+        None => range,
+    };
+    // At least two lines are joined, as for `J`
+    let end = end.max(start + 1).min(last);
+
+    if start == end {
+        return;
+    }
+
+    let buffer = &mut ctx.buffer_edit;
+    let mut joined = buffer.line(start).unwrap_or_default().into_owned();
+    for row in start + 1..=end {
+        let line = buffer.line(row).unwrap_or_default();
+        if command.bang {
+            joined.push_str(&line);
+            continue;
+        }
+
+        let line = line.trim_start();
+        if !joined.is_empty()
+            && !joined.ends_with([' ', '\t'])
+            && !line.is_empty()
+            && !line.starts_with(')')
+        {
+            joined.push(' ');
+        }
+        joined.push_str(line);
+    }
+
+    let from = buffer.line_offset(start);
+    let to = buffer.line_offset(end) + buffer.line(end).unwrap_or_default().len();
+    buffer.splice(from..to, &joined);
+    buffer.set_cursor_line(start);
+}
+
+/// `:[range]> {count}` and `:[range]< {count}`, where repeating the name shifts further
+pub fn ex_shift(ctx: &mut Context, command: &ExCommand) {
+    let right = command.name == ">";
+    let levels = 1 + command
+        .args
+        .chars()
+        .take_while(|c| c.to_string() == command.name)
+        .count();
+
+    let Ok(count) = count(ctx, &command.args[levels - 1..]) else {
+        return;
+    };
+    let Some(mut range) = line_range(ctx, command) else {
+        return;
+    };
+    if let Some(count) = count {
+        range = count_range(ctx, range, count);
+    }
+
+    let (start, end) = range;
+    let tabs = TabSettings::from(&ctx.options);
+    let buffer = &mut ctx.buffer_edit;
+    for row in start..=end {
+        let line = buffer.line(row).unwrap_or_default();
+        if line.is_empty() {
+            continue;
+        }
+
+        let indent_len = line.len() - line.trim_start().len();
+        let indent = tabs.advance(&line[..indent_len], 0);
+
+        let indent = if right {
+            indent + tabs.shift() * levels
+        } else {
+            indent.saturating_sub(tabs.shift() * levels)
+        };
+
+        let offset = buffer.line_offset(row);
+        buffer.splice(offset..offset + indent_len, &tabs.indent(0, indent));
+    }
+    buffer.set_cursor_line(end);
+
+    let lines = end + 1 - start;
+    if ctx.report(lines) {
+        ctx.info(format!(
+            "{} lines {}ed {} time{}",
+            lines,
+            command.name,
+            levels,
+            if levels == 1 { "" } else { "s" }
+        ));
+    }
+}
+
+/// `:[range]ret[ab][!] [new_tabstop]`
+///
+/// Rewrites whitespace containing tabs for 'tabstop' set to `new_tabstop`, keeping text at
+/// the same virtual columns, then sets 'tabstop'. With `!`, runs of spaces are rewritten too.
+pub fn ex_retab(ctx: &mut Context, command: &ExCommand) {
+    let args = command.args.trim();
+    let old = TabSettings::from(&ctx.options);
+    let new_tab_stop = if args.is_empty() {
+        old.tab_stop
+    } else {
+        match args.parse::<usize>() {
+            Ok(0) => old.tab_stop,
+            Ok(n) if n <= TABSTOP_MAX as usize => n,
+            _ => {
+                ctx.error(Error::new(475, format!("Invalid argument: {}", args)));
+                return;
+            }
+        }
+    };
+    let new = TabSettings {
+        tab_stop: new_tab_stop,
+        ..old
+    };
+
+    let buffer = &mut ctx.buffer_edit;
+    let last = buffer.line_count().saturating_sub(1);
+    let Some((start, end)) = command.range.resolve(buffer, buffer.cursor.row, (0, last)) else {
+        ctx.error(Error::new(16, "Invalid range"));
+        return;
+    };
+
+    for row in start..=end {
+        let line = buffer.line(row).unwrap_or_default();
+        let mut retabbed = String::new();
+        let mut column = 0;
+        let mut run = String::new();
+        let mut run_start = 0;
+        let mut changed = false;
+
+        let mut flush = |run: &mut String, retabbed: &mut String, from: usize, to: usize| {
+            let rewrite = run.contains('\t') || (command.bang && run.len() > 1);
+            if rewrite {
+                let whitespace = new.indent(from, to);
+                changed |= whitespace != *run;
+                retabbed.push_str(&whitespace);
+            } else {
+                retabbed.push_str(run);
+            }
+            run.clear();
+        };
+
+        for g in line.graphemes(true) {
+            if g == " " || g == "\t" {
+                if run.is_empty() {
+                    run_start = column;
+                }
+                run.push_str(g);
+            } else {
+                flush(&mut run, &mut retabbed, run_start, column);
+                retabbed.push_str(g);
+            }
+            column += old.width(g, column);
+        }
+        flush(&mut run, &mut retabbed, run_start, column);
+
+        if changed {
+            let offset = buffer.line_offset(row);
+            buffer.splice(offset..offset + line.len(), &retabbed);
+        }
+    }
+
+    let cursor_row = buffer.cursor.row;
+    let line = buffer.line(cursor_row).unwrap_or_default();
+    let cursor_column = buffer.cursor.column.min(grapheme::last_boundary(&line));
+    buffer.set_column(grapheme::floor_boundary(&line, cursor_column));
+
+    if new_tab_stop != old.tab_stop {
+        if let Err(error) = ctx.options.set("tabstop", new_tab_stop as i64) {
+            ctx.error(error);
+        }
+    }
+}
+
+/// `:[line]pu[t][!] [x]`, always linewise
+pub fn ex_put(ctx: &mut Context, command: &ExCommand) {
+    let name = command.args.trim().chars().next().unwrap_or('"');
+    let Some(register) = ctx.registers.get(name) else {
+        ctx.error(Error::new(353, format!("Nothing in register {}", name)));
+        return;
+    };
+
+    let mut text = register.text.clone();
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+
+    let row = match &command.range.start {
+        Some(address) if address.is_zero() => 0,
+        _ => {
+            let Some((_, end)) = line_range(ctx, command) else {
+                return;
+            };
+            if command.bang {
+                end
+            } else {
+                end + 1
+            }
+        }
+    };
+
+    let count = text.matches('\n').count();
+    let buffer = &mut ctx.buffer_edit;
+    buffer.insert_lines(row, &text);
+    buffer.set_cursor_line(row + count - 1);
+
+    report(ctx, count, "more lines");
+}
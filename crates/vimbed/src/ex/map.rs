@@ -0,0 +1,141 @@
+use crate::{
+    context::Context,
+    error::Error,
+    key::{self, Key},
+    map::{MapMode, Mapping},
+};
+
+use super::ExCommand;
+
+/// Which of the mapping commands was given
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MapCommand {
+    Map,
+    Noremap,
+    Unmap,
+}
+
+/// Mapping command names with their minimum abbreviation, mode prefix and kind
+const MAP_COMMANDS: &[(&str, usize, &str, MapCommand)] = &[
+    ("map", 3, "", MapCommand::Map),
+    ("nmap", 2, "n", MapCommand::Map),
+    ("vmap", 2, "v", MapCommand::Map),
+    ("xmap", 2, "x", MapCommand::Map),
+    ("smap", 4, "s", MapCommand::Map),
+    ("omap", 2, "o", MapCommand::Map),
+    ("imap", 2, "i", MapCommand::Map),
+    ("cmap", 2, "c", MapCommand::Map),
+    ("noremap", 2, "", MapCommand::Noremap),
+    ("nnoremap", 2, "n", MapCommand::Noremap),
+    ("vnoremap", 2, "v", MapCommand::Noremap),
+    ("xnoremap", 2, "x", MapCommand::Noremap),
+    ("snoremap", 4, "s", MapCommand::Noremap),
+    ("onoremap", 3, "o", MapCommand::Noremap),
+    ("inoremap", 3, "i", MapCommand::Noremap),
+    ("cnoremap", 3, "c", MapCommand::Noremap),
+    ("unmap", 3, "", MapCommand::Unmap),
+    ("nunmap", 3, "n", MapCommand::Unmap),
+    ("vunmap", 2, "v", MapCommand::Unmap),
+    ("xunmap", 2, "x", MapCommand::Unmap),
+    ("sunmap", 4, "s", MapCommand::Unmap),
+    ("ounmap", 2, "o", MapCommand::Unmap),
+    ("iunmap", 2, "i", MapCommand::Unmap),
+    ("cunmap", 2, "c", MapCommand::Unmap),
+];
+
+/// The mapping command named by `command`, with its mode prefix
+pub fn map_command(command: &ExCommand) -> Option<(MapCommand, &'static str)> {
+    MAP_COMMANDS
+        .iter()
+        .find(|(full, min, _, _)| command.is(full, *min))
+        .map(|(_, _, prefix, kind)| (*kind, *prefix))
+}
+
+/// `:{mode}map[!] [<silent>] {lhs} {rhs}`, `:{mode}noremap[!]` and `:{mode}unmap[!] {lhs}`
+///
+/// Without `{rhs}`, lists the mappings starting with `{lhs}`, or all mappings without
+/// `{lhs}`. `<Leader>` in either side is replaced with 'mapleader' when defining.
+pub fn ex_map(ctx: &mut Context, command: &ExCommand, kind: MapCommand, prefix: &str) {
+    let Some(modes) = MapMode::from_prefix(prefix, command.bang) else {
+        ctx.error(Error::new(477, "No ! allowed"));
+        return;
+    };
+
+    let mut args = command.args;
+    let mut silent = false;
+    loop {
+        if let Some(rest) = args.strip_prefix("<silent>") {
+            silent = true;
+            args = rest.trim_start();
+        } else if args.starts_with("<expr>") {
+            ctx.error(Error::new(474, "Invalid argument: <expr> is not supported"));
+            return;
+        } else {
+            break;
+        }
+    }
+
+    if kind == MapCommand::Unmap {
+        let lhs = args.trim_end();
+        if lhs.is_empty() {
+            ctx.error(Error::new(474, "Invalid argument"));
+        } else if !ctx.unmap(modes, lhs) {
+            ctx.error(Error::new(31, "No such mapping"));
+        }
+        return;
+    }
+
+    let (lhs, rhs) = match args.split_once([' ', '\t']) {
+        Some((lhs, rhs)) => (lhs, rhs.trim_start()),
+        None => (args, ""),
+    };
+
+    if rhs.is_empty() {
+        let listing = list(ctx, modes, lhs);
+        ctx.output(listing);
+        return;
+    }
+
+    let mapping = Mapping::keys(ctx.map_keys(rhs))
+        .with_noremap(kind == MapCommand::Noremap)
+        .with_silent(silent);
+    let lhs = ctx.map_keys(lhs);
+    ctx.mappings.insert(modes, lhs, mapping);
+}
+
+/// Mappings in `modes` starting with `lhs`, one per line
+///
+/// A mapping defined the same way in several of the modes is listed once.
+fn list(ctx: &Context, modes: &[MapMode], lhs: &str) -> String {
+    let prefix = ctx.map_keys(lhs);
+    let mut entries: Vec<(&Vec<Key>, &Mapping, Vec<MapMode>)> = vec![];
+    for &mode in modes {
+        for (lhs, mapping) in ctx.mappings.iter(mode, &prefix) {
+            let same = entries
+                .iter_mut()
+                .find(|(other_lhs, other, _)| *other_lhs == lhs && *other == mapping);
+            match same {
+                Some((_, _, modes)) => modes.push(mode),
+                None => entries.push((lhs, mapping, vec![mode])),
+            }
+        }
+    }
+
+    let lines = entries
+        .into_iter()
+        .map(|(lhs, mapping, modes)| {
+            format!(
+                "{:<3}{:<11} {}",
+                MapMode::letters(&modes),
+                key::notation(lhs.iter().copied()),
+                mapping.display()
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        "No mapping found".into()
+    } else {
+        lines.join("\n")
+    }
+}
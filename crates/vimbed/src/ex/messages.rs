@@ -0,0 +1,23 @@
+use crate::{context::Context, error::Error};
+
+use super::ExCommand;
+
+/// `:mes[sages]` and `:mes[sages] clear`
+///
+/// Shows the messages kept from earlier commands, oldest first, up to 'msghistory'.
+pub fn ex_messages(ctx: &mut Context, command: &ExCommand) {
+    match command.args.trim_end() {
+        "" => {
+            let history = ctx
+                .messages
+                .history()
+                .map(|message| message.to_string())
+                .collect::<Vec<_>>();
+            if !history.is_empty() {
+                ctx.output(history.join("\n"));
+            }
+        }
+        "clear" => ctx.messages.clear_history(),
+        args => ctx.error(Error::new(474, format!("Invalid argument: {}", args))),
+    }
+}
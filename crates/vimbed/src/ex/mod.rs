@@ -0,0 +1,200 @@
+pub mod buffers;
+pub mod global;
+pub mod lines;
+pub mod map;
+pub mod messages;
+pub mod normal;
+pub mod range;
+pub mod set;
+pub mod sort;
+pub mod substitute;
+pub mod tab_pages;
+pub mod windows;
+
+use nom::IResult;
+
+use crate::{context::Context, error::Error, option::SetScope, window::SplitDirection};
+
+use range::{range, Range};
+
+/// Parsed ex command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExCommand<'a> {
+    pub range: Range,
+    pub name: &'a str,
+    pub bang: bool,
+    pub args: &'a str,
+}
+
+impl ExCommand<'_> {
+    /// Whether the command name is a valid abbreviation of `full`, at least `min` chars long
+    pub fn is(&self, full: &str, min: usize) -> bool {
+        self.name.len() >= min && full.starts_with(self.name)
+    }
+}
+
+fn command_name(input: &str) -> IResult<&str, &str> {
+    nom::branch::alt((
+        nom::character::complete::alpha1,
+        nom::combinator::recognize(nom::character::complete::one_of("&<>!=~#@*")),
+    ))(input)
+}
+
+/// Parse an ex command line of the form `[range]name[!] [args]`
+pub fn ex_command(input: &str) -> IResult<&str, ExCommand<'_>> {
+    let (input, _) = nom::bytes::complete::take_while(|c| c == ':' || c == ' ')(input)?;
+    let (input, range) = range(input)?;
+    let (input, _) = nom::character::complete::space0(input)?;
+    let (input, name) = command_name(input)?;
+    let (input, bang) = nom::combinator::opt(nom::character::complete::char('!'))(input)?;
+    let (args, _) = nom::character::complete::space0(input)?;
+
+    Ok((
+        "",
+        ExCommand {
+            range,
+            name,
+            bang: bang.is_some(),
+            args,
+        },
+    ))
+}
+
+/// Split `input` at the first occurrence of `delim` not preceded by a backslash,
+/// unescaping `\{delim}` along the way
+pub fn split_delimited(input: &str, delim: char) -> (String, Option<&str>) {
+    let mut out = String::new();
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, c)) if c == delim => out.push(c),
+                Some((_, c)) => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                None => out.push('\\'),
+            },
+            c if c == delim => return (out, Some(&input[i + c.len_utf8()..])),
+            c => out.push(c),
+        }
+    }
+    (out, None)
+}
+
+/// Whether the command changes the text of the edit buffer itself, rather than
+/// through other commands as `:global` and `:normal` do
+///
+/// `:s` and `:&` check for themselves, as only counting matches with `n` changes nothing.
+fn modifies(command: &ExCommand) -> bool {
+    matches!(command.name, ">" | "<" | "t")
+        || command.is("delete", 1)
+        || command.is("move", 1)
+        || command.is("copy", 2)
+        || command.is("join", 1)
+        || command.is("put", 2)
+        || command.is("retab", 3)
+        || command.is("sort", 3)
+}
+
+/// Execute a built-in ex command, returning false if `line` does not name one
+///
+/// Writing a 'readonly' buffer and quitting with unsaved changes are refused here
+/// unless forced with `!`, while the commands themselves are left to the embedder.
+/// Quitting with more than one window or tab page closes the current window instead.
+pub fn execute(ctx: &mut Context, line: &str) -> bool {
+    let Ok((_, command)) = ex_command(line) else {
+        return false;
+    };
+
+    if modifies(&command) && !ctx.check_modifiable() {
+        return true;
+    }
+
+    let write = command.is("write", 1) || command.is("wq", 2) || command.is("xit", 1);
+    if write && !command.bang && ctx.options.bool("readonly") == Some(true) {
+        ctx.error(Error::new(
+            45,
+            "'readonly' option is set (add ! to override)",
+        ));
+        return true;
+    }
+
+    if command.is("quit", 1) && (ctx.windows.len() > 1 || ctx.tab_pages.len() > 1) {
+        windows::ex_quit_window(ctx, &command);
+        return true;
+    }
+
+    if command.is("quit", 1) && !command.bang {
+        if ctx.buffer_edit.modified() {
+            ctx.error(Error::new(
+                37,
+                "No write since last change (add ! to override)",
+            ));
+            return true;
+        }
+
+        let hidden = ctx.buffers.iter().find(|entry| {
+            let buffer = entry.buffer.as_ref();
+            buffer.is_some_and(|buffer| buffer.modified())
+        });
+        if let Some(entry) = hidden {
+            let message = format!(
+                "No write since last change for buffer \"{}\"",
+                entry.display_name()
+            );
+            ctx.error(Error::new(162, message));
+            return true;
+        }
+    }
+
+    match command.name {
+        "&" => substitute::ex_substitute_repeat(ctx, &command),
+        _ if command.is("substitute", 1) => substitute::ex_substitute(ctx, &command),
+        _ if command.is("global", 1) => global::ex_global(ctx, &command, command.bang),
+        _ if command.is("vglobal", 1) => global::ex_global(ctx, &command, true),
+        ">" | "<" => lines::ex_shift(ctx, &command),
+        _ if command.is("delete", 1) => lines::ex_delete(ctx, &command),
+        _ if command.is("yank", 1) => lines::ex_yank(ctx, &command),
+        _ if command.is("move", 1) => lines::ex_move(ctx, &command),
+        _ if command.is("copy", 2) || command.name == "t" => lines::ex_copy(ctx, &command),
+        _ if command.is("join", 1) => lines::ex_join(ctx, &command),
+        _ if command.is("put", 2) => lines::ex_put(ctx, &command),
+        _ if command.is("retab", 3) => lines::ex_retab(ctx, &command),
+        _ if command.is("normal", 4) => normal::ex_normal(ctx, &command),
+        _ if command.is("sort", 3) => sort::ex_sort(ctx, &command),
+        _ if command.is("set", 2) => set::ex_set(ctx, &command, SetScope::Both),
+        _ if command.is("setlocal", 4) => set::ex_set(ctx, &command, SetScope::Local),
+        _ if command.is("setglobal", 4) => set::ex_set(ctx, &command, SetScope::Global),
+        _ if command.is("undo", 1) => ctx.undo(1),
+        _ if command.is("redo", 3) => ctx.redo(1),
+        _ if command.is("messages", 3) => messages::ex_messages(ctx, &command),
+        _ if command.is("ls", 2) || command.is("buffers", 7) || command.is("files", 5) => {
+            buffers::ex_ls(ctx)
+        }
+        _ if command.is("buffer", 1) => buffers::ex_buffer(ctx, &command),
+        _ if command.is("bnext", 2) => buffers::ex_bnext(ctx, &command, true),
+        _ if command.is("bNext", 2) || command.is("bprevious", 2) => {
+            buffers::ex_bnext(ctx, &command, false)
+        }
+        _ if command.is("bdelete", 2) => buffers::ex_bdelete(ctx, &command),
+        _ if command.is("split", 2) => ctx.split_window(SplitDirection::Horizontal),
+        _ if command.is("vsplit", 2) => ctx.split_window(SplitDirection::Vertical),
+        _ if command.is("close", 3) => ctx.close_window(ctx.windows.current()),
+        _ if command.is("only", 2) => ctx.only_window(),
+        _ if command.is("wincmd", 4) => windows::ex_wincmd(ctx, &command),
+        _ if command.is("tabnew", 6) || command.is("tabedit", 4) => ctx.new_tab_page(),
+        _ if command.is("tabclose", 4) => tab_pages::ex_tabclose(ctx, &command),
+        _ if command.is("tabnext", 4) => tab_pages::ex_tabnext(ctx, &command),
+        _ if command.is("tabNext", 4) || command.is("tabprevious", 4) => {
+            tab_pages::ex_tabprevious(ctx, &command)
+        }
+        _ if command.is("tabmove", 4) => tab_pages::ex_tabmove(ctx, &command),
+        _ => match map::map_command(&command) {
+            Some((kind, prefix)) => map::ex_map(ctx, &command, kind, prefix),
+            None => return false,
+        },
+    }
+
+    true
+}
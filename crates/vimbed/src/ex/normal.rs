@@ -0,0 +1,42 @@
+use crate::{context::Context, error::Error};
+
+use super::ExCommand;
+
+/// `:[range]norm[al][!] {commands}`
+///
+/// Executes `{commands}` as typed normal mode keys, once at the cursor or once per line
+/// of the range with the cursor at the start of the line. An insert or command line left
+/// pending by the keys is ended as if `<Esc>` were typed. With `!`, mappings don't apply.
+pub fn ex_normal(ctx: &mut Context, command: &ExCommand) {
+    if command.args.is_empty() {
+        ctx.error(Error::new(471, "Argument required"));
+        return;
+    }
+
+    if command.range.is_empty() {
+        ctx.nested(|ctx| ctx.input_keys_complete(command.args, !command.bang));
+        return;
+    }
+
+    let buffer = &ctx.buffer_edit;
+    let current = buffer.cursor.row;
+    let Some((start, end)) = command.range.resolve(buffer, current, (current, current)) else {
+        ctx.error(Error::new(16, "Invalid range"));
+        return;
+    };
+
+    // Keys run on every line are undone together
+    ctx.nested(|ctx| {
+        for row in start..=end {
+            let buffer = &mut ctx.buffer_edit;
+            if row >= buffer.line_count() {
+                break;
+            }
+
+            buffer.cursor.row = row;
+            buffer.set_column(0);
+
+            ctx.input_keys_complete(command.args, !command.bang);
+        }
+    });
+}
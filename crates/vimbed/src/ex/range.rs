@@ -0,0 +1,231 @@
+use nom::IResult;
+
+use crate::{buffer::Buffer, pattern, storage::TextStorage};
+
+/// Base of a line address, before offsets are applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressBase {
+    /// `N`, 1-based
+    Line(usize),
+    /// `.`
+    Current,
+    /// `$`
+    Last,
+    /// `/pat/`
+    Forward(String),
+    /// `?pat?`
+    Backward(String),
+}
+
+/// Single line address with trailing `+N` / `-N` offsets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    pub base: AddressBase,
+    pub offset: isize,
+}
+
+impl Address {
+    /// Whether this is the special line `0`, meaning before the first line
+    pub fn is_zero(&self) -> bool {
+        self.base == AddressBase::Line(0) && self.offset == 0
+    }
+
+    /// Resolve to a 0-based line index, relative to `current`
+    pub fn resolve<S: TextStorage>(&self, buffer: &Buffer<S>, current: usize) -> Option<usize> {
+        let last = buffer.line_count().saturating_sub(1);
+        let line = match &self.base {
+            AddressBase::Line(0) => 0,
+            AddressBase::Line(line) => line - 1,
+            AddressBase::Current => current,
+            AddressBase::Last => last,
+            AddressBase::Forward(pat) => search_line(buffer, pat, current, true)?,
+            AddressBase::Backward(pat) => search_line(buffer, pat, current, false)?,
+        };
+
+        line.checked_add_signed(self.offset)
+            .filter(|&line| line <= last)
+    }
+}
+
+/// Find the next line after (or before) `current` matching `pat`, wrapping around the buffer
+fn search_line<S: TextStorage>(
+    buffer: &Buffer<S>,
+    pat: &str,
+    current: usize,
+    forward: bool,
+) -> Option<usize> {
+    let regex = pattern::compile(pat, false).ok()?;
+    let count = buffer.line_count();
+    (1..=count)
+        .map(|i| {
+            if forward {
+                (current + i) % count
+            } else {
+                (current + count * 2 - i) % count
+            }
+        })
+        .find(|line| regex.is_match(&buffer.line(*line).unwrap_or_default()))
+}
+
+/// Line range preceding an ex command
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Range {
+    pub start: Option<Address>,
+    pub end: Option<Address>,
+    /// `%`
+    pub whole: bool,
+}
+
+impl Range {
+    /// Whether the command line specified any addresses
+    pub fn is_empty(&self) -> bool {
+        !self.whole && self.start.is_none() && self.end.is_none()
+    }
+
+    /// Single line number given without offsets, as in `:3b`, which some commands take as a
+    /// count or buffer number
+    pub fn number(&self) -> Option<usize> {
+        match (&self.start, &self.end) {
+            (
+                Some(Address {
+                    base: AddressBase::Line(number),
+                    offset: 0,
+                }),
+                None,
+            ) if !self.whole => Some(*number),
+            _ => None,
+        }
+    }
+
+    /// Resolve to an inclusive pair of 0-based line indices, falling back to `default`
+    pub fn resolve<S: TextStorage>(
+        &self,
+        buffer: &Buffer<S>,
+        current: usize,
+        default: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        if self.whole {
+            return Some((0, buffer.line_count().saturating_sub(1)));
+        }
+
+        let (start, end) = match (&self.start, &self.end) {
+            (None, None) => return Some(default),
+            (Some(start), None) => {
+                let start = start.resolve(buffer, current)?;
+                (start, start)
+            }
+            (start, Some(end)) => {
+                let start = match start {
+                    Some(start) => start.resolve(buffer, current)?,
+                    None => current,
+                };
+                (start, end.resolve(buffer, current)?)
+            }
+        };
+
+        Some((start.min(end), start.max(end)))
+    }
+}
+
+fn number(input: &str) -> IResult<&str, usize> {
+    nom::combinator::map_res(nom::character::complete::digit1, str::parse::<usize>)(input)
+}
+
+fn delimited_pattern(delim: char) -> impl FnMut(&str) -> IResult<&str, String> {
+    move |input| {
+        let (input, _) = nom::character::complete::char(delim)(input)?;
+        let mut pat = String::new();
+        let mut chars = input.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, c)) if c == delim => pat.push(c),
+                    Some((_, c)) => {
+                        pat.push('\\');
+                        pat.push(c);
+                    }
+                    None => pat.push('\\'),
+                },
+                c if c == delim => return Ok((&input[i + c.len_utf8()..], pat)),
+                c => pat.push(c),
+            }
+        }
+        Ok(("", pat))
+    }
+}
+
+fn address_base(input: &str) -> IResult<&str, AddressBase> {
+    nom::branch::alt((
+        nom::combinator::map(number, AddressBase::Line),
+        nom::combinator::value(AddressBase::Current, nom::character::complete::char('.')),
+        nom::combinator::value(AddressBase::Last, nom::character::complete::char('$')),
+        nom::combinator::map(delimited_pattern('/'), AddressBase::Forward),
+        nom::combinator::map(delimited_pattern('?'), AddressBase::Backward),
+    ))(input)
+}
+
+fn address_offset(input: &str) -> IResult<&str, isize> {
+    let (input, sign) = nom::character::complete::one_of("+-")(input)?;
+    let (input, count) = nom::combinator::opt(number)(input)?;
+    let count = isize::try_from(count.unwrap_or(1)).unwrap_or(isize::MAX);
+    Ok((input, if sign == '-' { -count } else { count }))
+}
+
+/// Parse a single address, where a bare offset is relative to the current line
+pub fn address(input: &str) -> IResult<&str, Address> {
+    let (input, base) = nom::combinator::opt(address_base)(input)?;
+    let (input, offsets) = nom::multi::many0(address_offset)(input)?;
+
+    if base.is_none() && offsets.is_empty() {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Digit,
+        )));
+    }
+
+    Ok((
+        input,
+        Address {
+            base: base.unwrap_or(AddressBase::Current),
+            offset: offsets.into_iter().fold(0, isize::saturating_add),
+        },
+    ))
+}
+
+/// Parse a `%`, `N`, or `N,M` line range
+pub fn range(input: &str) -> IResult<&str, Range> {
+    if let Some(input) = input.strip_prefix('%') {
+        return Ok((
+            input,
+            Range {
+                whole: true,
+                ..Default::default()
+            },
+        ));
+    }
+
+    let (input, start) = nom::combinator::opt(address)(input)?;
+    let (input, end) = nom::combinator::opt(nom::sequence::preceded(
+        nom::character::complete::char(','),
+        nom::combinator::opt(address),
+    ))(input)?;
+
+    let end = match end {
+        // `N,` without an end address means `N,.`
+        Some(None) => Some(Address {
+            base: AddressBase::Current,
+            offset: 0,
+        }),
+        Some(end) => end,
+        None => None,
+    };
+
+    Ok((
+        input,
+        Range {
+            start,
+            end,
+            whole: false,
+        },
+    ))
+}
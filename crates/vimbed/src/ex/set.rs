@@ -0,0 +1,78 @@
+use crate::{context::Context, option::SetScope};
+
+use super::ExCommand;
+
+/// Split `:set` arguments at unescaped whitespace, unescaping `\ ` and `\\`
+fn set_args(input: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut arg = String::new();
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) if c == ' ' || c == '\\' => arg.push(c),
+                Some(c) => {
+                    arg.push('\\');
+                    arg.push(c);
+                }
+                None => arg.push('\\'),
+            },
+            ' ' | '\t' => {
+                if !arg.is_empty() {
+                    args.push(std::mem::take(&mut arg));
+                }
+            }
+            c => arg.push(c),
+        }
+    }
+    if !arg.is_empty() {
+        args.push(arg);
+    }
+    args
+}
+
+/// `:se[t] {arg}...`, `:setl[ocal] {arg}...` and `:setg[lobal] {arg}...`
+///
+/// Without arguments, lists options that differ from their defaults. `all` lists every
+/// option and `all&` resets them.
+pub fn ex_set(ctx: &mut Context, command: &ExCommand, scope: SetScope) {
+    let args = set_args(command.args);
+    if args.is_empty() {
+        ctx.output(ctx.options.display(false));
+        return;
+    }
+
+    let mut shown = vec![];
+    for arg in args {
+        let result = match arg.as_str() {
+            "all" => Ok(Some(ctx.options.display(true))),
+            "all&" => {
+                let names = ctx.options.defs().map(|def| def.name).collect::<Vec<_>>();
+                names
+                    .into_iter()
+                    .try_for_each(|name| ctx.options.reset(name, scope))
+                    .map(|_| None)
+            }
+            arg => ctx.options.set_arg(arg, scope),
+        };
+
+        match result {
+            Ok(Some(text)) => shown.push(text),
+            Ok(None) => (),
+            Err(error) => {
+                ctx.error(error);
+                return;
+            }
+        }
+    }
+
+    if !shown.is_empty() {
+        ctx.output(
+            shown
+                .iter()
+                .map(|text| format!("  {}", text))
+                .collect::<Vec<_>>()
+                .join(""),
+        );
+    }
+}
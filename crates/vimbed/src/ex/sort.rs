@@ -0,0 +1,259 @@
+use std::cmp::Ordering;
+
+use regex::Regex;
+
+use crate::{context::Context, error::Error, pattern};
+
+use super::{split_delimited, ExCommand};
+
+/// Number format used to sort on the first number in each line
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SortNumber {
+    /// `n`
+    Decimal,
+    /// `f`
+    Float,
+    /// `x`
+    Hex,
+    /// `o`
+    Octal,
+    /// `b`
+    Binary,
+}
+
+impl SortNumber {
+    fn regex(&self) -> Regex {
+        Regex::new(match self {
+            SortNumber::Decimal => r"-?[0-9]+",
+            SortNumber::Float => r"[-+]?(?:[0-9]+\.?[0-9]*|\.[0-9]+)(?:[eE][-+]?[0-9]+)?",
+            SortNumber::Hex => r"-?(?:0[xX])?[0-9a-fA-F]+",
+            SortNumber::Octal => r"-?[0-7]+",
+            SortNumber::Binary => r"-?(?:0[bB])?[01]+",
+        })
+        .unwrap()
+    }
+
+    /// Parse the first number in `text`
+    fn parse(&self, regex: &Regex, text: &str) -> Option<f64> {
+        let m = regex.find(text)?.as_str();
+        let (negative, digits) = match m.strip_prefix('-') {
+            Some(digits) if *self != SortNumber::Float => (true, digits),
+            _ => (false, m),
+        };
+
+        let value = match self {
+            SortNumber::Decimal => digits.parse::<i128>().ok()? as f64,
+            SortNumber::Float => digits.parse::<f64>().ok()?,
+            SortNumber::Hex => {
+                let digits = digits
+                    .strip_prefix("0x")
+                    .or_else(|| digits.strip_prefix("0X"))
+                    .unwrap_or(digits);
+                i128::from_str_radix(digits, 16).ok()? as f64
+            }
+            SortNumber::Octal => i128::from_str_radix(digits, 8).ok()? as f64,
+            SortNumber::Binary => {
+                let digits = digits
+                    .strip_prefix("0b")
+                    .or_else(|| digits.strip_prefix("0B"))
+                    .unwrap_or(digits);
+                i128::from_str_radix(digits, 2).ok()? as f64
+            }
+        };
+
+        Some(if negative { -value } else { value })
+    }
+}
+
+/// Options following `:sort`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SortOptions {
+    pub reverse: bool,
+    pub ignore_case: bool,
+    pub unique: bool,
+    pub number: Option<SortNumber>,
+    pub pattern: Option<String>,
+    /// `r`: Sort on the pattern match instead of the text following it
+    pub on_match: bool,
+}
+
+impl SortOptions {
+    fn parse(args: &str, reverse: bool) -> Result<Self, Error> {
+        let mut options = SortOptions {
+            reverse,
+            ..Default::default()
+        };
+
+        let mut rest = args;
+        while let Some(c) = rest.chars().next() {
+            let number = match c {
+                ' ' | '\t' => None,
+                'i' => {
+                    options.ignore_case = true;
+                    None
+                }
+                'u' => {
+                    options.unique = true;
+                    None
+                }
+                'r' => {
+                    options.on_match = true;
+                    None
+                }
+                'n' => Some(SortNumber::Decimal),
+                'f' => Some(SortNumber::Float),
+                'x' => Some(SortNumber::Hex),
+                'o' => Some(SortNumber::Octal),
+                'b' => Some(SortNumber::Binary),
+                c if !c.is_alphanumeric() && !"\\\"|".contains(c) => {
+                    let (pattern, after) = split_delimited(&rest[c.len_utf8()..], c);
+                    options.pattern = Some(pattern);
+                    rest = after.unwrap_or_default();
+                    continue;
+                }
+                _ => return Err(Error::new(474, format!("Invalid argument: {}", rest))),
+            };
+
+            if let Some(number) = number {
+                if options.number.is_some() {
+                    return Err(Error::new(474, "Invalid argument"));
+                }
+                options.number = Some(number);
+            }
+
+            rest = &rest[c.len_utf8()..];
+        }
+
+        Ok(options)
+    }
+}
+
+/// Sort key extracted from a line
+#[derive(Debug, Clone, PartialEq)]
+enum SortKey {
+    Text(String),
+    Number(Option<f64>),
+}
+
+impl SortKey {
+    fn compare(&self, other: &SortKey) -> Ordering {
+        match (self, other) {
+            (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+            // Lines without a number sort before those with one
+            (SortKey::Number(a), SortKey::Number(b)) => match (a, b) {
+                (Some(a), Some(b)) => a.total_cmp(b),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            },
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+/// `:[range]sor[t][!] [b][f][i][n][o][u][x] [/{pattern}/] [r]`
+///
+/// Sorts the range as a single change. With a pattern, lines are sorted on the text
+/// following its match, or with `r` on the match itself; lines without a match sort as
+/// if empty.
+pub fn ex_sort(ctx: &mut Context, command: &ExCommand) {
+    let mut options = match SortOptions::parse(command.args, command.bang) {
+        Ok(options) => options,
+        Err(error) => {
+            ctx.error(error);
+            return;
+        }
+    };
+
+    let regex = match options.pattern.take() {
+        Some(pattern) => {
+            let pattern = if pattern.is_empty() {
+                match &ctx.last_pattern {
+                    Some(pattern) => pattern.clone(),
+                    None => {
+                        ctx.error(Error::new(35, "No previous regular expression"));
+                        return;
+                    }
+                }
+            } else {
+                pattern
+            };
+            ctx.last_pattern = Some(pattern.clone());
+
+            let ignore_case = ctx.options.bool("ignorecase").unwrap_or_default();
+            match pattern::compile(&pattern, ignore_case) {
+                Ok(regex) => Some(regex),
+                Err(_) => {
+                    ctx.error(Error::new(
+                        383,
+                        format!("Invalid search string: {}", pattern),
+                    ));
+                    return;
+                }
+            }
+        }
+        None => None,
+    };
+
+    let buffer = &mut ctx.buffer_edit;
+    let last = buffer.line_count().saturating_sub(1);
+    let Some((start, end)) = command.range.resolve(buffer, buffer.cursor.row, (0, last)) else {
+        ctx.error(Error::new(16, "Invalid range"));
+        return;
+    };
+
+    let number_regex = options.number.map(|number| number.regex());
+
+    let mut lines = buffer
+        .lines_from(start)
+        .take(end + 1 - start)
+        .map(|line| {
+            let text = match &regex {
+                Some(regex) => match regex.find(&line) {
+                    Some(m) if options.on_match => m.as_str(),
+                    Some(m) => &line[m.end()..],
+                    None => "",
+                },
+                None => &line,
+            };
+
+            let key = match (options.number, &number_regex) {
+                (Some(number), Some(regex)) => SortKey::Number(number.parse(regex, text)),
+                _ if options.ignore_case => SortKey::Text(text.to_lowercase()),
+                _ => SortKey::Text(text.to_owned()),
+            };
+
+            (key, line.into_owned())
+        })
+        .collect::<Vec<_>>();
+
+    lines.sort_by(|(a, _), (b, _)| a.compare(b));
+    if options.reverse {
+        lines.reverse();
+    }
+
+    let mut sorted: Vec<String> = vec![];
+    for (_, line) in lines {
+        let duplicate = options.unique
+            && sorted.last().is_some_and(|last| {
+                if options.ignore_case {
+                    last.to_lowercase() == line.to_lowercase()
+                } else {
+                    *last == line
+                }
+            });
+
+        if !duplicate {
+            sorted.push(line);
+        }
+    }
+
+    let removed = end + 1 - start - sorted.len();
+
+    let from = buffer.line_offset(start);
+    let to = buffer.line_offset(end) + buffer.line(end).unwrap_or_default().len();
+    buffer.splice(from..to, &sorted.join("\n"));
+    buffer.set_cursor_line(start);
+
+    if ctx.report(removed) {
+        ctx.info(format!("{} fewer lines", removed));
+    }
+}
@@ -0,0 +1,627 @@
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::{
+    buffer::Buffer, command::COMMAND_ESCAPE, context::Context, error::Error, pattern,
+    storage::TextStorage,
+};
+
+use super::{split_delimited, ExCommand};
+
+/// Flags accepted after a `:s` command
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SubstituteFlags {
+    /// `g`: Replace every match in the line instead of the first
+    pub global: bool,
+    /// `c`: Confirm each substitution
+    pub confirm: bool,
+    /// `i` / `I`: Override case sensitivity
+    pub ignore_case: Option<bool>,
+    /// `n`: Report the number of matches without substituting
+    pub count_only: bool,
+    /// `e`: Don't report an error when the pattern isn't found
+    pub no_error: bool,
+}
+
+impl SubstituteFlags {
+    /// Parse a flag string, starting from `previous` if it begins with `&`
+    ///
+    /// Returns the remaining input, which should hold an optional count.
+    pub fn parse(input: &str, previous: SubstituteFlags) -> (Self, &str) {
+        let (mut flags, input) = match input.strip_prefix('&') {
+            Some(input) => (previous, input),
+            None => (SubstituteFlags::default(), input),
+        };
+
+        let mut rest = input;
+        for c in input.chars() {
+            match c {
+                'g' => flags.global = !flags.global,
+                'c' => flags.confirm = !flags.confirm,
+                'i' => flags.ignore_case = Some(true),
+                'I' => flags.ignore_case = Some(false),
+                'n' => flags.count_only = true,
+                'e' => flags.no_error = true,
+                _ => break,
+            }
+            rest = &rest[1..];
+        }
+
+        (flags, rest)
+    }
+}
+
+/// Case conversion applied to replacement text
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaseModifier {
+    /// `\u`
+    UpperNext,
+    /// `\l`
+    LowerNext,
+    /// `\U`
+    Upper,
+    /// `\L`
+    Lower,
+    /// `\E` / `\e`
+    End,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplacementPart {
+    Literal(String),
+    /// `&` / `\0` for the whole match, `\1`..`\9` for a group
+    Group(usize),
+    Case(CaseModifier),
+}
+
+/// Parsed `:s` replacement string
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    pub parts: Vec<ReplacementPart>,
+}
+
+impl Replacement {
+    pub fn parse(input: &str) -> Self {
+        let mut parts = vec![];
+        let mut literal = String::new();
+
+        let push = |parts: &mut Vec<ReplacementPart>, literal: &mut String, part| {
+            if !literal.is_empty() {
+                parts.push(ReplacementPart::Literal(std::mem::take(literal)));
+            }
+            parts.push(part);
+        };
+
+        let mut chars = input.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '&' => push(&mut parts, &mut literal, ReplacementPart::Group(0)),
+                '\r' => literal.push('\n'),
+                '\\' => match chars.next() {
+                    Some(n @ '0'..='9') => push(
+                        &mut parts,
+                        &mut literal,
+                        ReplacementPart::Group(n as usize - '0' as usize),
+                    ),
+                    Some('u') => push(
+                        &mut parts,
+                        &mut literal,
+                        ReplacementPart::Case(CaseModifier::UpperNext),
+                    ),
+                    Some('l') => push(
+                        &mut parts,
+                        &mut literal,
+                        ReplacementPart::Case(CaseModifier::LowerNext),
+                    ),
+                    Some('U') => push(
+                        &mut parts,
+                        &mut literal,
+                        ReplacementPart::Case(CaseModifier::Upper),
+                    ),
+                    Some('L') => push(
+                        &mut parts,
+                        &mut literal,
+                        ReplacementPart::Case(CaseModifier::Lower),
+                    ),
+                    Some('E') | Some('e') => push(
+                        &mut parts,
+                        &mut literal,
+                        ReplacementPart::Case(CaseModifier::End),
+                    ),
+                    Some('r') => literal.push('\n'),
+                    Some('n') => literal.push('\0'),
+                    Some('t') => literal.push('\t'),
+                    Some(c) => literal.push(c),
+                    None => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            parts.push(ReplacementPart::Literal(literal));
+        }
+
+        Replacement { parts }
+    }
+
+    /// Expand the replacement for a set of regex captures
+    pub fn expand(&self, captures: &regex::Captures) -> String {
+        let mut out = String::new();
+        let mut next: Option<CaseModifier> = None;
+        let mut all: Option<CaseModifier> = None;
+
+        let push = |out: &mut String, next: &mut Option<CaseModifier>, all, text: &str| {
+            for c in text.chars() {
+                match next.take().or(all) {
+                    Some(CaseModifier::UpperNext) | Some(CaseModifier::Upper) => {
+                        out.extend(c.to_uppercase())
+                    }
+                    Some(CaseModifier::LowerNext) | Some(CaseModifier::Lower) => {
+                        out.extend(c.to_lowercase())
+                    }
+                    _ => out.push(c),
+                }
+            }
+        };
+
+        for part in &self.parts {
+            match part {
+                ReplacementPart::Literal(text) => push(&mut out, &mut next, all, text),
+                ReplacementPart::Group(group) => push(
+                    &mut out,
+                    &mut next,
+                    all,
+                    captures.get(*group).map(|m| m.as_str()).unwrap_or_default(),
+                ),
+                ReplacementPart::Case(modifier) => match modifier {
+                    CaseModifier::UpperNext | CaseModifier::LowerNext => next = Some(*modifier),
+                    CaseModifier::Upper | CaseModifier::Lower => all = Some(*modifier),
+                    CaseModifier::End => all = None,
+                },
+            }
+        }
+
+        out
+    }
+}
+
+/// Replace unescaped `~` in a replacement string with the previous replacement
+fn expand_tilde(input: &str, previous: &str) -> String {
+    let mut out = String::new();
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => out.push_str(previous),
+            '\\' => {
+                out.push('\\');
+                if let Some(c) = chars.next() {
+                    out.push(c);
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Parameters of the most recent `:s`, reused by `:&`, `:&&` and `g&`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Substitute {
+    pub pattern: String,
+    pub replacement: String,
+    pub flags: SubstituteFlags,
+}
+
+/// In-progress substitution over a range of lines
+///
+/// Drives both immediate substitution and the interactive confirm prompt,
+/// for which the context holds it until the user answers for every match.
+#[derive(Debug, Clone)]
+pub struct Substitution {
+    regex: Regex,
+    replacement: Replacement,
+    replacement_text: String,
+    flags: SubstituteFlags,
+
+    row: usize,
+    end_row: usize,
+    column: usize,
+    skip_empty_at: Option<(usize, usize)>,
+
+    current: Option<(usize, Range<usize>)>,
+
+    substitutions: usize,
+    lines: usize,
+    last_row: Option<usize>,
+}
+
+impl Substitution {
+    /// Prompt displayed while confirming a match
+    pub fn prompt(&self) -> String {
+        format!("replace with {} (y/n/a/q/l)?", self.replacement_text)
+    }
+
+    /// Line and byte column range of the match awaiting confirmation
+    pub fn current_match<S: TextStorage>(
+        &self,
+        buffer: &Buffer<S>,
+    ) -> Option<(usize, Range<usize>)> {
+        let (row, range) = self.current.clone()?;
+        buffer.line(row)?;
+        Some((row, range))
+    }
+
+    /// Find the next match at or after the current position
+    fn advance<S: TextStorage>(&mut self, buffer: &Buffer<S>) {
+        while self.row <= self.end_row {
+            let Some(line) = buffer.line(self.row) else {
+                break;
+            };
+
+            if self.column <= line.len() {
+                if let Some(m) = self.regex.find_at(&line, self.column) {
+                    if m.is_empty() && self.skip_empty_at == Some((self.row, m.start())) {
+                        // An empty match directly after the previous one is ignored
+                        self.column = next_char_boundary(&line, m.start());
+                        self.skip_empty_at = None;
+                        continue;
+                    }
+
+                    self.current = Some((self.row, m.range()));
+                    return;
+                }
+            }
+
+            self.row += 1;
+            self.column = 0;
+        }
+
+        self.current = None;
+    }
+
+    /// Move past the current match without replacing it
+    fn skip<S: TextStorage>(&mut self, buffer: &Buffer<S>) {
+        if let Some((row, range)) = self.current.take() {
+            self.next_column(buffer, row, range.end, range.is_empty());
+        }
+        self.advance(buffer);
+    }
+
+    /// Replace the current match and move past it
+    fn replace<S: TextStorage>(&mut self, buffer: &mut Buffer<S>) {
+        let Some((row, range)) = self.current.take() else {
+            return;
+        };
+
+        if self.last_row != Some(row) {
+            self.lines += 1;
+            self.last_row = Some(row);
+        }
+        self.substitutions += 1;
+
+        if self.flags.count_only {
+            self.next_column(buffer, row, range.end, range.is_empty());
+            self.advance(buffer);
+            return;
+        }
+
+        let line = buffer.line(row).unwrap_or_default();
+        let text = match self.regex.captures_at(&line, range.start) {
+            Some(captures) => self.replacement.expand(&captures),
+            None => String::new(),
+        };
+
+        let line_offset = buffer.line_offset(row);
+        buffer.splice(line_offset + range.start..line_offset + range.end, &text);
+
+        match text.rfind('\n') {
+            Some(i) => {
+                // Line breaks in the replacement move the rest of the line down
+                let breaks = text.matches('\n').count();
+                self.end_row += breaks;
+                self.last_row = Some(row + breaks);
+                let column = text.len() - i - 1;
+                self.next_column(buffer, row + breaks, column, range.is_empty());
+            }
+            None => {
+                let column = range.start + text.len();
+                self.next_column(buffer, row, column, range.is_empty());
+            }
+        }
+
+        self.advance(buffer);
+    }
+
+    /// Position the search after a match ending at `column` on `row`
+    fn next_column<S: TextStorage>(
+        &mut self,
+        buffer: &Buffer<S>,
+        row: usize,
+        column: usize,
+        empty: bool,
+    ) {
+        if !self.flags.global {
+            self.row = row + 1;
+            self.column = 0;
+            return;
+        }
+
+        self.row = row;
+        if empty {
+            // Step over a character so an empty match can't repeat in place
+            let line = buffer.line(row).unwrap_or_default();
+            self.column = next_char_boundary(&line, column);
+        } else {
+            self.column = column;
+            self.skip_empty_at = Some((row, column));
+        }
+    }
+
+    /// Set the cursor to the match awaiting confirmation
+    fn show<S: TextStorage>(&self, buffer: &mut Buffer<S>) {
+        if let Some((row, range)) = self.current_match(buffer) {
+            buffer.cursor.row = row;
+            buffer.set_column(range.start);
+        }
+    }
+}
+
+fn next_char_boundary(line: &str, index: usize) -> usize {
+    line[index..]
+        .chars()
+        .next()
+        .map(|c| index + c.len_utf8())
+        .unwrap_or(index + 1)
+}
+
+/// `:[range]s[ubstitute]/{pattern}/{string}/[flags] [count]`
+pub fn ex_substitute(ctx: &mut Context, command: &ExCommand) {
+    let args = command.args;
+
+    let delim = args.chars().next();
+    let previous = ctx.last_substitute.clone();
+
+    let (pattern, replacement, rest, previous_flags) = match delim {
+        Some(delim) if !(delim.is_alphanumeric() || "\\\"| &".contains(delim)) => {
+            let (pattern, rest) = split_delimited(&args[delim.len_utf8()..], delim);
+            let (replacement, rest) = match rest {
+                Some(rest) => split_delimited(rest, delim),
+                None => (String::new(), None),
+            };
+
+            let previous_replacement = previous
+                .as_ref()
+                .map(|p| p.replacement.as_str())
+                .unwrap_or_default();
+            let replacement = expand_tilde(&replacement, previous_replacement);
+
+            let pattern = if pattern.is_empty() {
+                match &ctx.last_pattern {
+                    Some(pattern) => pattern.clone(),
+                    None => {
+                        ctx.error(Error::new(35, "No previous regular expression"));
+                        return;
+                    }
+                }
+            } else {
+                pattern
+            };
+
+            let previous_flags = previous.map(|p| p.flags).unwrap_or_default();
+            (
+                pattern,
+                replacement,
+                rest.unwrap_or_default(),
+                previous_flags,
+            )
+        }
+        _ => {
+            // `:s [flags] [count]` repeats the last substitute
+            let Some(previous) = previous else {
+                ctx.error(Error::new(35, "No previous regular expression"));
+                return;
+            };
+            (previous.pattern, previous.replacement, args, previous.flags)
+        }
+    };
+
+    let (flags, rest) = SubstituteFlags::parse(rest, previous_flags);
+    substitute(ctx, command, pattern, replacement, flags, rest);
+}
+
+/// `:&[&][flags] [count]`, repeat the last substitute
+pub fn ex_substitute_repeat(ctx: &mut Context, command: &ExCommand) {
+    let Some(previous) = ctx.last_substitute.clone() else {
+        ctx.error(Error::new(35, "No previous regular expression"));
+        return;
+    };
+
+    let (flags, rest) = SubstituteFlags::parse(command.args, previous.flags);
+    substitute(
+        ctx,
+        command,
+        previous.pattern,
+        previous.replacement,
+        flags,
+        rest,
+    );
+}
+
+fn substitute(
+    ctx: &mut Context,
+    command: &ExCommand,
+    pattern: String,
+    replacement: String,
+    flags: SubstituteFlags,
+    rest: &str,
+) {
+    let rest = rest.trim();
+    let count = if rest.is_empty() {
+        None
+    } else {
+        match rest.parse::<usize>() {
+            Ok(count) if count > 0 => Some(count),
+            Ok(_) => {
+                ctx.error(Error::new(939, "Positive count required"));
+                return;
+            }
+            Err(_) => {
+                ctx.error(Error::new(488, format!("Trailing characters: {}", rest)));
+                return;
+            }
+        }
+    };
+
+    if !flags.count_only && !ctx.check_modifiable() {
+        return;
+    }
+
+    ctx.last_substitute = Some(Substitute {
+        pattern: pattern.clone(),
+        replacement: replacement.clone(),
+        flags,
+    });
+    ctx.last_pattern = Some(pattern.clone());
+
+    let buffer = &ctx.buffer_edit;
+    let current = buffer.cursor.row;
+    let Some((mut start, mut end)) = command.range.resolve(buffer, current, (current, current))
+    else {
+        ctx.error(Error::new(16, "Invalid range"));
+        return;
+    };
+
+    if let Some(count) = count {
+        start = end;
+        end = start
+            .saturating_add(count - 1)
+            .min(buffer.line_count().saturating_sub(1));
+    }
+
+    let ignore_case = flags
+        .ignore_case
+        .unwrap_or_else(|| ctx.ignore_case(&pattern));
+    let regex = match pattern::compile(&pattern, ignore_case) {
+        Ok(regex) => regex,
+        Err(_) => {
+            ctx.error(Error::new(
+                383,
+                format!("Invalid search string: {}", pattern),
+            ));
+            return;
+        }
+    };
+
+    let mut substitution = Substitution {
+        regex,
+        replacement: Replacement::parse(&replacement),
+        replacement_text: replacement,
+        flags,
+        row: start,
+        end_row: end,
+        column: 0,
+        skip_empty_at: None,
+        current: None,
+        substitutions: 0,
+        lines: 0,
+        last_row: None,
+    };
+
+    substitution.advance(&ctx.buffer_edit);
+
+    if substitution.current.is_none() {
+        if !flags.no_error {
+            ctx.error(Error::new(486, format!("Pattern not found: {}", pattern)));
+        }
+        return;
+    }
+
+    if flags.confirm && !flags.count_only {
+        if ctx.global_busy {
+            // The prompt can't suspend a running :global
+            ctx.info("Cannot confirm substitutions under :global");
+            return;
+        }
+
+        substitution.show(&mut ctx.buffer_edit);
+        ctx.substitute_confirm = Some(substitution);
+        return;
+    }
+
+    while substitution.current.is_some() {
+        substitution.replace(&mut ctx.buffer_edit);
+    }
+
+    finish(ctx, substitution);
+}
+
+/// Handle a key while a `:s///c` confirm prompt is active
+pub fn substitute_confirm_input(ctx: &mut Context, input: &str) {
+    let Some(mut substitution) = ctx.substitute_confirm.take() else {
+        return;
+    };
+
+    let buffer = &mut ctx.buffer_edit;
+    match input {
+        "y" => substitution.replace(buffer),
+        "l" => {
+            substitution.replace(buffer);
+            substitution.current = None;
+        }
+        "n" => substitution.skip(buffer),
+        "a" => {
+            while substitution.current.is_some() {
+                substitution.replace(buffer);
+            }
+        }
+        "q" | COMMAND_ESCAPE => substitution.current = None,
+        _ => (),
+    }
+
+    if substitution.current.is_some() {
+        substitution.show(buffer);
+        ctx.substitute_confirm = Some(substitution);
+    } else {
+        finish(ctx, substitution);
+    }
+}
+
+/// Place the cursor and report the result of a completed substitution
+fn finish(ctx: &mut Context, substitution: Substitution) {
+    let Substitution {
+        substitutions,
+        lines,
+        last_row,
+        flags,
+        ..
+    } = substitution;
+
+    if flags.count_only {
+        ctx.info(format!(
+            "{} match{} on {} line{}",
+            substitutions,
+            if substitutions == 1 { "" } else { "es" },
+            lines,
+            if lines == 1 { "" } else { "s" },
+        ));
+        return;
+    }
+
+    if let Some(row) = last_row {
+        ctx.buffer_edit.set_cursor_line(row);
+    }
+
+    if ctx.report(substitutions) {
+        ctx.info(format!(
+            "{} substitution{} on {} line{}",
+            substitutions,
+            if substitutions == 1 { "" } else { "s" },
+            lines,
+            if lines == 1 { "" } else { "s" },
+        ));
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{context::Context, error::Error};
+
+use super::ExCommand;
+
+/// Tab page number given as the argument or range of a command, as in `:tabclose 2`
+fn page_number(ctx: &mut Context, command: &ExCommand) -> Option<Option<usize>> {
+    match command.args.trim() {
+        "" => Some(command.range.number()),
+        "$" => Some(Some(ctx.tab_pages.len())),
+        arg => match arg.parse() {
+            Ok(number) => Some(Some(number)),
+            Err(_) => {
+                ctx.error(Error::new(474, "Invalid argument"));
+                None
+            }
+        },
+    }
+}
+
+/// `:tabc[lose] [N]`, closing the current tab page if no number is given
+pub fn ex_tabclose(ctx: &mut Context, command: &ExCommand) {
+    if let Some(number) = page_number(ctx, command) {
+        ctx.close_tab_page(number.unwrap_or(ctx.tab_pages.current()));
+    }
+}
+
+/// `:tabn[ext] [N]`, going to tab page N or the next one
+pub fn ex_tabnext(ctx: &mut Context, command: &ExCommand) {
+    let number = match page_number(ctx, command) {
+        Some(Some(number)) => number,
+        Some(None) => ctx.tab_pages.step(ctx.tab_pages.current(), 1, true),
+        None => return,
+    };
+    if number == 0 || number > ctx.tab_pages.len() {
+        ctx.error(Error::new(16, "Invalid range"));
+        return;
+    }
+    ctx.switch_tab_page(number);
+}
+
+/// `:tabp[revious] [N]` and `:tabN[ext] [N]`, going N tab pages back
+pub fn ex_tabprevious(ctx: &mut Context, command: &ExCommand) {
+    if let Some(count) = page_number(ctx, command) {
+        let count = count.unwrap_or(1);
+        let number = ctx.tab_pages.step(ctx.tab_pages.current(), count, false);
+        ctx.switch_tab_page(number);
+    }
+}
+
+/// `:tabm[ove] [N]`, `:tabm[ove] +N` and `:tabm[ove] -N`
+///
+/// The current tab page moves after tab page N, to the start for `0`, to the end if no
+/// number is given, or N places along for a relative number.
+pub fn ex_tabmove(ctx: &mut Context, command: &ExCommand) {
+    let arg = command.args.trim();
+    let current = ctx.tab_pages.current() - 1;
+    let last = ctx.tab_pages.len() - 1;
+
+    let index = match arg {
+        "" | "$" => Some(last),
+        _ if arg.starts_with(['+', '-']) => {
+            let offset = match &arg[1..] {
+                "" => Ok(1),
+                n => n.parse::<usize>(),
+            };
+            match (arg.starts_with('+'), offset) {
+                (true, Ok(n)) => current.checked_add(n).filter(|&index| index <= last),
+                (false, Ok(n)) => current.checked_sub(n),
+                (_, Err(_)) => None,
+            }
+        }
+        _ => match arg.parse::<usize>() {
+            // Tab pages after the current one move down a place once it is taken out
+            Ok(n) if n <= current => Some(n),
+            Ok(n) => Some((n - 1).min(last)),
+            Err(_) => None,
+        },
+    };
+
+    match index {
+        Some(index) => ctx.move_tab_page(index),
+        None => ctx.error(Error::new(474, "Invalid argument")),
+    }
+}
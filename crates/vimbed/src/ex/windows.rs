@@ -0,0 +1,78 @@
+use crate::{
+    context::Context,
+    error::Error,
+    window::{Neighbour, SplitDirection},
+};
+
+use super::ExCommand;
+
+/// Keys that may follow `Ctrl-W` or `:wincmd`
+pub const WINCMD_KEYS: &str = "svwWhjklco=+-";
+
+/// Run a `Ctrl-W` command, as typed in normal mode or given to `:wincmd`
+///
+/// A count selects the window for `w` and `W`, and the number of lines for `+` and `-`.
+pub fn wincmd(ctx: &mut Context, key: char, count: Option<usize>) {
+    let n = count.unwrap_or(1);
+    match key {
+        's' => ctx.split_window(SplitDirection::Horizontal),
+        'v' => ctx.split_window(SplitDirection::Vertical),
+        'w' | 'W' => {
+            let current = ctx.windows.current();
+            let id = match count {
+                Some(count) => {
+                    let ids = ctx.windows.ids();
+                    ids[count.clamp(1, ids.len()) - 1]
+                }
+                None => ctx.windows.step(current, 1, key == 'w'),
+            };
+            ctx.switch_window(id);
+        }
+        'h' => ctx.neighbour_window(Neighbour::Left, n),
+        'j' => ctx.neighbour_window(Neighbour::Down, n),
+        'k' => ctx.neighbour_window(Neighbour::Up, n),
+        'l' => ctx.neighbour_window(Neighbour::Right, n),
+        'c' => ctx.close_window(ctx.windows.current()),
+        'o' => ctx.only_window(),
+        '=' => ctx.windows.equalize(),
+        '+' => ctx.windows.grow(isize::try_from(n).unwrap_or(isize::MAX)),
+        '-' => ctx.windows.grow(-isize::try_from(n).unwrap_or(isize::MAX)),
+        _ => ctx.error(Error::new(474, "Invalid argument")),
+    }
+}
+
+/// `:[N]winc[md] {arg}`
+pub fn ex_wincmd(ctx: &mut Context, command: &ExCommand) {
+    let mut chars = command.args.trim().chars();
+    match (chars.next(), chars.next()) {
+        (Some(key), None) => wincmd(ctx, key, command.range.number()),
+        (None, _) => ctx.error(Error::new(471, "Argument required")),
+        _ => ctx.error(Error::new(474, "Invalid argument")),
+    }
+}
+
+/// `:q[uit][!]` with more than one window or tab page, which closes the current window
+///
+/// Closing the last window showing a modified buffer is refused unless forced with `!`
+/// or allowed by 'hidden'.
+pub fn ex_quit_window(ctx: &mut Context, command: &ExCommand) {
+    let current = ctx.windows.current();
+    let buffer = ctx.buffers.current();
+    let other_pages = (1..=ctx.tab_pages.len()).filter_map(|number| ctx.tab_pages.get(number));
+    let shown_elsewhere = ctx
+        .windows
+        .iter()
+        .any(|window| window.id != current && window.buffer == buffer)
+        || other_pages
+            .flat_map(|windows| windows.iter())
+            .any(|window| window.buffer == buffer);
+    let hidden = ctx.options.bool("hidden") == Some(true);
+    if !command.bang && !shown_elsewhere && !hidden && ctx.buffer_edit.modified() {
+        ctx.error(Error::new(
+            37,
+            "No write since last change (add ! to override)",
+        ));
+        return;
+    }
+    ctx.close_window(current);
+}
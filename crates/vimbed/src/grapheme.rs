@@ -0,0 +1,65 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Byte offset of the cluster boundary following `offset`, or the line length
+pub fn next_boundary(line: &str, offset: usize) -> usize {
+    let offset = floor_boundary(line, offset);
+    line[offset..]
+        .graphemes(true)
+        .next()
+        .map(|g| offset + g.len())
+        .unwrap_or(line.len())
+}
+
+/// Byte offset of the cluster boundary preceding `offset`, or 0
+pub fn prev_boundary(line: &str, offset: usize) -> usize {
+    let offset = floor_boundary(line, offset);
+    line[..offset]
+        .grapheme_indices(true)
+        .next_back()
+        .map(|(i, _)| i)
+        .unwrap_or_default()
+}
+
+/// Byte offset of the start of the cluster containing `offset`
+pub fn floor_boundary(line: &str, offset: usize) -> usize {
+    if offset >= line.len() {
+        return line.len();
+    }
+
+    line.grapheme_indices(true)
+        .take_while(|(i, _)| *i <= offset)
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or_default()
+}
+
+/// Byte offset of the start of the last cluster in `line`
+pub fn last_boundary(line: &str) -> usize {
+    prev_boundary(line, line.len())
+}
+
+/// Number of cluster boundaries between byte offsets `from` and `to`
+pub fn count(line: &str, from: usize, to: usize) -> usize {
+    line[from..to].graphemes(true).count()
+}
+
+/// Terminal cell width of a grapheme cluster, ignoring tabs
+///
+/// Wide and fullwidth characters take two cells, combining marks and other
+/// zero-width characters take none.
+pub fn width(grapheme: &str) -> usize {
+    grapheme.width()
+}
+
+/// Longest prefix of `text` fitting within `columns` terminal cells, ignoring tabs
+pub fn truncate(text: &str, columns: usize) -> &str {
+    let mut used = 0;
+    for (i, g) in text.grapheme_indices(true) {
+        used += width(g);
+        if used > columns {
+            return &text[..i];
+        }
+    }
+    text
+}
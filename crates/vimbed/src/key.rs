@@ -0,0 +1,340 @@
+use std::{fmt::Display, str::FromStr};
+
+use nom::IResult;
+
+use crate::error::Error;
+
+/// A key without modifiers
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KeyCode {
+    Char(char),
+    Escape,
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Insert,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    /// Function key `<F1>` to `<F37>`
+    F(u8),
+    /// Keypad `<kEnter>`
+    KeypadEnter,
+    /// Keypad digit or operator, such as `<k0>` or `<kPlus>`
+    Keypad(char),
+}
+
+/// Modifier keys held while pressing a key
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        shift: false,
+        ctrl: false,
+        alt: false,
+        meta: false,
+    };
+
+    pub const SHIFT: Modifiers = Modifiers {
+        shift: true,
+        ..Modifiers::NONE
+    };
+
+    pub const CTRL: Modifiers = Modifiers {
+        ctrl: true,
+        ..Modifiers::NONE
+    };
+
+    pub const ALT: Modifiers = Modifiers {
+        alt: true,
+        ..Modifiers::NONE
+    };
+
+    pub const META: Modifiers = Modifiers {
+        meta: true,
+        ..Modifiers::NONE
+    };
+
+    pub fn is_empty(&self) -> bool {
+        *self == Modifiers::NONE
+    }
+
+    /// Set the modifier named by a notation prefix letter, returning false if unknown
+    fn set(&mut self, name: char) -> bool {
+        match name.to_ascii_uppercase() {
+            'S' => self.shift = true,
+            'C' => self.ctrl = true,
+            'A' => self.alt = true,
+            'M' => self.meta = true,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl std::ops::BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Modifiers {
+            shift: self.shift || rhs.shift,
+            ctrl: self.ctrl || rhs.ctrl,
+            alt: self.alt || rhs.alt,
+            meta: self.meta || rhs.meta,
+        }
+    }
+}
+
+/// A single typed key, as written in Vim's `<C-w>` notation
+///
+/// Keys are normalized on construction so equal keystrokes compare equal:
+/// shift is folded into the case of letters, and letters held with ctrl are
+/// lowercase, so `<S-a>` is `A` and `<C-W>` is `<C-w>`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+/// Named keys, with the name printed for each listed first
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("Esc", KeyCode::Escape),
+    ("CR", KeyCode::Enter),
+    ("Return", KeyCode::Enter),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("BS", KeyCode::Backspace),
+    ("BackSpace", KeyCode::Backspace),
+    ("Del", KeyCode::Delete),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("kEnter", KeyCode::KeypadEnter),
+    ("kPlus", KeyCode::Keypad('+')),
+    ("kMinus", KeyCode::Keypad('-')),
+    ("kMultiply", KeyCode::Keypad('*')),
+    ("kDivide", KeyCode::Keypad('/')),
+    ("kPoint", KeyCode::Keypad('.')),
+    ("kComma", KeyCode::Keypad(',')),
+    ("kEqual", KeyCode::Keypad('=')),
+    ("Space", KeyCode::Char(' ')),
+    ("lt", KeyCode::Char('<')),
+    ("Bslash", KeyCode::Char('\\')),
+    ("Bar", KeyCode::Char('|')),
+    ("NL", KeyCode::Char('\n')),
+];
+
+impl KeyCode {
+    /// Look up a key name from inside `<>`, ignoring case
+    fn from_name(name: &str) -> Option<KeyCode> {
+        if let Some((_, code)) = KEY_NAMES
+            .iter()
+            .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        {
+            return Some(*code);
+        }
+
+        let mut chars = name.chars();
+        match (chars.next()?, chars.as_str()) {
+            (c, "") => Some(KeyCode::Char(c)),
+            ('F' | 'f', n) => match n.parse() {
+                Ok(n @ 1..=37) => Some(KeyCode::F(n)),
+                _ => None,
+            },
+            ('k' | 'K', n) if n.len() == 1 && n.as_bytes()[0].is_ascii_digit() => {
+                Some(KeyCode::Keypad(n.as_bytes()[0] as char))
+            }
+            _ => None,
+        }
+    }
+
+    /// Name printed inside `<>`, if the key needs one
+    fn name(&self) -> Option<String> {
+        match self {
+            KeyCode::Char(c) => KEY_NAMES
+                .iter()
+                .find(|(_, code)| *code == KeyCode::Char(*c))
+                .map(|(name, _)| name.to_string()),
+            KeyCode::F(n) => Some(format!("F{}", n)),
+            KeyCode::Keypad(c) if c.is_ascii_digit() => Some(format!("k{}", c)),
+            code => KEY_NAMES
+                .iter()
+                .find(|(_, named)| named == code)
+                .map(|(name, _)| name.to_string()),
+        }
+    }
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: Modifiers) -> Self {
+        let mut key = Key { code, modifiers };
+        if let KeyCode::Char(c) = key.code {
+            if key.modifiers.ctrl && c.is_ascii_alphabetic() {
+                key.code = KeyCode::Char(c.to_ascii_lowercase());
+            } else if key.modifiers.shift && c.is_alphabetic() {
+                key.code = KeyCode::Char(c.to_uppercase().next().unwrap_or(c));
+                key.modifiers.shift = false;
+            } else if key.modifiers.shift && !c.is_whitespace() {
+                // The shifted symbol itself is reported, as with `!` for `<S-1>`
+                key.modifiers.shift = false;
+            }
+        }
+        // Some letters, such as `ı`, are an ASCII letter once shifted
+        if let (KeyCode::Char(c), true) = (key.code, key.modifiers.ctrl) {
+            key.code = KeyCode::Char(c.to_ascii_lowercase());
+        }
+        key
+    }
+
+    pub fn char(c: char) -> Self {
+        Key::new(KeyCode::Char(c), Modifiers::NONE)
+    }
+
+    pub fn ctrl(c: char) -> Self {
+        Key::new(KeyCode::Char(c), Modifiers::CTRL)
+    }
+
+    /// The character typed by this key, if it has no modifiers
+    pub fn as_char(&self) -> Option<char> {
+        match (self.code, self.modifiers.is_empty()) {
+            (KeyCode::Char(c), true) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+impl From<char> for Key {
+    fn from(c: char) -> Self {
+        Key::char(c)
+    }
+}
+
+impl From<KeyCode> for Key {
+    fn from(code: KeyCode) -> Self {
+        Key::new(code, Modifiers::NONE)
+    }
+}
+
+impl Display for Key {
+    /// Writes the key in notation that parses back to the same key
+    ///
+    /// Unmodified characters other than `<` are written as themselves.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(c) = self.as_char() {
+            if c != '<' {
+                return write!(f, "{}", c);
+            }
+        }
+
+        f.write_str("<")?;
+        for (held, prefix) in [
+            (self.modifiers.shift, "S-"),
+            (self.modifiers.ctrl, "C-"),
+            (self.modifiers.alt, "A-"),
+            (self.modifiers.meta, "M-"),
+        ] {
+            if held {
+                f.write_str(prefix)?;
+            }
+        }
+        match (self.code.name(), self.code) {
+            (Some(name), _) => f.write_str(&name)?,
+            (None, KeyCode::Char(c)) => write!(f, "{}", c)?,
+            (None, code) => write!(f, "{:?}", code)?,
+        }
+        f.write_str(">")
+    }
+}
+
+impl FromStr for Key {
+    type Err = Error;
+
+    /// Parse exactly one key, such as `x`, `<lt>` or `<C-S-Tab>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match key(s) {
+            Ok(("", key)) => Ok(key),
+            _ => Err(Error::new(475, format!("Invalid key: {}", s))),
+        }
+    }
+}
+
+/// Parses `<...>` key notation, such as `<Esc>`, `<C-w>` or `<M-x>`
+pub fn key_notation(input: &str) -> IResult<&str, Key> {
+    let (rest, _) = nom::bytes::complete::tag("<")(input)?;
+
+    // Modifier prefixes, each a letter and a dash, with the key name last
+    let mut modifiers = Modifiers::NONE;
+    let mut rest = rest;
+    loop {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(m), Some('-'), Some(c)) if c != '>' || chars.next() == Some('>') => {
+                if !modifiers.set(m) {
+                    break;
+                }
+                rest = &rest[2..];
+            }
+            _ => break,
+        }
+    }
+
+    // A single character name may itself be `>` or `-`
+    let len = match rest.chars().next() {
+        Some(c) if rest[c.len_utf8()..].starts_with('>') => c.len_utf8(),
+        _ => rest.find(['>', '<']).unwrap_or(rest.len()),
+    };
+    let (name, rest) = rest.split_at(len);
+    let (rest, _) = nom::bytes::complete::tag(">")(rest)?;
+
+    match KeyCode::from_name(name) {
+        Some(code) => Ok((rest, Key::new(code, modifiers))),
+        None => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Tag,
+        ))),
+    }
+}
+
+/// Parses one key, in notation or as a single character
+///
+/// A `<` that doesn't begin valid notation is the `<` key itself.
+pub fn key(input: &str) -> IResult<&str, Key> {
+    nom::branch::alt((
+        key_notation,
+        nom::combinator::map(nom::character::complete::anychar, Key::char),
+    ))(input)
+}
+
+/// Split text written in key notation into keys
+pub fn keys(input: &str) -> impl Iterator<Item = Key> + '_ {
+    let mut input = input;
+    std::iter::from_fn(move || {
+        let (rest, key) = key(input).ok()?;
+        input = rest;
+        Some(key)
+    })
+}
+
+/// Write a sequence of keys in notation
+pub fn notation(keys: impl IntoIterator<Item = Key>) -> String {
+    keys.into_iter().map(|key| key.to_string()).collect()
+}
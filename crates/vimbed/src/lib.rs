@@ -1,9 +1,30 @@
 pub mod buffer;
+pub mod buffer_list;
 pub mod char_len;
 pub mod command;
 pub mod context;
+pub mod error;
+pub mod event;
+pub mod ex;
+pub mod grapheme;
+pub mod key;
+pub mod line_index;
+pub mod map;
+pub mod mark;
+pub mod message;
 pub mod mode;
 pub mod motion;
 pub mod operator;
+pub mod option;
+pub mod pattern;
+pub mod register;
+#[cfg(feature = "serde")]
+pub mod session;
+pub mod storage;
+pub mod tab;
+pub mod tab_page;
+pub mod undo;
+pub mod window;
 
 pub use nom;
+pub use ropey;
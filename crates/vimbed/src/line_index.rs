@@ -0,0 +1,193 @@
+use std::{cmp::Ordering, ops::Range};
+
+use crate::storage::TextStorage;
+
+/// Fewest lines in a chunk of the index, so small texts are held in one
+const CHUNK_MIN: usize = 64;
+
+/// Byte offsets at which the lines of a buffer start, updated on each edit
+///
+/// Looking up a line or the line containing an offset is then independent of the
+/// size of the text, whatever storage holds it. The starts are held in chunks of about
+/// the square root of the line count, each relative to the start of its first line, so
+/// an edit only rewrites one chunk and the positions of those after it.
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    /// Chunks of consecutive lines, the first starting at line and offset zero
+    chunks: Vec<Chunk>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Chunk {
+    /// First line of the chunk
+    row: usize,
+    /// Byte offset at which the first line starts
+    offset: usize,
+    /// Start of each line relative to `offset`, the first always being zero
+    starts: Vec<usize>,
+}
+
+impl Default for LineIndex {
+    fn default() -> Self {
+        LineIndex {
+            chunks: vec![Chunk {
+                row: 0,
+                offset: 0,
+                starts: vec![0],
+            }],
+            len: 0,
+        }
+    }
+}
+
+impl LineIndex {
+    /// Index the lines of `text`
+    pub fn new<S: TextStorage + ?Sized>(text: &S) -> Self {
+        let mut starts = vec![0];
+        let mut offset = 0;
+        for line in text.lines_from(0) {
+            offset += line.len() + 1;
+            starts.push(offset);
+        }
+        starts.pop();
+
+        LineIndex {
+            chunks: chunks(0, &starts, chunk_len(starts.len())),
+            len: text.len(),
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        let last = self.chunks.last().unwrap();
+        last.row + last.starts.len()
+    }
+
+    /// Byte offset at which a line starts, or the text length past the last line
+    pub fn line_offset(&self, row: usize) -> usize {
+        let chunk = &self.chunks[self.chunks.partition_point(|chunk| chunk.row <= row) - 1];
+        match chunk.starts.get(row - chunk.row) {
+            Some(start) => chunk.offset + start,
+            None => self.len,
+        }
+    }
+
+    /// Byte offset at which a line ends, before its newline
+    pub fn line_end(&self, row: usize) -> usize {
+        if row + 1 < self.line_count() {
+            self.line_offset(row + 1) - 1
+        } else {
+            self.len
+        }
+    }
+
+    /// Line containing a byte offset
+    pub fn offset_line(&self, offset: usize) -> usize {
+        let chunk = &self.chunks[self.chunk_at(offset)];
+        let relative = offset - chunk.offset;
+        chunk.row + chunk.starts.partition_point(|&start| start <= relative) - 1
+    }
+
+    /// Update the index for `range` of the text being replaced with `text`
+    pub fn splice(&mut self, range: Range<usize>, text: &str) {
+        // Bring the lines the range touches into the chunk it starts in
+        let index = self.chunk_at(range.start);
+        let end = self.chunk_at(range.end);
+        if end > index {
+            let merged: Vec<_> = self.chunks.drain(index + 1..=end).collect();
+            let chunk = &mut self.chunks[index];
+            for other in merged {
+                let base = other.offset - chunk.offset;
+                chunk
+                    .starts
+                    .extend(other.starts.iter().map(|start| start + base));
+            }
+        }
+
+        // Lines starting after a newline in the range go, and those in the text arrive
+        let chunk = &mut self.chunks[index];
+        let range = range.start - chunk.offset..range.end - chunk.offset;
+        let first = chunk.starts.partition_point(|&start| start <= range.start);
+        let last = chunk.starts.partition_point(|&start| start <= range.end);
+        let inserted = text.match_indices('\n').map(|(i, _)| range.start + i + 1);
+        let count = text.matches('\n').count();
+        chunk.starts.splice(first..last, inserted);
+
+        let delta = text.len() as isize - range.len() as isize;
+        for start in &mut chunk.starts[first + count..] {
+            *start = start.wrapping_add_signed(delta);
+        }
+        let rows = count as isize - (last - first) as isize;
+        for chunk in &mut self.chunks[index + 1..] {
+            chunk.row = chunk.row.wrapping_add_signed(rows);
+            chunk.offset = chunk.offset.wrapping_add_signed(delta);
+        }
+        self.len = self.len.wrapping_add_signed(delta);
+
+        let len = chunk_len(self.line_count());
+        if self.chunks[index].starts.len() > 2 * len {
+            let chunk = self.chunks.remove(index);
+            let starts: Vec<_> = chunk
+                .starts
+                .iter()
+                .map(|start| chunk.offset + start)
+                .collect();
+            let split = chunks(chunk.row, &starts, len);
+            self.chunks.splice(index..index, split);
+        }
+    }
+
+    /// Index of the chunk holding the line containing a byte offset
+    fn chunk_at(&self, offset: usize) -> usize {
+        self.chunks.partition_point(|chunk| chunk.offset <= offset) - 1
+    }
+
+    /// Start of each line, in order
+    fn starts(&self) -> impl Iterator<Item = usize> + '_ {
+        self.chunks
+            .iter()
+            .flat_map(|chunk| chunk.starts.iter().map(|start| chunk.offset + start))
+    }
+}
+
+/// Lines in each chunk of an index of `lines` lines
+fn chunk_len(lines: usize) -> usize {
+    lines.isqrt().max(CHUNK_MIN)
+}
+
+/// Chunks of `len` lines from `starts`, the first being line `row`
+fn chunks(row: usize, starts: &[usize], len: usize) -> Vec<Chunk> {
+    starts
+        .chunks(len)
+        .enumerate()
+        .map(|(i, starts)| Chunk {
+            row: row + i * len,
+            offset: starts[0],
+            starts: starts.iter().map(|start| start - starts[0]).collect(),
+        })
+        .collect()
+}
+
+// Indexes of the same text compare equal however their lines are chunked
+
+impl PartialEq for LineIndex {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.starts().eq(other.starts())
+    }
+}
+
+impl Eq for LineIndex {}
+
+impl PartialOrd for LineIndex {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LineIndex {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.starts()
+            .cmp(other.starts())
+            .then(self.len.cmp(&other.len))
+    }
+}
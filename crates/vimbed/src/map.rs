@@ -0,0 +1,244 @@
+use std::{collections::BTreeMap, fmt::Debug, ops::Bound, rc::Rc};
+
+use crate::{
+    context::Context,
+    key::{self, Key},
+};
+
+/// Mode in which a mapping applies
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MapMode {
+    Normal,
+    Visual,
+    Select,
+    OperatorPending,
+    Insert,
+    CommandLine,
+}
+
+impl MapMode {
+    /// Modes covered by a `:map` command prefix, such as `n` for `:nmap`
+    ///
+    /// No prefix covers normal, visual, select and operator-pending modes, or insert
+    /// and command-line modes with `!`.
+    pub fn from_prefix(prefix: &str, bang: bool) -> Option<&'static [MapMode]> {
+        use MapMode::*;
+
+        Some(match (prefix, bang) {
+            ("", false) => &[Normal, Visual, Select, OperatorPending],
+            ("", true) => &[Insert, CommandLine],
+            ("n", false) => &[Normal],
+            ("v", false) => &[Visual, Select],
+            ("x", false) => &[Visual],
+            ("s", false) => &[Select],
+            ("o", false) => &[OperatorPending],
+            ("i", false) => &[Insert],
+            ("c", false) => &[CommandLine],
+            _ => return None,
+        })
+    }
+
+    /// Characters shown when listing a mapping defined the same way in each of `modes`
+    ///
+    /// Modes covered together by a `:map` prefix are shown as that prefix, with a blank
+    /// for normal, visual, select and operator-pending modes.
+    pub fn letters(modes: &[MapMode]) -> String {
+        use MapMode::*;
+
+        let has = |mode| modes.contains(&mode);
+        if has(Insert) && has(CommandLine) {
+            return "!".into();
+        } else if has(Insert) {
+            return "i".into();
+        } else if has(CommandLine) {
+            return "c".into();
+        } else if [Normal, Visual, Select, OperatorPending]
+            .into_iter()
+            .all(has)
+        {
+            return " ".into();
+        }
+
+        let mut letters = String::new();
+        if has(Normal) {
+            letters.push('n');
+        }
+        if has(OperatorPending) {
+            letters.push('o');
+        }
+        match (has(Visual), has(Select)) {
+            (true, true) => letters.push('v'),
+            (true, false) => letters.push('x'),
+            (false, true) => letters.push('s'),
+            (false, false) => (),
+        }
+        letters
+    }
+}
+
+/// Closure invoked when a mapping's keys are typed
+pub type MapCallback = Rc<dyn Fn(&mut Context<'_>)>;
+
+/// What a mapping's keys are replaced with
+#[derive(Clone)]
+pub enum MapTarget {
+    Keys(Vec<Key>),
+    Callback(MapCallback),
+}
+
+/// Callbacks are equal only if they are the same closure
+impl PartialEq for MapTarget {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (MapTarget::Keys(a), MapTarget::Keys(b)) => a == b,
+            (MapTarget::Callback(a), MapTarget::Callback(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Debug for MapTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapTarget::Keys(keys) => f.debug_tuple("Keys").field(keys).finish(),
+            MapTarget::Callback(_) => f.write_str("Callback"),
+        }
+    }
+}
+
+/// Right-hand side of a mapping
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mapping {
+    pub rhs: MapTarget,
+    /// Whether the replacement keys are used as-is rather than mapped again
+    pub noremap: bool,
+    /// Whether non-error messages produced by the replacement keys are suppressed
+    pub silent: bool,
+}
+
+impl Mapping {
+    pub fn new(rhs: MapTarget) -> Self {
+        Mapping {
+            rhs,
+            noremap: false,
+            silent: false,
+        }
+    }
+
+    pub fn keys(rhs: Vec<Key>) -> Self {
+        Self::new(MapTarget::Keys(rhs))
+    }
+
+    pub fn callback<F>(f: F) -> Self
+    where
+        F: Fn(&mut Context<'_>) + 'static,
+    {
+        Self::new(MapTarget::Callback(Rc::new(f)))
+    }
+
+    pub fn with_noremap(mut self, noremap: bool) -> Self {
+        self.noremap = noremap;
+        self
+    }
+
+    pub fn with_silent(mut self, silent: bool) -> Self {
+        self.silent = silent;
+        self
+    }
+
+    /// The replacement in key notation, as shown by `:map`
+    pub fn display(&self) -> String {
+        let flag = if self.noremap { '*' } else { ' ' };
+        match &self.rhs {
+            MapTarget::Keys(keys) if keys.is_empty() => format!("{}<Nop>", flag),
+            MapTarget::Keys(keys) => format!("{}{}", flag, key::notation(keys.iter().copied())),
+            MapTarget::Callback(_) => format!("{}<Callback>", flag),
+        }
+    }
+}
+
+/// Result of matching typed keys against the mappings of a mode
+#[derive(Debug, Clone)]
+pub enum MapMatch {
+    /// No mapping starts with the keys
+    None,
+    /// The keys begin a longer mapping, so more keys are needed to decide
+    Pending,
+    /// The first `len` keys are a mapping's left-hand side
+    Found(usize, Mapping),
+}
+
+/// Key mappings for each mode
+#[derive(Debug, Default, Clone)]
+pub struct Mappings {
+    maps: BTreeMap<MapMode, BTreeMap<Vec<Key>, Mapping>>,
+}
+
+impl Mappings {
+    pub fn insert(&mut self, modes: &[MapMode], lhs: Vec<Key>, mapping: Mapping) {
+        for mode in modes {
+            self.maps
+                .entry(*mode)
+                .or_default()
+                .insert(lhs.clone(), mapping.clone());
+        }
+    }
+
+    /// Remove the mapping of `lhs` in each of `modes`, returning false if there was none
+    pub fn remove(&mut self, modes: &[MapMode], lhs: &[Key]) -> bool {
+        let mut removed = false;
+        for mode in modes {
+            if let Some(map) = self.maps.get_mut(mode) {
+                removed |= map.remove(lhs).is_some();
+            }
+        }
+        removed
+    }
+
+    pub fn get(&self, mode: MapMode, lhs: &[Key]) -> Option<&Mapping> {
+        self.maps.get(&mode)?.get(lhs)
+    }
+
+    pub fn is_empty(&self, mode: MapMode) -> bool {
+        self.maps.get(&mode).is_none_or(BTreeMap::is_empty)
+    }
+
+    /// Mappings in `mode` whose left-hand side starts with `prefix`, in order
+    pub fn iter(
+        &self,
+        mode: MapMode,
+        prefix: &[Key],
+    ) -> impl Iterator<Item = (&Vec<Key>, &Mapping)> + '_ {
+        let prefix = prefix.to_vec();
+        self.maps
+            .get(&mode)
+            .into_iter()
+            .flat_map(|map| map.iter())
+            .filter(move |(lhs, _)| lhs.starts_with(&prefix))
+    }
+
+    /// Match the start of `keys` against the mappings of `mode`
+    ///
+    /// While a longer mapping could still match, the result is pending unless `complete`
+    /// says no more keys are coming, in which case the longest full match is used.
+    pub fn find(&self, mode: MapMode, keys: &[Key], complete: bool) -> MapMatch {
+        let Some(map) = self.maps.get(&mode) else {
+            return MapMatch::None;
+        };
+
+        let longer = map
+            .range::<[Key], _>((Bound::Excluded(keys), Bound::Unbounded))
+            .next()
+            .is_some_and(|(lhs, _)| lhs.starts_with(keys));
+        if longer && !complete {
+            return MapMatch::Pending;
+        }
+
+        (1..=keys.len())
+            .rev()
+            .find_map(|len| map.get(&keys[..len]).map(|mapping| (len, mapping)))
+            .map_or(MapMatch::None, |(len, mapping)| {
+                MapMatch::Found(len, mapping.clone())
+            })
+    }
+}
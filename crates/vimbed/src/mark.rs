@@ -0,0 +1,62 @@
+use std::collections::BTreeSet;
+
+/// Line-level summary of a buffer edit
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct LineEdit {
+    /// First line whose position may have changed
+    pub start: usize,
+    /// Number of lines removed from `start` onward
+    pub removed: usize,
+    /// Number of lines inserted in their place
+    pub inserted: usize,
+}
+
+impl LineEdit {
+    /// Map a line through the edit, or `None` if it was removed
+    pub fn transform(&self, line: usize) -> Option<usize> {
+        if line < self.start {
+            Some(line)
+        } else if line < self.start + self.removed {
+            None
+        } else {
+            Some(line - self.removed + self.inserted)
+        }
+    }
+}
+
+/// Set of lines that follow their text as the buffer is edited, as used by `:global`
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineMarks(BTreeSet<usize>);
+
+impl LineMarks {
+    pub fn insert(&mut self, line: usize) {
+        self.0.insert(line);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Remove and return the first marked line
+    pub fn pop_first(&mut self) -> Option<usize> {
+        self.0.pop_first()
+    }
+
+    /// Move marks to follow an edit, dropping those on removed lines
+    pub fn adjust(&mut self, edit: &LineEdit) {
+        if edit.removed == 0 && edit.inserted == 0 {
+            return;
+        }
+
+        self.0 = self
+            .0
+            .iter()
+            .flat_map(|line| edit.transform(*line))
+            .collect();
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    collections::{vec_deque::Drain, VecDeque},
+    fmt::Display,
+};
+
+use crate::error::Error;
+
+/// Text for the embedder to show in the message area
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Info(String),
+    Error(Error),
+}
+
+impl Message {
+    pub fn is_error(&self) -> bool {
+        matches!(self, Message::Error(_))
+    }
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Message::Info(text) => f.write_str(text),
+            Message::Error(error) => error.fmt(f),
+        }
+    }
+}
+
+impl From<Error> for Message {
+    fn from(error: Error) -> Self {
+        Message::Error(error)
+    }
+}
+
+/// Position in the message queue, to find messages added after it
+///
+/// Positions count every message ever queued, so they stay valid as messages are drained.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MessageMark(usize);
+
+/// Messages waiting to be displayed, and the history shown by `:messages`
+#[derive(Debug, Default, Clone)]
+pub struct Messages {
+    queue: VecDeque<Message>,
+    history: VecDeque<Message>,
+    /// Messages drained from the front of the queue
+    drained: usize,
+}
+
+impl Messages {
+    /// Queue a message for display and keep it in the history, which holds up to `limit`
+    pub fn push(&mut self, message: Message, limit: usize) {
+        self.history.push_back(message.clone());
+        while self.history.len() > limit {
+            self.history.pop_front();
+        }
+        self.queue.push_back(message);
+    }
+
+    /// Queue a message for display without keeping it, as for command output
+    pub fn show(&mut self, message: Message) {
+        self.queue.push_back(message);
+    }
+
+    /// Take the messages waiting to be displayed, oldest first
+    pub fn drain(&mut self) -> Drain<'_, Message> {
+        self.drained += self.queue.len();
+        self.queue.drain(..)
+    }
+
+    /// Messages waiting to be displayed, oldest first
+    pub fn pending(&self) -> impl Iterator<Item = &Message> + '_ {
+        self.queue.iter()
+    }
+
+    /// Most recent message waiting to be displayed
+    pub fn last(&self) -> Option<&Message> {
+        self.queue.back()
+    }
+
+    /// Messages kept for `:messages`, oldest first
+    pub fn history(&self) -> impl Iterator<Item = &Message> + '_ {
+        self.history.iter()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    pub fn mark(&self) -> MessageMark {
+        MessageMark(self.drained + self.queue.len())
+    }
+
+    /// Whether an error has been queued since `mark`
+    pub fn error_since(&self, mark: MessageMark) -> bool {
+        let start = mark.0.saturating_sub(self.drained);
+        self.queue.iter().skip(start).any(Message::is_error)
+    }
+}
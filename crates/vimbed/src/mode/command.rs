@@ -1,9 +1,11 @@
+use std::num::NonZeroUsize;
+
 use nom::IResult;
 
 use crate::{
     command::{
         command_insert, command_variant, BufferCommand, Command, ContextCommand, COMMAND_BACKSPACE,
-        COMMAND_CARRIAGE_RETURN, COMMAND_ESCAPE,
+        COMMAND_CARRIAGE_RETURN, COMMAND_ESCAPE, COMMAND_LESS_THAN,
     },
     motion::{character::CharacterMotion, left_right::LeftRightMotion, Motion, MotionVariant},
     operator::OperatorVariant,
@@ -11,7 +13,7 @@ use crate::{
 
 use super::Mode;
 
-pub fn command_command(input: &str) -> IResult<&str, Command> {
+pub fn command_command(input: &str) -> IResult<&str, Command<'_>> {
     nom::branch::alt((
         command_variant(
             COMMAND_ESCAPE,
@@ -28,12 +30,14 @@ pub fn command_command(input: &str) -> IResult<&str, Command> {
         command_variant(
             COMMAND_BACKSPACE,
             Command::Multi(vec![
-                BufferCommand::from(MotionVariant::from(CharacterMotion::backward(1).unwrap()))
-                    .into(),
+                BufferCommand::from(MotionVariant::from(CharacterMotion::Backward(
+                    NonZeroUsize::MIN,
+                )))
+                .into(),
                 Command::operator(
                     1,
                     OperatorVariant::Delete,
-                    Motion::new_one(CharacterMotion::forward(1).unwrap().into()),
+                    Motion::new_one(CharacterMotion::Forward(NonZeroUsize::MIN).into()),
                 ),
             ]),
         ),
@@ -42,10 +46,11 @@ pub fn command_command(input: &str) -> IResult<&str, Command> {
             Command::Multi(vec![
                 Command::motion(1, CharacterMotion::EndOfBuffer.into()),
                 BufferCommand::Insert("\n").into(),
-                ContextCommand::RunCommand.into(),
                 ContextCommand::ChangeMode(Mode::Normal).into(),
+                ContextCommand::RunCommand.into(),
             ]),
         ),
+        command_variant(COMMAND_LESS_THAN, Command::from(BufferCommand::Insert("<"))),
         command_insert,
     ))(input)
 }
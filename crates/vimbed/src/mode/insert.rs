@@ -1,37 +1,33 @@
+use std::num::NonZeroUsize;
+
 use nom::IResult;
 
 use crate::{
     command::{
         command_insert, command_variant, BufferCommand, Command, ContextCommand, COMMAND_BACKSPACE,
-        COMMAND_ESCAPE,
+        COMMAND_ESCAPE, COMMAND_LESS_THAN, COMMAND_TAB,
     },
-    motion::{character::CharacterMotion, left_right::LeftRightMotion, Motion, MotionVariant},
-    operator::OperatorVariant,
+    motion::{left_right::LeftRightMotion, MotionVariant},
 };
 
 use super::Mode;
 
-pub fn insert_command(input: &str) -> IResult<&str, Command> {
+pub fn insert_command(input: &str) -> IResult<&str, Command<'_>> {
     nom::branch::alt((
         command_variant(
             COMMAND_ESCAPE,
             Command::Multi(vec![
-                BufferCommand::from(MotionVariant::from(LeftRightMotion::left(1).unwrap())).into(),
+                ContextCommand::FinishInsert.into(),
+                BufferCommand::from(MotionVariant::from(LeftRightMotion::Left(
+                    NonZeroUsize::MIN,
+                )))
+                .into(),
                 ContextCommand::from(Mode::Normal).into(),
             ]),
         ),
-        command_variant(
-            COMMAND_BACKSPACE,
-            Command::Multi(vec![
-                BufferCommand::from(MotionVariant::from(CharacterMotion::backward(1).unwrap()))
-                    .into(),
-                Command::operator(
-                    1,
-                    OperatorVariant::Delete,
-                    Motion::new_one(CharacterMotion::forward(1).unwrap().into()),
-                ),
-            ]),
-        ),
+        command_variant(COMMAND_BACKSPACE, Command::from(BufferCommand::Backspace)),
+        command_variant(COMMAND_TAB, Command::from(BufferCommand::InsertTab)),
+        command_variant(COMMAND_LESS_THAN, Command::from(BufferCommand::Insert("<"))),
         command_insert,
     ))(input)
 }
@@ -4,10 +4,20 @@ pub mod normal;
 
 use std::fmt::Display;
 
+use crate::operator::OperatorVariant;
+
 // Top-level mode
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Mode {
+    #[default]
     Normal,
+    /// An operator has been typed and awaits its motion
+    OperatorPending {
+        operator: OperatorVariant,
+        /// Count typed before the operator, if any
+        count: Option<usize>,
+    },
     Insert,
     Command(CommandMode),
 }
@@ -16,21 +26,26 @@ impl Display for Mode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(match self {
             Mode::Normal => "Normal",
+            Mode::OperatorPending { .. } => "Operator-pending",
             Mode::Insert => "Insert",
             Mode::Command(_) => "Command",
         })
     }
 }
 
-impl Default for Mode {
-    fn default() -> Self {
-        Mode::Normal
-    }
-}
-
 // Command mode submode
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CommandMode {
     Command,
     Search,
 }
+
+/// Shape a renderer should draw the cursor in
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CursorShape {
+    Block,
+    /// Vertical bar between characters
+    Bar,
+    Underline,
+}
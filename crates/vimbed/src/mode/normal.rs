@@ -4,101 +4,279 @@ use nom::IResult;
 
 use crate::{
     command::{
-        command_delete, command_variant, BufferCommand, Command, ContextCommand, COMMAND_BACKSPACE,
-        COMMAND_CARRIAGE_RETURN,
+        command_delete, command_register, command_variant, BufferCommand, Command, ContextCommand,
+        COMMAND_BACKSPACE, COMMAND_CARRIAGE_RETURN, COMMAND_CTRL_CARET, COMMAND_CTRL_R,
+        COMMAND_CTRL_W,
     },
+    ex::windows::WINCMD_KEYS,
+    key::{key, KeyCode},
     motion::{
         character::CharacterMotion, left_right::LeftRightMotion, motion, up_down::UpDownMotion,
-        word::WordMotion, Motion, MotionVariant,
+        word::WordMotion, Motion,
     },
     operator::{operator, Operator, OperatorVariant},
 };
 
 use super::{CommandMode, Mode};
 
+const ONE: NonZeroUsize = NonZeroUsize::MIN;
+
+/// Matches an optional count, which can't begin with `0` as that is a motion
+fn count(input: &str) -> IResult<&str, Option<NonZeroUsize>> {
+    let (input, digits) = nom::combinator::opt(nom::combinator::recognize(nom::sequence::pair(
+        nom::character::complete::one_of("123456789"),
+        nom::character::complete::digit0,
+    )))(input)?;
+
+    // Counts too large to represent saturate rather than failing
+    let count = digits.map(|digits| digits.parse().unwrap_or(NonZeroUsize::MAX));
+    Ok((input, count))
+}
+
 fn normal_motion(input: &str) -> IResult<&str, Motion> {
     nom::branch::alt((
-        motion(COMMAND_BACKSPACE, CharacterMotion::backward(1).unwrap()),
-        motion(COMMAND_CARRIAGE_RETURN, UpDownMotion::down(1).unwrap()),
-        motion("h", LeftRightMotion::Left(NonZeroUsize::new(1).unwrap())),
-        motion("j", UpDownMotion::down(1).unwrap()),
-        motion("k", UpDownMotion::up(1).unwrap()),
-        motion("l", LeftRightMotion::Right(NonZeroUsize::new(1).unwrap())),
+        motion(COMMAND_BACKSPACE, CharacterMotion::Backward(ONE)),
+        motion(COMMAND_CARRIAGE_RETURN, UpDownMotion::Down(ONE)),
+        motion(" ", CharacterMotion::Forward(ONE)),
+        motion("h", LeftRightMotion::Left(ONE)),
+        motion("j", UpDownMotion::Down(ONE)),
+        motion("k", UpDownMotion::Up(ONE)),
+        motion("l", LeftRightMotion::Right(ONE)),
         motion("0", LeftRightMotion::FirstCharacter),
         motion("^", LeftRightMotion::FirstNonBlankCharacter),
         motion("$", LeftRightMotion::LastCharacter),
         motion("gg", UpDownMotion::FirstLine),
         motion("G", UpDownMotion::LastLine),
-        motion("w", WordMotion::forward(1).unwrap()),
-        motion("b", WordMotion::backward(1).unwrap()),
+        motion("w", WordMotion::Forward(ONE)),
+        motion("b", WordMotion::Backward(ONE)),
     ))(input)
 }
 
-// Normal mode input
-fn normal_command_motion(input: &str) -> IResult<&str, Command> {
+/// Matches a motion with an optional count, such as `3w`
+fn counted_motion(input: &str) -> IResult<&str, Motion> {
+    let (input, count) = count(input)?;
     let (input, motion) = normal_motion(input)?;
-    Ok((input, BufferCommand::from(motion).into()))
+    Ok((
+        input,
+        count.map_or(motion, |count| motion.with_count(count)),
+    ))
+}
+
+// Normal mode input
+fn normal_command_motion<'a>(
+    count: Option<NonZeroUsize>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Command<'a>> {
+    move |input| {
+        let (input, motion) = normal_motion(input)?;
+        let motion = count.map_or(motion, |count| motion.with_count(count));
+        Ok((input, BufferCommand::from(motion).into()))
+    }
 }
 
 fn normal_operator(input: &str) -> IResult<&str, Operator> {
-    nom::branch::alt((operator("d", OperatorVariant::Delete, normal_motion),))(input)
+    nom::branch::alt((operator("d", OperatorVariant::Delete, counted_motion),))(input)
 }
 
-fn normal_command_operator(input: &str) -> IResult<&str, Command> {
-    let (input, operator) = normal_operator(input)?;
-    Ok((input, BufferCommand::from(operator).into()))
+/// Matches a count and operator awaiting their motion, such as `2d` at the start of `2d3w`
+pub fn normal_operator_pending(input: &str) -> IResult<&str, Mode> {
+    let (input, count) = count(input)?;
+    let (input, operator) = nom::branch::alt((nom::combinator::value(
+        OperatorVariant::Delete,
+        nom::bytes::complete::tag("d"),
+    ),))(input)?;
+
+    let count = count.map(NonZeroUsize::get);
+    Ok((input, Mode::OperatorPending { operator, count }))
 }
 
-pub fn normal_command(input: &str) -> IResult<&str, Command> {
-    nom::branch::alt((
-        command_variant("i", ContextCommand::from(Mode::Insert)),
-        command_variant(
-            "a",
-            Command::from(vec![
-                ContextCommand::from(Mode::Insert).into(),
-                BufferCommand::from(MotionVariant::from(LeftRightMotion::right(1).unwrap())).into(),
-            ]),
-        ),
-        command_variant(
-            "o",
-            Command::from(vec![
-                ContextCommand::from(Mode::Insert).into(),
-                BufferCommand::from(MotionVariant::from(LeftRightMotion::LastCharacter)).into(),
-                BufferCommand::Insert("\n").into(),
-            ]),
-        ),
-        command_variant(
-            "O",
-            Command::from(vec![
-                ContextCommand::from(Mode::Insert).into(),
-                BufferCommand::from(MotionVariant::from(LeftRightMotion::FirstCharacter)).into(),
-                BufferCommand::Insert("\n").into(),
-                BufferCommand::from(MotionVariant::from(UpDownMotion::up(1).unwrap())).into(),
-            ]),
-        ),
-        command_variant(
-            "s",
-            Command::from(vec![
-                Command::operator(
-                    1,
-                    OperatorVariant::Delete,
-                    Motion::new_one(CharacterMotion::forward(1).unwrap().into()),
+/// Matches an operator and its motion, the count before it multiplying the motion's
+fn normal_command_operator<'a>(
+    count: Option<NonZeroUsize>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Command<'a>> {
+    move |input| {
+        let (input, mut operator) = normal_operator(input)?;
+        operator.repeat = count.map_or(1, NonZeroUsize::get);
+        Ok((input, BufferCommand::from(operator).into()))
+    }
+}
+
+/// Matches a doubled operator acting on whole lines, such as `dd` or `d3d`
+fn normal_command_lines<'a>(
+    count: Option<NonZeroUsize>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Command<'a>> {
+    move |input| {
+        let (input, _) = nom::bytes::streaming::tag("d")(input)?;
+        let (input, inner) = self::count(input)?;
+        let (input, _) = nom::bytes::streaming::tag("d")(input)?;
+
+        let count = count.unwrap_or(ONE).saturating_mul(inner.unwrap_or(ONE));
+        Ok((input, ContextCommand::ExCount("delete", count.get()).into()))
+    }
+}
+
+/// Enter insert mode after `setup`, inserting the typed text `count` times on `<Esc>`
+fn insert<'a>(count: usize, newline: bool, setup: Vec<Command<'a>>) -> Command<'a> {
+    let mut commands = vec![ContextCommand::from(Mode::Insert).into()];
+    commands.extend(setup);
+    commands.push(ContextCommand::StartInsert { count, newline }.into());
+    commands.into()
+}
+
+fn normal_command_insert<'a>(count: usize) -> impl FnMut(&'a str) -> IResult<&'a str, Command<'a>> {
+    move |input| {
+        nom::branch::alt((
+            command_variant("i", insert(count, false, vec![])),
+            command_variant(
+                "a",
+                insert(
+                    count,
+                    false,
+                    vec![Command::motion(1, LeftRightMotion::Right(ONE).into())],
                 ),
-                ContextCommand::from(Mode::Insert).into(),
-            ]),
-        ),
-        command_variant(
-            "S",
-            Command::from(vec![
-                Command::motion(1, LeftRightMotion::FirstNonBlankCharacter.into()),
+            ),
+            command_variant(
+                "A",
+                insert(
+                    count,
+                    false,
+                    vec![Command::motion(1, LeftRightMotion::LastCharacter.into())],
+                ),
+            ),
+            command_variant(
+                "I",
+                insert(
+                    count,
+                    false,
+                    vec![Command::motion(
+                        1,
+                        LeftRightMotion::FirstNonBlankCharacter.into(),
+                    )],
+                ),
+            ),
+            command_variant(
+                "o",
+                insert(
+                    count,
+                    true,
+                    vec![
+                        Command::motion(1, LeftRightMotion::LastCharacter.into()),
+                        BufferCommand::Insert("\n").into(),
+                    ],
+                ),
+            ),
+            command_variant(
+                "O",
+                insert(
+                    count,
+                    true,
+                    vec![
+                        Command::motion(1, LeftRightMotion::FirstCharacter.into()),
+                        BufferCommand::Insert("\n").into(),
+                        Command::motion(1, UpDownMotion::Up(ONE).into()),
+                    ],
+                ),
+            ),
+            command_variant(
+                "s",
+                Command::from(vec![
+                    Command::operator(
+                        count,
+                        OperatorVariant::Delete,
+                        Motion::new_one(LeftRightMotion::Right(ONE).into()),
+                    ),
+                    ContextCommand::from(Mode::Insert).into(),
+                ]),
+            ),
+            // `{N}S` joins its lines into one, then changes that line
+            command_variant(
+                "S",
+                Command::from(vec![
+                    ContextCommand::ExCount("join", count).into(),
+                    // In insert mode, so the cursor stays after the indent
+                    ContextCommand::from(Mode::Insert).into(),
+                    Command::motion(1, LeftRightMotion::FirstNonBlankCharacter.into()),
+                    Command::operator(
+                        1,
+                        OperatorVariant::Delete,
+                        Motion::new_one(LeftRightMotion::LastCharacter.into()),
+                    ),
+                ]),
+            ),
+        ))(input)
+    }
+}
+
+fn normal_command_edit<'a>(count: usize) -> impl FnMut(&'a str) -> IResult<&'a str, Command<'a>> {
+    move |input| {
+        nom::branch::alt((
+            command_variant(
+                "x",
                 Command::operator(
-                    1,
+                    count,
                     OperatorVariant::Delete,
-                    Motion::new_one(LeftRightMotion::LastCharacter.into()),
+                    Motion::new_one(LeftRightMotion::Right(ONE).into()),
                 ),
-                ContextCommand::from(Mode::Insert).into(),
-            ]),
-        ),
+            ),
+            // Joining fewer than two lines still joins the next one
+            command_variant("J", ContextCommand::ExCount("join", count.max(2))),
+            command_variant(
+                "p",
+                ContextCommand::Put {
+                    before: false,
+                    count,
+                },
+            ),
+            command_variant(
+                "P",
+                ContextCommand::Put {
+                    before: true,
+                    count,
+                },
+            ),
+            command_variant("u", BufferCommand::Undo(count)),
+            command_variant(COMMAND_CTRL_R, BufferCommand::Redo(count)),
+            command_variant("g&", ContextCommand::Ex("%s//~/&")),
+            command_register("q", ContextCommand::Record),
+            command_register("@", move |name| ContextCommand::Execute(name, count)),
+        ))(input)
+    }
+}
+
+/// Matches `Ctrl-W` followed by a window command key, which may also be typed with ctrl
+/// held, as in `<C-w><C-w>`
+fn normal_command_window<'a>(
+    count: Option<NonZeroUsize>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Command<'a>> {
+    move |input| {
+        let (input, _) = nom::bytes::streaming::tag(COMMAND_CTRL_W)(input)?;
+        if input.is_empty() {
+            return Err(nom::Err::Incomplete(nom::Needed::new(1)));
+        }
+        let (rest, typed) = key(input)?;
+        match typed.code {
+            KeyCode::Char(c) if WINCMD_KEYS.contains(c) => Ok((
+                rest,
+                ContextCommand::Window(c, count.map(NonZeroUsize::get)).into(),
+            )),
+            _ => Err(nom::Err::Error(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::OneOf,
+            ))),
+        }
+    }
+}
+
+/// Matches a normal mode command, with a count that applies to all of it
+///
+/// A count before an operator multiplies the count before its motion, so `2d3w`
+/// deletes six words.
+pub fn normal_command(input: &str) -> IResult<&str, Command<'_>> {
+    let (input, count) = count(input)?;
+    let n = count.map_or(1, NonZeroUsize::get);
+
+    nom::branch::alt((
+        normal_command_insert(n),
+        normal_command_edit(n),
         command_variant(
             ":",
             ContextCommand::from(Mode::Command(CommandMode::Command)),
@@ -107,31 +285,27 @@ pub fn normal_command(input: &str) -> IResult<&str, Command> {
             "/",
             ContextCommand::from(Mode::Command(CommandMode::Search)),
         ),
+        // `{N}<C-^>` edits buffer N rather than the alternate buffer
         command_variant(
-            "x",
-            Command::operator(
-                1,
-                OperatorVariant::Delete,
-                Motion::new_one(CharacterMotion::forward(1).unwrap().into()),
-            ),
-        ),
-        command_variant(
-            "dd",
-            Command::from(vec![
-                Command::motion(1, LeftRightMotion::FirstCharacter.into()),
-                Command::operator(
-                    1,
-                    OperatorVariant::Delete,
-                    Motion::new_one(UpDownMotion::down(1).unwrap().into()),
-                ),
-            ]),
+            COMMAND_CTRL_CARET,
+            match count {
+                Some(count) => ContextCommand::ExCount("buffer", count.get()),
+                None => ContextCommand::Ex("buffer #"),
+            },
         ),
+        // `{N}gt` goes to tab page N rather than the next one
         command_variant(
-            " ",
-            BufferCommand::from(MotionVariant::from(CharacterMotion::forward(1).unwrap())),
+            "gt",
+            match count {
+                Some(count) => ContextCommand::ExCount("tabnext", count.get()),
+                None => ContextCommand::Ex("tabnext"),
+            },
         ),
-        normal_command_motion,
-        normal_command_operator,
+        command_variant("gT", ContextCommand::ExCount("tabprevious", n)),
+        normal_command_window(count),
+        normal_command_lines(count),
+        normal_command_motion(count),
+        normal_command_operator(count),
         command_delete,
     ))(input)
 }
@@ -1,6 +1,6 @@
 use std::num::NonZeroUsize;
 
-use crate::{buffer::Buffer, char_len::CharLen};
+use crate::{buffer::Buffer, storage::TextStorage};
 
 use super::MotionTrait;
 
@@ -20,40 +20,64 @@ impl CharacterMotion {
     pub fn backward(count: usize) -> Option<Self> {
         NonZeroUsize::new(count).map(CharacterMotion::Backward)
     }
+
+    /// The motion with its distance multiplied by `count`
+    pub fn with_count(self, count: NonZeroUsize) -> Self {
+        match self {
+            CharacterMotion::Forward(dc) => CharacterMotion::Forward(dc.saturating_mul(count)),
+            CharacterMotion::Backward(dc) => CharacterMotion::Backward(dc.saturating_mul(count)),
+            motion => motion,
+        }
+    }
 }
 
 impl MotionTrait for CharacterMotion {
-    fn apply(&self, buffer: &mut Buffer, block_newline: bool) {
+    fn apply<S: TextStorage>(&self, buffer: &mut Buffer<S>, block_newline: bool) {
         let cursor_offset = buffer.cursor_offset();
         let next_offset = match self {
             CharacterMotion::Forward(dc) => {
-                let mut ofs = cursor_offset.saturating_add(dc.get());
-                if buffer.cursor.column == buffer.cursor_line().char_len().saturating_sub(1)
-                    && block_newline
+                let mut ofs = cursor_offset;
+                for _ in 0..dc
+                    .get()
+                    .min(buffer.text.len().saturating_sub(cursor_offset))
                 {
-                    ofs = ofs.saturating_add(1);
+                    ofs = buffer.next_offset(ofs);
+
+                    // Step over the line break rather than resting on it
+                    let (column, row) = buffer.offset_position(ofs);
+                    let line = buffer.line(row).unwrap_or_default();
+                    if block_newline && !line.is_empty() && column == line.len() {
+                        ofs = buffer.next_offset(ofs);
+                    }
                 }
 
-                ofs.min(buffer.char_len().saturating_sub(1))
+                if block_newline && ofs >= buffer.text.len() {
+                    buffer.prev_offset(buffer.text.len())
+                } else {
+                    ofs
+                }
             }
             CharacterMotion::Backward(dc) => {
-                let mut ofs = cursor_offset.saturating_sub(dc.get());
-
-                if buffer.cursor.column == 0 && block_newline {
-                    ofs = ofs.saturating_sub(1);
+                let mut ofs = cursor_offset;
+                for _ in 0..dc.get().min(cursor_offset) {
+                    let (column, _) = buffer.offset_position(ofs);
+                    ofs = buffer.prev_offset(ofs);
+                    if block_newline && column == 0 && ofs > 0 {
+                        let (_, row) = buffer.offset_position(ofs);
+                        if !buffer.line(row).unwrap_or_default().is_empty() {
+                            ofs = buffer.prev_offset(ofs);
+                        }
+                    }
                 }
-
-                ofs.min(buffer.char_len().saturating_sub(1))
+                ofs
             }
             CharacterMotion::StartOfBuffer => 0,
-            CharacterMotion::EndOfBuffer => buffer.buffer.char_len(),
+            CharacterMotion::EndOfBuffer => buffer.text.len(),
         };
 
         let (x, y) = buffer.offset_position(next_offset);
 
-        buffer.cursor.column = x;
         buffer.cursor.row = y;
-
-        buffer.cursor.target_column = buffer.cursor.column;
+        buffer.set_column(x);
     }
 }
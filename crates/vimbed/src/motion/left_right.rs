@@ -1,8 +1,8 @@
 use std::num::NonZeroUsize;
 
-use crate::{buffer::Buffer, char_len::CharLen};
+use crate::{buffer::Buffer, grapheme, storage::TextStorage};
 
-use super::{Motion, MotionTrait, WordMotion};
+use super::MotionTrait;
 
 #[derive(Debug, Copy, Clone)]
 pub enum LeftRightMotion {
@@ -21,41 +21,40 @@ impl LeftRightMotion {
     pub fn right(count: usize) -> Option<Self> {
         NonZeroUsize::new(count).map(LeftRightMotion::Right)
     }
-}
 
-impl MotionTrait for LeftRightMotion {
-    fn apply(&self, buffer: &mut Buffer, block_newline: bool) {
-        let line_end = buffer.cursor_line().char_len();
-        let line_end_offset = line_end.saturating_sub(if block_newline { 1 } else { 0 });
+    /// The motion with its distance multiplied by `count`
+    pub fn with_count(self, count: NonZeroUsize) -> Self {
         match self {
-            LeftRightMotion::FirstCharacter => buffer.cursor.column = 0,
-            LeftRightMotion::FirstNonBlankCharacter => {
-                buffer.motion(
-                    Motion::new_one(LeftRightMotion::FirstCharacter.into()),
-                    block_newline,
-                );
-                buffer.motion(
-                    Motion::new_one(WordMotion::forward(1).unwrap().into()),
-                    block_newline,
-                );
-            }
-            LeftRightMotion::LastCharacter => buffer.cursor.column = line_end_offset,
-            LeftRightMotion::Left(dx) => {
-                if buffer.cursor.column > 0 {
-                    buffer.cursor.column = buffer.cursor.column.saturating_sub(dx.get());
-                }
-            }
-            LeftRightMotion::Right(dx) => {
-                if buffer.cursor.column < line_end_offset {
-                    buffer.cursor.column = buffer
-                        .cursor
-                        .column
-                        .saturating_add(dx.get())
-                        .min(line_end_offset);
-                }
-            }
+            LeftRightMotion::Left(dx) => LeftRightMotion::Left(dx.saturating_mul(count)),
+            LeftRightMotion::Right(dx) => LeftRightMotion::Right(dx.saturating_mul(count)),
+            motion => motion,
         }
+    }
+}
+
+impl MotionTrait for LeftRightMotion {
+    fn apply<S: TextStorage>(&self, buffer: &mut Buffer<S>, block_newline: bool) {
+        let line = buffer.cursor_line();
+        let line_end_offset = if block_newline {
+            grapheme::last_boundary(&line)
+        } else {
+            line.len()
+        };
+        let column = match self {
+            LeftRightMotion::FirstCharacter => 0,
+            LeftRightMotion::FirstNonBlankCharacter => buffer.first_non_blank(buffer.cursor.row),
+            LeftRightMotion::LastCharacter => line_end_offset,
+            LeftRightMotion::Left(dx) => (0..dx.get().min(line.len()))
+                .fold(buffer.cursor.column, |column, _| {
+                    grapheme::prev_boundary(&line, column)
+                }),
+            LeftRightMotion::Right(dx) => (0..dx.get().min(line.len()))
+                .fold(buffer.cursor.column, |column, _| {
+                    grapheme::next_boundary(&line, column)
+                })
+                .min(line_end_offset.max(buffer.cursor.column)),
+        };
 
-        buffer.cursor.target_column = buffer.cursor.column;
+        buffer.set_column(column);
     }
 }
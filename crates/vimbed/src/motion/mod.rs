@@ -8,14 +8,16 @@ use left_right::LeftRightMotion;
 use up_down::UpDownMotion;
 use word::WordMotion;
 
+use std::num::NonZeroUsize;
+
 use nom::IResult;
 
-use crate::buffer::Buffer;
+use crate::{buffer::Buffer, storage::TextStorage};
 
 /// Trait for applying a motion to a buffer
 /// TODO: This should probably apply to a new Cursor abstraction
 pub trait MotionTrait {
-    fn apply(&self, buffer: &mut Buffer, block_newline: bool);
+    fn apply<S: TextStorage>(&self, buffer: &mut Buffer<S>, block_newline: bool);
 }
 
 /// Repeatable motion
@@ -32,11 +34,8 @@ where
 {
     let variant = variant.into();
     move |input| {
-        let (input, repeat) = nom::character::complete::digit0(input)?;
-        let repeat = repeat.parse::<usize>().unwrap_or(1);
-
         let (input, _) = nom::bytes::streaming::tag(tag)(input)?;
-        Ok((input, Motion::new(repeat, variant)))
+        Ok((input, Motion::new_one(variant)))
     }
 }
 
@@ -48,10 +47,18 @@ impl Motion {
     pub fn new_one(variant: MotionVariant) -> Self {
         Self::new(1, variant)
     }
+
+    /// The motion with a count typed before it, multiplying any count it already has
+    pub fn with_count(self, count: NonZeroUsize) -> Self {
+        Motion {
+            variant: self.variant.with_count(count),
+            ..self
+        }
+    }
 }
 
 impl MotionTrait for Motion {
-    fn apply(&self, buffer: &mut Buffer, block_newline: bool) {
+    fn apply<S: TextStorage>(&self, buffer: &mut Buffer<S>, block_newline: bool) {
         for _ in 0..self.repeat {
             self.variant.apply(buffer, block_newline);
         }
@@ -92,8 +99,20 @@ impl From<CharacterMotion> for MotionVariant {
     }
 }
 
+impl MotionVariant {
+    /// The motion with its distance multiplied by `count`
+    pub fn with_count(self, count: NonZeroUsize) -> Self {
+        match self {
+            MotionVariant::LeftRight(motion) => motion.with_count(count).into(),
+            MotionVariant::UpDown(motion) => motion.with_count(count).into(),
+            MotionVariant::Word(motion) => motion.with_count(count).into(),
+            MotionVariant::Character(motion) => motion.with_count(count).into(),
+        }
+    }
+}
+
 impl MotionTrait for MotionVariant {
-    fn apply(&self, buffer: &mut Buffer, block_newline: bool) {
+    fn apply<S: TextStorage>(&self, buffer: &mut Buffer<S>, block_newline: bool) {
         match self {
             MotionVariant::LeftRight(motion) => motion.apply(buffer, block_newline),
             MotionVariant::UpDown(motion) => motion.apply(buffer, block_newline),
@@ -1,6 +1,6 @@
 use std::num::NonZeroUsize;
 
-use crate::{buffer::Buffer, char_len::CharLen};
+use crate::{buffer::Buffer, grapheme, storage::TextStorage};
 
 use super::MotionTrait;
 
@@ -8,6 +8,8 @@ use super::MotionTrait;
 pub enum UpDownMotion {
     FirstLine,
     LastLine,
+    /// Line number, counting from one
+    Line(NonZeroUsize),
     Up(NonZeroUsize),
     Down(NonZeroUsize),
 }
@@ -20,36 +22,49 @@ impl UpDownMotion {
     pub fn down(count: usize) -> Option<Self> {
         NonZeroUsize::new(count).map(UpDownMotion::Down)
     }
+
+    /// The motion with its distance multiplied by `count`
+    ///
+    /// A count given to the first or last line motion is the line to move to instead.
+    pub fn with_count(self, count: NonZeroUsize) -> Self {
+        match self {
+            UpDownMotion::FirstLine | UpDownMotion::LastLine => UpDownMotion::Line(count),
+            UpDownMotion::Line(line) => UpDownMotion::Line(line.saturating_mul(count)),
+            UpDownMotion::Up(dy) => UpDownMotion::Up(dy.saturating_mul(count)),
+            UpDownMotion::Down(dy) => UpDownMotion::Down(dy.saturating_mul(count)),
+        }
+    }
 }
 
 impl MotionTrait for UpDownMotion {
-    fn apply(&self, buffer: &mut Buffer, block_newline: bool) {
+    fn apply<S: TextStorage>(&self, buffer: &mut Buffer<S>, block_newline: bool) {
         match self {
             UpDownMotion::FirstLine => buffer.cursor.row = 0,
-            UpDownMotion::LastLine => buffer.cursor.row = buffer.lines().count() - 1,
+            UpDownMotion::LastLine => buffer.cursor.row = buffer.line_count() - 1,
+            UpDownMotion::Line(line) => {
+                buffer.cursor.row = (line.get() - 1).min(buffer.line_count().saturating_sub(1))
+            }
             UpDownMotion::Up(dy) => {
                 if buffer.cursor.row > 0 {
                     buffer.cursor.row = buffer.cursor.row.saturating_sub(dy.get());
                 }
             }
             UpDownMotion::Down(dy) => {
-                let row_end = buffer.lines().count().saturating_sub(1);
+                let row_end = buffer.line_count().saturating_sub(1);
                 if buffer.cursor.row < row_end {
                     buffer.cursor.row = buffer.cursor.row.saturating_add(dy.get()).min(row_end);
                 }
             }
         }
 
-        let line_end = buffer.cursor_line().char_len();
-        let line_end_offset = line_end.saturating_sub(if block_newline { 1 } else { 0 });
-
-        if buffer.cursor.column > line_end_offset {
-            buffer.cursor.column = line_end_offset;
-        }
+        let line = buffer.cursor_line();
+        let line_end_offset = if block_newline {
+            grapheme::last_boundary(&line)
+        } else {
+            line.len()
+        };
 
-        let target_column = buffer.cursor.target_column.min(line_end_offset);
-        if buffer.cursor.column < target_column {
-            buffer.cursor.column = target_column
-        }
+        let column = buffer.column_at_virtual(buffer.cursor.row, buffer.cursor.target_column);
+        buffer.cursor.column = column.min(line_end_offset);
     }
 }
@@ -1,6 +1,6 @@
 use std::num::NonZeroUsize;
 
-use crate::buffer::Buffer;
+use crate::{buffer::Buffer, storage::TextStorage};
 
 use super::MotionTrait;
 
@@ -18,30 +18,45 @@ impl WordMotion {
     pub fn backward(count: usize) -> Option<Self> {
         NonZeroUsize::new(count).map(WordMotion::Backward)
     }
+
+    /// The motion with its distance multiplied by `count`
+    pub fn with_count(self, count: NonZeroUsize) -> Self {
+        match self {
+            WordMotion::Forward(dw) => WordMotion::Forward(dw.saturating_mul(count)),
+            WordMotion::Backward(dw) => WordMotion::Backward(dw.saturating_mul(count)),
+        }
+    }
 }
 
 impl MotionTrait for WordMotion {
-    fn apply(&self, buffer: &mut Buffer, _block_newline: bool) {
-        let word_offsets = buffer.word_offsets();
-
-        let cursor_word_offset = buffer.cursor_word_offset();
-
-        let next_word_idx = match self {
-            WordMotion::Forward(dw) => cursor_word_offset
-                .saturating_add(dw.get())
-                .min(word_offsets.len().saturating_sub(1)),
-            WordMotion::Backward(dw) => cursor_word_offset
-                .saturating_sub(dw.get())
-                .min(word_offsets.len().saturating_sub(2)),
+    fn apply<S: TextStorage>(&self, buffer: &mut Buffer<S>, _block_newline: bool) {
+        let cursor_offset = buffer.cursor_offset();
+
+        // Start of the word under the cursor, or the first word when the cursor precedes it
+        let Some(word_offset) = buffer
+            .prev_word(cursor_offset + 1)
+            .or_else(|| buffer.next_word(cursor_offset))
+        else {
+            return;
         };
 
-        let next_word_offset = word_offsets[next_word_idx];
+        // Step over whole words, stopping at the first or last one
+        let mut next_word_offset = word_offset;
+        let (WordMotion::Forward(dw) | WordMotion::Backward(dw)) = self;
+        for _ in 0..dw.get() {
+            let next = match self {
+                WordMotion::Forward(_) => buffer.next_word(next_word_offset),
+                WordMotion::Backward(_) => buffer.prev_word(next_word_offset),
+            };
+            let Some(next) = next else {
+                break;
+            };
+            next_word_offset = next;
+        }
 
         let (x, y) = buffer.offset_position(next_word_offset);
 
-        buffer.cursor.column = x;
         buffer.cursor.row = y;
-
-        buffer.cursor.target_column = buffer.cursor.column;
+        buffer.set_column(x);
     }
 }
@@ -1,14 +1,22 @@
-use crate::{buffer::Buffer, motion::Motion};
+use crate::{buffer::Buffer, motion::Motion, storage::TextStorage};
 
-pub fn operator_delete(buffer: &mut Buffer, motion: Motion) {
+/// Delete the text the motion moves over, returning it
+pub fn operator_delete<S: TextStorage>(buffer: &mut Buffer<S>, motion: Motion) -> String {
+    let cursor = buffer.cursor;
     let from = buffer.cursor_offset();
     buffer.motion(motion, false);
     let to = buffer.cursor_offset();
 
+    // Undo restores the cursor from before the motion
+    buffer.cursor = cursor;
+
     let (from, to) = (from.min(to), from.max(to));
-    buffer.replace_range(from..to, "");
+    let text = buffer.text.slice(from..to).into_owned();
+    buffer.splice(from..to, "");
 
     let (x, y) = buffer.offset_position(from);
-    buffer.cursor.column = x;
     buffer.cursor.row = y;
+    buffer.set_column(x);
+
+    text
 }
@@ -2,13 +2,16 @@ pub mod delete;
 
 use delete::operator_delete;
 
+use std::num::NonZeroUsize;
+
 use nom::IResult;
 
-use crate::motion::Motion;
+use crate::{buffer::Buffer, motion::Motion, register::Register, storage::TextStorage};
 
 /// A type that can apply an operator to a buffer
 pub trait OperatorTrait {
-    fn apply(&self, buffer: &mut crate::buffer::Buffer);
+    /// Apply the operator, returning any text it removed for the registers
+    fn apply<S: TextStorage>(&self, buffer: &mut Buffer<S>) -> Option<Register>;
 }
 
 /// Repeatable operator
@@ -34,16 +37,23 @@ impl Operator {
 }
 
 impl OperatorTrait for Operator {
-    fn apply(&self, buffer: &mut crate::buffer::Buffer) {
+    fn apply<S: TextStorage>(&self, buffer: &mut Buffer<S>) -> Option<Register> {
+        // A count before the operator multiplies the motion's count
+        let motion = match NonZeroUsize::new(self.repeat) {
+            Some(count) if count.get() > 1 => self.motion.with_count(count),
+            _ => self.motion,
+        };
+
         match self.variant {
-            OperatorVariant::Delete => operator_delete(buffer, self.motion),
-            _ => (),
+            OperatorVariant::Delete => Some(Register::charwise(operator_delete(buffer, motion))),
+            _ => None,
         }
     }
 }
 
 /// Closed set of built-in operators
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OperatorVariant {
     Change,
     Delete,
@@ -73,13 +83,10 @@ where
 {
     let variant = variant.into();
     move |input| {
-        let (input, repeat) = nom::character::complete::digit0(input)?;
-        let repeat = repeat.parse::<usize>().unwrap_or(1);
-
         let (input, _) = nom::bytes::streaming::tag(tag)(input)?;
 
         let (input, motion) = motion(input)?;
 
-        Ok((input, Operator::new(repeat, variant, motion)))
+        Ok((input, Operator::new_one(variant, motion)))
     }
 }
@@ -0,0 +1,581 @@
+use std::{collections::BTreeMap, fmt::Debug};
+
+use crate::error::Error;
+
+/// Value of an option
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OptionValue {
+    Bool(bool),
+    Number(i64),
+    String(String),
+    /// Comma-separated list of items
+    List(Vec<String>),
+}
+
+impl OptionValue {
+    /// Display the value as `:set` would, given the option's name
+    pub fn display(&self, name: &str) -> String {
+        match self {
+            OptionValue::Bool(true) => name.to_owned(),
+            OptionValue::Bool(false) => format!("no{}", name),
+            OptionValue::Number(n) => format!("{}={}", name, n),
+            OptionValue::String(s) => format!("{}={}", name, s),
+            OptionValue::List(items) => format!("{}={}", name, items.join(",")),
+        }
+    }
+
+    /// Parse `text` as a value of the same type as `self`
+    fn parse(&self, name: &str, text: &str) -> Result<OptionValue, Error> {
+        Ok(match self {
+            OptionValue::Bool(_) => {
+                return Err(Error::new(
+                    474,
+                    format!("Invalid argument: {}={}", name, text),
+                ));
+            }
+            OptionValue::Number(_) => match text.parse::<i64>() {
+                Ok(n) => OptionValue::Number(n),
+                Err(_) => {
+                    return Err(Error::new(
+                        521,
+                        format!("Number required after =: {}={}", name, text),
+                    ))
+                }
+            },
+            OptionValue::String(_) => OptionValue::String(text.to_owned()),
+            OptionValue::List(_) => OptionValue::List(
+                text.split(',')
+                    .filter(|item| !item.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect(),
+            ),
+        })
+    }
+}
+
+impl From<bool> for OptionValue {
+    fn from(b: bool) -> Self {
+        OptionValue::Bool(b)
+    }
+}
+
+impl From<i64> for OptionValue {
+    fn from(n: i64) -> Self {
+        OptionValue::Number(n)
+    }
+}
+
+impl From<&str> for OptionValue {
+    fn from(s: &str) -> Self {
+        OptionValue::String(s.to_owned())
+    }
+}
+
+impl From<String> for OptionValue {
+    fn from(s: String) -> Self {
+        OptionValue::String(s)
+    }
+}
+
+impl From<Vec<String>> for OptionValue {
+    fn from(items: Vec<String>) -> Self {
+        OptionValue::List(items)
+    }
+}
+
+/// Whether an option has a single value or one per buffer
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptionScope {
+    Global,
+    /// Local to a buffer, with a global value used by buffers that don't set their own
+    Buffer,
+}
+
+/// Checks a new value before it is assigned
+pub type OptionValidator = fn(&OptionValue) -> Result<(), Error>;
+
+/// Definition of an option in the registry
+#[derive(Debug, Clone)]
+pub struct OptionDef {
+    pub name: &'static str,
+    pub short: Option<&'static str>,
+    pub scope: OptionScope,
+    pub default: OptionValue,
+    pub validate: Option<OptionValidator>,
+}
+
+impl OptionDef {
+    pub fn new(name: &'static str, scope: OptionScope, default: impl Into<OptionValue>) -> Self {
+        OptionDef {
+            name,
+            short: None,
+            scope,
+            default: default.into(),
+            validate: None,
+        }
+    }
+
+    pub fn with_short(mut self, short: &'static str) -> Self {
+        self.short = Some(short);
+        self
+    }
+
+    pub fn with_validator(mut self, validate: OptionValidator) -> Self {
+        self.validate = Some(validate);
+        self
+    }
+}
+
+/// Which values of a buffer-local option an assignment writes
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SetScope {
+    /// `:set`, writing both the global and local value
+    Both,
+    /// `:setlocal`
+    Local,
+    /// `:setglobal`
+    Global,
+}
+
+/// Notification of an assignment to an option
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionChange {
+    pub name: &'static str,
+    pub scope: SetScope,
+    /// Effective value before the assignment
+    pub old: OptionValue,
+    /// Effective value after the assignment
+    pub new: OptionValue,
+}
+
+/// Callback invoked after an option is assigned
+pub type OptionCallback = Box<dyn FnMut(&OptionChange)>;
+
+fn positive(value: &OptionValue) -> Result<(), Error> {
+    match value {
+        OptionValue::Number(n) if *n <= 0 => Err(Error::new(487, "Argument must be positive")),
+        _ => Ok(()),
+    }
+}
+
+fn non_negative(value: &OptionValue) -> Result<(), Error> {
+    match value {
+        OptionValue::Number(n) if *n < 0 => Err(Error::new(487, "Argument must be positive")),
+        _ => Ok(()),
+    }
+}
+
+/// Largest tab or indent width, as in Vim, so whitespace built from it stays small
+pub const TABSTOP_MAX: i64 = 9999;
+
+fn tab_width(value: &OptionValue) -> Result<(), Error> {
+    match value {
+        OptionValue::Number(n) if *n > TABSTOP_MAX => Err(Error::new(474, "Invalid argument")),
+        _ => Ok(()),
+    }
+}
+
+fn tab_stop(value: &OptionValue) -> Result<(), Error> {
+    positive(value).and_then(|_| tab_width(value))
+}
+
+fn shift_width(value: &OptionValue) -> Result<(), Error> {
+    non_negative(value).and_then(|_| tab_width(value))
+}
+
+/// Options known to every context
+fn builtin() -> Vec<OptionDef> {
+    use OptionScope::*;
+
+    vec![
+        OptionDef::new("ignorecase", Global, false).with_short("ic"),
+        OptionDef::new("smartcase", Global, false).with_short("scs"),
+        OptionDef::new("report", Global, 2i64).with_validator(non_negative),
+        OptionDef::new("virtualedit", Global, Vec::new()).with_short("ve"),
+        OptionDef::new("tabstop", Buffer, 8i64)
+            .with_short("ts")
+            .with_validator(tab_stop),
+        OptionDef::new("shiftwidth", Buffer, 8i64)
+            .with_short("sw")
+            .with_validator(shift_width),
+        OptionDef::new("softtabstop", Buffer, 0i64)
+            .with_short("sts")
+            .with_validator(tab_width),
+        OptionDef::new("expandtab", Buffer, false).with_short("et"),
+        OptionDef::new("modifiable", Buffer, true).with_short("ma"),
+        OptionDef::new("readonly", Buffer, false).with_short("ro"),
+        OptionDef::new("maxmapdepth", Global, 1000i64)
+            .with_short("mmd")
+            .with_validator(positive),
+        OptionDef::new("timeout", Global, true).with_short("to"),
+        OptionDef::new("timeoutlen", Global, 1000i64)
+            .with_short("tm")
+            .with_validator(non_negative),
+        OptionDef::new("ttimeout", Global, false),
+        OptionDef::new("ttimeoutlen", Global, -1i64).with_short("ttm"),
+        OptionDef::new("hidden", Global, false).with_short("hid"),
+        OptionDef::new("msghistory", Global, 500i64)
+            .with_short("mhi")
+            .with_validator(non_negative),
+        // Stands in for Vim's `g:mapleader` variable, which has no equivalent here
+        OptionDef::new("mapleader", Global, "\\"),
+    ]
+}
+
+/// Values of buffer-local options set for one buffer
+pub type LocalOptions = BTreeMap<&'static str, OptionValue>;
+
+/// Typed option registry holding global and buffer-local values
+pub struct Options {
+    defs: BTreeMap<&'static str, OptionDef>,
+    global: BTreeMap<&'static str, OptionValue>,
+    /// Values local to the current buffer
+    local: LocalOptions,
+    subscribers: Vec<OptionCallback>,
+}
+
+impl Debug for Options {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Options")
+            .field("global", &self.global)
+            .field("local", &self.local)
+            .finish()
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        let mut options = Options {
+            defs: Default::default(),
+            global: Default::default(),
+            local: Default::default(),
+            subscribers: Default::default(),
+        };
+
+        for def in builtin() {
+            options.register(def);
+        }
+
+        options
+    }
+}
+
+impl Options {
+    /// Add an option to the registry, replacing any existing definition of the same name
+    pub fn register(&mut self, def: OptionDef) {
+        self.global.insert(def.name, def.default.clone());
+        self.local.remove(def.name);
+        self.defs.insert(def.name, def);
+    }
+
+    /// Look up an option by full or short name
+    pub fn def(&self, name: &str) -> Option<&OptionDef> {
+        self.defs
+            .get(name)
+            .or_else(|| self.defs.values().find(|def| def.short == Some(name)))
+    }
+
+    /// Iterate over all registered options in name order
+    pub fn defs(&self) -> impl Iterator<Item = &OptionDef> {
+        self.defs.values()
+    }
+
+    /// Effective value of an option: its local value if set, otherwise its global value
+    pub fn get(&self, name: &str) -> Option<&OptionValue> {
+        let name = self.def(name)?.name;
+        self.local.get(name).or_else(|| self.global.get(name))
+    }
+
+    /// Global value of each option that differs from its default
+    pub fn changed_globals(&self) -> impl Iterator<Item = (&'static str, &OptionValue)> {
+        self.defs.values().flat_map(|def| {
+            let value = self.global.get(def.name)?;
+            (*value != def.default).then_some((def.name, value))
+        })
+    }
+
+    /// Local value of each buffer-local option set with `:set` or `:setlocal`
+    pub fn locals(&self) -> impl Iterator<Item = (&'static str, &OptionValue)> {
+        self.local.iter().map(|(name, value)| (*name, value))
+    }
+
+    /// Local values of the current buffer, swapped out when another becomes current
+    ///
+    /// Buffer-local options without a local value take their global value first, so
+    /// `:set` in another buffer doesn't change them.
+    pub(crate) fn locals_mut(&mut self) -> &mut LocalOptions {
+        for def in self.defs.values() {
+            if def.scope == OptionScope::Buffer && !self.local.contains_key(def.name) {
+                if let Some(value) = self.global.get(def.name) {
+                    self.local.insert(def.name, value.clone());
+                }
+            }
+        }
+        &mut self.local
+    }
+
+    /// Global value of an option
+    pub fn get_global(&self, name: &str) -> Option<&OptionValue> {
+        let name = self.def(name)?.name;
+        self.global.get(name)
+    }
+
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            OptionValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn number(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            OptionValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn string(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            OptionValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn list(&self, name: &str) -> Option<&[String]> {
+        match self.get(name)? {
+            OptionValue::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Whether a list option contains `item`
+    pub fn contains(&self, name: &str, item: &str) -> bool {
+        self.list(name)
+            .map(|items| items.iter().any(|i| i == item))
+            .unwrap_or_default()
+    }
+
+    /// Assign an option as `:set` does
+    pub fn set(&mut self, name: &str, value: impl Into<OptionValue>) -> Result<(), Error> {
+        self.set_scoped(name, value.into(), SetScope::Both)
+    }
+
+    /// Assign an option as `:setlocal` does
+    pub fn set_local(&mut self, name: &str, value: impl Into<OptionValue>) -> Result<(), Error> {
+        self.set_scoped(name, value.into(), SetScope::Local)
+    }
+
+    /// Assign an option as `:setglobal` does
+    pub fn set_global(&mut self, name: &str, value: impl Into<OptionValue>) -> Result<(), Error> {
+        self.set_scoped(name, value.into(), SetScope::Global)
+    }
+
+    /// Reset an option to its default value
+    pub fn reset(&mut self, name: &str, scope: SetScope) -> Result<(), Error> {
+        let Some(def) = self.def(name) else {
+            return Err(Error::new(518, format!("Unknown option: {}", name)));
+        };
+        let default = def.default.clone();
+        self.set_scoped(name, default, scope)
+    }
+
+    /// Check that an option exists and accepts `value`, returning its definition
+    fn check(&self, name: &str, value: &OptionValue) -> Result<&OptionDef, Error> {
+        let Some(def) = self.def(name) else {
+            return Err(Error::new(518, format!("Unknown option: {}", name)));
+        };
+
+        if std::mem::discriminant(&def.default) != std::mem::discriminant(value) {
+            return Err(Error::new(474, format!("Invalid argument: {}", def.name)));
+        }
+
+        if let Some(validate) = def.validate {
+            validate(value)?;
+        }
+        Ok(def)
+    }
+
+    /// Local values for a buffer that isn't current, keeping only the buffer-local options
+    /// that accept their value
+    #[cfg(feature = "serde")]
+    pub(crate) fn checked_locals(
+        &self,
+        values: impl IntoIterator<Item = (String, OptionValue)>,
+    ) -> LocalOptions {
+        values
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let def = self.check(&name, &value).ok()?;
+                (def.scope == OptionScope::Buffer).then_some((def.name, value))
+            })
+            .collect()
+    }
+
+    /// Assign an option, checking its type and validator, then notify subscribers
+    pub fn set_scoped(
+        &mut self,
+        name: &str,
+        value: OptionValue,
+        scope: SetScope,
+    ) -> Result<(), Error> {
+        let def = self.check(name, &value)?;
+        let name = def.name;
+        let buffer_local = def.scope == OptionScope::Buffer;
+        let old = self.get(name).cloned().unwrap_or_else(|| value.clone());
+
+        match scope {
+            SetScope::Both => {
+                self.global.insert(name, value.clone());
+                if buffer_local {
+                    self.local.insert(name, value);
+                }
+            }
+            SetScope::Local if buffer_local => {
+                self.local.insert(name, value);
+            }
+            SetScope::Local | SetScope::Global => {
+                self.global.insert(name, value);
+            }
+        }
+
+        let Some(new) = self.get(name).cloned() else {
+            return Ok(());
+        };
+
+        let change = OptionChange {
+            name,
+            scope,
+            old,
+            new,
+        };
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber(&change);
+        }
+
+        Ok(())
+    }
+
+    /// Register a callback invoked after any option is assigned
+    pub fn subscribe<F>(&mut self, f: F)
+    where
+        F: FnMut(&OptionChange) + 'static,
+    {
+        self.subscribers.push(Box::new(f));
+    }
+
+    /// Apply one argument of a `:set` command, returning text to display if any
+    ///
+    /// Supports `opt`, `noopt`, `invopt`, `opt!`, `opt?`, `opt&`, `opt=val`, `opt:val`,
+    /// `opt+=val`, `opt-=val` and `opt^=val`.
+    pub fn set_arg(&mut self, arg: &str, scope: SetScope) -> Result<Option<String>, Error> {
+        let name_len = arg
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(arg.len());
+        let (name, rest) = arg.split_at(name_len);
+
+        // Boolean prefixes only apply when the full name isn't an option itself
+        let (name, prefix) = match self.def(name) {
+            Some(_) => (name, None),
+            None => match name
+                .strip_prefix("no")
+                .map(|name| (name, "no"))
+                .or_else(|| name.strip_prefix("inv").map(|name| (name, "inv")))
+            {
+                Some((stripped, prefix)) if self.def(stripped).is_some() => {
+                    (stripped, Some(prefix))
+                }
+                _ => (name, None),
+            },
+        };
+
+        let Some(def) = self.def(name) else {
+            return Err(Error::new(518, format!("Unknown option: {}", arg)));
+        };
+        let name = def.name;
+        let default = def.default.clone();
+        let value = match scope {
+            SetScope::Global => self.get_global(name),
+            _ => self.get(name),
+        }
+        .cloned()
+        .unwrap_or_else(|| default.clone());
+
+        let is_bool = matches!(value, OptionValue::Bool(_));
+        if prefix.is_some() && !is_bool {
+            return Err(Error::new(474, format!("Invalid argument: {}", arg)));
+        }
+
+        let operator = rest.find(['=', ':']).map(|i| rest.split_at(i));
+        let new = match (rest, operator) {
+            ("?", _) => return Ok(Some(value.display(name))),
+            ("&" | "&vim" | "&vi", _) => {
+                self.reset(name, scope)?;
+                return Ok(None);
+            }
+            ("!", _) if is_bool => OptionValue::Bool(value != OptionValue::Bool(true)),
+            ("", _) => match (prefix, value) {
+                (Some("no"), _) => OptionValue::Bool(false),
+                (Some(_), OptionValue::Bool(b)) => OptionValue::Bool(!b),
+                (_, OptionValue::Bool(_)) => OptionValue::Bool(true),
+                (_, value) => return Ok(Some(value.display(name))),
+            },
+            (_, Some((op, text))) if !is_bool => {
+                let text = &text[1..];
+                let operand = value.parse(name, text)?;
+                match op {
+                    "" => operand,
+                    "+" | "-" | "^" => combine(&value, operand, op).ok_or_else(|| {
+                        Error::new(521, format!("Number required after =: {}", arg))
+                    })?,
+                    _ => return Err(Error::new(474, format!("Invalid argument: {}", arg))),
+                }
+            }
+            _ => return Err(Error::new(474, format!("Invalid argument: {}", arg))),
+        };
+
+        self.set_scoped(name, new, scope)?;
+        Ok(None)
+    }
+
+    /// Display every option that differs from its default, or every option if `all`
+    pub fn display(&self, all: bool) -> String {
+        let mut lines = vec!["--- Options ---".to_owned()];
+        for def in self.defs.values() {
+            let Some(value) = self.get(def.name) else {
+                continue;
+            };
+            if all || *value != def.default {
+                lines.push(format!("  {}", value.display(def.name)));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Apply `+=`, `-=` or `^=` to a value, or `None` if a number overflows
+fn combine(value: &OptionValue, operand: OptionValue, op: &str) -> Option<OptionValue> {
+    Some(match (value, operand) {
+        (OptionValue::Number(a), OptionValue::Number(b)) => OptionValue::Number(match op {
+            "+" => a.checked_add(b)?,
+            "-" => a.checked_sub(b)?,
+            _ => a.checked_mul(b)?,
+        }),
+        (OptionValue::String(a), OptionValue::String(b)) => OptionValue::String(match op {
+            "+" => format!("{}{}", a, b),
+            "-" => a.replacen(&b, "", 1),
+            _ => format!("{}{}", b, a),
+        }),
+        (OptionValue::List(a), OptionValue::List(b)) => {
+            let mut items = a.clone();
+            items.retain(|item| !b.contains(item));
+            match op {
+                "+" => items.extend(b),
+                "-" => (),
+                _ => items.splice(0..0, b).for_each(drop),
+            }
+            OptionValue::List(items)
+        }
+        (_, operand) => operand,
+    })
+}
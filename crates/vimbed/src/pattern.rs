@@ -0,0 +1,236 @@
+use regex::{Regex, RegexBuilder};
+
+/// Translates a Vim 'magic' pattern into a Rust regex
+///
+/// Supports groups, alternation and multis (`\(\)`, `\|`, `*`, `\+`, `\=`, `\?`, `\{n,m}`),
+/// word boundaries (`\<`, `\>`), the common character classes (`\s`, `\d`, `\w`, `\a`, `\l`,
+/// `\u`, `\x` and their negations) and the `\c` / `\C` case modifiers.
+///
+/// Patterns are matched against a single line, so `\n` never matches.
+pub fn compile(pattern: &str, ignore_case: bool) -> Result<Regex, regex::Error> {
+    let mut ignore_case = ignore_case;
+    let mut out = String::with_capacity(pattern.len());
+
+    let mut chars = pattern.chars().peekable();
+    let mut at_start = true;
+
+    while let Some(c) = chars.next() {
+        let was_start = at_start;
+        at_start = false;
+
+        match c {
+            '\\' => {
+                let Some(e) = chars.next() else {
+                    out.push_str("\\\\");
+                    break;
+                };
+                match e {
+                    '(' => {
+                        out.push('(');
+                        at_start = true;
+                    }
+                    '%' if chars.peek() == Some(&'(') => {
+                        chars.next();
+                        out.push_str("(?:");
+                        at_start = true;
+                    }
+                    ')' => out.push(')'),
+                    '|' => {
+                        out.push('|');
+                        at_start = true;
+                    }
+                    '+' => out.push('+'),
+                    '=' | '?' => out.push('?'),
+                    '{' => {
+                        let lazy = chars.peek() == Some(&'-');
+                        if lazy {
+                            chars.next();
+                        }
+                        let mut bounds = String::new();
+                        for c in chars.by_ref() {
+                            match c {
+                                '\\' => (),
+                                '}' => break,
+                                c => bounds.push(c),
+                            }
+                        }
+                        match bounds.split_once(',') {
+                            Some((min, max)) => {
+                                let min = if min.is_empty() { "0" } else { min };
+                                out.push_str(&format!("{{{},{}}}", min, max));
+                            }
+                            None if bounds.is_empty() => out.push_str("{0,}"),
+                            None => out.push_str(&format!("{{{}}}", bounds)),
+                        }
+                        if lazy {
+                            out.push('?');
+                        }
+                    }
+                    '<' | '>' => out.push_str("\\b"),
+                    's' => out.push_str("[ \\t]"),
+                    'S' => out.push_str("[^ \\t]"),
+                    'd' => out.push_str("[0-9]"),
+                    'D' => out.push_str("[^0-9]"),
+                    'w' => out.push_str("[0-9A-Za-z_]"),
+                    'W' => out.push_str("[^0-9A-Za-z_]"),
+                    'a' => out.push_str("[A-Za-z]"),
+                    'A' => out.push_str("[^A-Za-z]"),
+                    'l' => out.push_str("[a-z]"),
+                    'L' => out.push_str("[^a-z]"),
+                    'u' => out.push_str("[A-Z]"),
+                    'U' => out.push_str("[^A-Z]"),
+                    'x' => out.push_str("[0-9A-Fa-f]"),
+                    'X' => out.push_str("[^0-9A-Fa-f]"),
+                    'n' => out.push_str("\\n"),
+                    't' => out.push_str("\\t"),
+                    'e' => out.push_str("\\x1b"),
+                    'r' => out.push_str("\\r"),
+                    'c' => {
+                        ignore_case = true;
+                        at_start = was_start;
+                    }
+                    'C' => {
+                        ignore_case = false;
+                        at_start = was_start;
+                    }
+                    e => push_literal(&mut out, e),
+                }
+            }
+            '^' if was_start => {
+                out.push('^');
+                at_start = true;
+            }
+            '$' if is_end(&chars) => out.push('$'),
+            '.' | '*' => out.push(c),
+            '[' => push_class(&mut out, &mut chars),
+            c => push_literal(&mut out, c),
+        }
+    }
+
+    RegexBuilder::new(&out)
+        .case_insensitive(ignore_case)
+        .build()
+}
+
+/// Whether the remaining pattern input ends a branch, making `$` an anchor
+fn is_end(chars: &std::iter::Peekable<std::str::Chars>) -> bool {
+    let mut rest = chars.clone();
+    match rest.next() {
+        None => true,
+        Some('\\') => matches!(rest.next(), Some(')') | Some('|')),
+        _ => false,
+    }
+}
+
+fn push_literal(out: &mut String, c: char) {
+    if regex_syntax_char(c) {
+        out.push('\\');
+    }
+    out.push(c);
+}
+
+fn regex_syntax_char(c: char) -> bool {
+    matches!(
+        c,
+        '\\' | '.'
+            | '+'
+            | '*'
+            | '?'
+            | '('
+            | ')'
+            | '|'
+            | '['
+            | ']'
+            | '{'
+            | '}'
+            | '^'
+            | '$'
+            | '#'
+            | '&'
+            | '-'
+            | '~'
+    )
+}
+
+/// Copies a `[...]` collection through, escaping characters the regex crate treats specially
+fn push_class(out: &mut String, chars: &mut std::iter::Peekable<std::str::Chars>) {
+    let rest = chars.clone().collect::<Vec<_>>();
+    let Some(end) = class_end(&rest) else {
+        // Unterminated collection matches a literal '['
+        out.push_str("\\[");
+        return;
+    };
+
+    out.push('[');
+    let mut i = 0;
+    if rest[i] == '^' {
+        out.push('^');
+        i += 1;
+    }
+    while i < end {
+        let c = rest[i];
+        i += 1;
+        match c {
+            '\\' => {
+                let e = rest[i];
+                i += 1;
+                match e {
+                    'e' => out.push_str("\\x1b"),
+                    't' => out.push_str("\\t"),
+                    'n' => out.push_str("\\n"),
+                    'r' => out.push_str("\\r"),
+                    e => {
+                        out.push('\\');
+                        out.push(e);
+                    }
+                }
+            }
+            '[' if rest.get(i) == Some(&':') => {
+                out.push('[');
+                while i < end {
+                    out.push(rest[i]);
+                    i += 1;
+                    if rest[i - 1] == ']' {
+                        break;
+                    }
+                }
+            }
+            '[' | '&' | '~' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push(']');
+
+    for _ in 0..end + 1 {
+        chars.next();
+    }
+}
+
+/// Index of the `]` closing a collection, given the input following its `[`
+fn class_end(chars: &[char]) -> Option<usize> {
+    let mut i = 0;
+    if chars.first() == Some(&'^') {
+        i += 1;
+    }
+    if chars.get(i) == Some(&']') {
+        i += 1;
+    }
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '[' if chars.get(i + 1) == Some(&':') => {
+                i += 2;
+                while i < chars.len() && chars[i] != ']' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            ']' => return Some(i),
+            _ => i += 1,
+        }
+    }
+    None
+}
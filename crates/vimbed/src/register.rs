@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+/// Text stored in a register
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Register {
+    pub text: String,
+    /// Whether the text is a set of whole lines, each terminated by a newline
+    pub linewise: bool,
+}
+
+impl Register {
+    pub fn new(text: String, linewise: bool) -> Self {
+        Register { text, linewise }
+    }
+
+    pub fn linewise(text: String) -> Self {
+        Self::new(text, true)
+    }
+
+    pub fn charwise(text: String) -> Self {
+        Self::new(text, false)
+    }
+}
+
+/// Named, numbered and unnamed registers
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Registers {
+    registers: BTreeMap<char, Register>,
+}
+
+impl Registers {
+    /// Whether `name` can be used in a `"x` prefix or ex command register argument
+    pub fn is_valid(name: char) -> bool {
+        name.is_ascii_alphanumeric() || "\"-_".contains(name)
+    }
+
+    pub fn get(&self, name: char) -> Option<&Register> {
+        self.registers.get(&name.to_ascii_lowercase())
+    }
+
+    /// Write to a named register, or append if the name is uppercase
+    fn set(&mut self, name: char, register: Register) {
+        if name.is_ascii_uppercase() {
+            let name = name.to_ascii_lowercase();
+            match self.registers.get_mut(&name) {
+                Some(existing) => {
+                    if register.linewise && !existing.linewise {
+                        existing.text.push('\n');
+                    }
+                    existing.text.push_str(&register.text);
+                    existing.linewise |= register.linewise;
+                }
+                None => {
+                    self.registers.insert(name, register);
+                }
+            }
+        } else {
+            self.registers.insert(name, register);
+        }
+    }
+
+    /// Store yanked text in `name`, or the unnamed and `0` registers
+    pub fn yank(&mut self, name: Option<char>, register: Register) {
+        let target = match name {
+            Some('_') => return,
+            None | Some('"') => '0',
+            Some(name) => name,
+        };
+        self.set(target, register);
+        self.update_unnamed(target);
+    }
+
+    /// Store recorded keys in `name`, leaving the unnamed register untouched
+    pub fn record(&mut self, name: char, register: Register) {
+        self.set(name, register);
+    }
+
+    /// Store deleted text in `name`, or shift it into the numbered registers
+    pub fn delete(&mut self, name: Option<char>, register: Register) {
+        let target = match name {
+            Some('_') => return,
+            None | Some('"') if register.linewise || register.text.contains('\n') => {
+                let numbered = ['1', '2', '3', '4', '5', '6', '7', '8', '9'];
+                for pair in numbered.windows(2).rev() {
+                    if let Some(r) = self.registers.remove(&pair[0]) {
+                        self.registers.insert(pair[1], r);
+                    }
+                }
+                '1'
+            }
+            None | Some('"') => '-',
+            Some(name) => name,
+        };
+        self.set(target, register);
+        self.update_unnamed(target);
+    }
+
+    /// Point the unnamed register at the last written register
+    fn update_unnamed(&mut self, name: char) {
+        if let Some(register) = self.get(name).cloned() {
+            self.registers.insert('"', register);
+        }
+    }
+}
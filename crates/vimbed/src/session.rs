@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buffer::{Buffer, BufferText, Cursor},
+    buffer_list::{BufferList, ListedBuffer},
+    context::Context,
+    error::Error,
+    ex::substitute::Substitute,
+    mark::LineMarks,
+    mode::Mode,
+    option::{OptionValue, SetScope},
+    register::Registers,
+    tab_page::TabPages,
+    undo::History,
+    window::Windows,
+};
+
+/// Saved state of a buffer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BufferSession {
+    pub text: String,
+    pub cursor: Cursor,
+    pub line_marks: LineMarks,
+    /// Undo history, whose edits apply to `text`
+    pub history: History,
+    /// History state last saved, if undo and redo can still reach it
+    pub saved: Option<u64>,
+    pub modified: bool,
+}
+
+/// Saved state of a listed buffer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListedBufferSession {
+    pub number: usize,
+    pub name: Option<String>,
+    /// Text and editing state, or `None` for the current buffer, saved as the session's `edit`
+    pub buffer: Option<BufferSession>,
+    /// Values of buffer-local options, which for the current buffer are `local_options`
+    pub local_options: BTreeMap<String, OptionValue>,
+}
+
+/// Editor state saved between sessions, as by [`Context::session`]
+///
+/// The command line and search histories are the text of their buffers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// Format of the saved state, which [`Context::restore`] must know
+    pub version: u32,
+    pub mode: Mode,
+    pub edit: BufferSession,
+    pub command: BufferSession,
+    pub search: BufferSession,
+    pub registers: Registers,
+    /// Global values of options changed from their defaults
+    pub options: BTreeMap<String, OptionValue>,
+    /// Values of buffer-local options set locally
+    pub local_options: BTreeMap<String, OptionValue>,
+    pub last_pattern: Option<String>,
+    pub last_substitute: Option<Substitute>,
+    /// Buffer list in order, exactly one of them current
+    pub buffers: Vec<ListedBufferSession>,
+    pub alternate_buffer: Option<usize>,
+    /// Windows of the current tab page
+    pub windows: Windows,
+    pub tab_pages: TabPages,
+}
+
+impl Session {
+    /// Format written by this version of the library
+    pub const VERSION: u32 = 1;
+}
+
+fn option_values<'a>(
+    values: impl Iterator<Item = (&'static str, &'a OptionValue)>,
+) -> BTreeMap<String, OptionValue> {
+    values
+        .map(|(name, value)| (name.to_owned(), value.clone()))
+        .collect()
+}
+
+impl Context<'_> {
+    /// Save the editor state, to restore when the text is reopened
+    ///
+    /// An operator awaiting its motion is saved as normal mode.
+    pub fn session(&self) -> Session {
+        let mode = match self.mode {
+            Mode::OperatorPending { .. } => Mode::Normal,
+            mode => mode,
+        };
+        let buffers = self
+            .buffers
+            .iter()
+            .map(|entry| ListedBufferSession {
+                number: entry.number,
+                name: entry.name.clone(),
+                buffer: entry.buffer.as_ref().map(Buffer::session),
+                local_options: option_values(
+                    entry
+                        .local_options
+                        .iter()
+                        .map(|(name, value)| (*name, value)),
+                ),
+            })
+            .collect();
+        let mut windows = self.windows.clone();
+        if let Some(window) = windows.get_mut(self.windows.current()) {
+            window.cursor = self.buffer_edit.cursor;
+        }
+
+        Session {
+            version: Session::VERSION,
+            mode,
+            edit: self.buffer_edit.session(),
+            command: self.buffer_command.session(),
+            search: self.buffer_search.session(),
+            registers: self.registers.clone(),
+            options: option_values(self.options.changed_globals()),
+            local_options: option_values(self.options.locals()),
+            last_pattern: self.last_pattern.clone(),
+            last_substitute: self.last_substitute.clone(),
+            buffers,
+            alternate_buffer: self.buffers.alternate(),
+            windows,
+            tab_pages: self.tab_pages.clone(),
+        }
+    }
+
+    /// Restore a saved editor state
+    ///
+    /// Options the session names but the context no longer defines or accepts the
+    /// value of are left unchanged, as are options the session doesn't name. A session
+    /// saved in a format this version doesn't know, or whose buffers and windows don't
+    /// match up, is refused, leaving the context as it was.
+    pub fn restore(&mut self, session: Session) -> Result<(), Error> {
+        if session.version != Session::VERSION {
+            return Err(Error::new(
+                474,
+                format!("Unknown session version: {}", session.version),
+            ));
+        }
+        let Some(current) = check_buffers(&session) else {
+            return Err(Error::new(474, "Invalid session"));
+        };
+
+        let buffers = session
+            .buffers
+            .into_iter()
+            .map(|entry| ListedBuffer {
+                number: entry.number,
+                name: entry.name,
+                buffer: entry.buffer.map(|saved| {
+                    let mut buffer = Buffer::from(BufferText::from(String::new()));
+                    buffer.restore(saved);
+                    buffer
+                }),
+                local_options: self.options.checked_locals(entry.local_options),
+            })
+            .collect();
+        self.buffers = BufferList::from_buffers(buffers, current, session.alternate_buffer);
+
+        let area = self.windows.area();
+        self.windows = session.windows;
+        self.tab_pages = session.tab_pages;
+        self.windows.set_area(area);
+        self.tab_pages.set_area(area);
+
+        self.buffer_edit.restore(session.edit);
+        self.buffer_command.restore(session.command);
+        self.buffer_search.restore(session.search);
+        self.registers = session.registers;
+        self.last_pattern = session.last_pattern;
+        self.last_substitute = session.last_substitute;
+
+        for (name, value) in session.options {
+            let _ = self.options.set_scoped(&name, value, SetScope::Global);
+        }
+        for (name, value) in session.local_options {
+            let _ = self.options.set_scoped(&name, value, SetScope::Local);
+        }
+
+        self.set_mode(session.mode);
+        Ok(())
+    }
+}
+
+/// Number of the current buffer, if the buffer list has distinct numbers with exactly one
+/// current, and the windows show listed buffers with the current one in the current window
+fn check_buffers(session: &Session) -> Option<usize> {
+    let mut current = session
+        .buffers
+        .iter()
+        .filter(|entry| entry.buffer.is_none());
+    let current = match (current.next(), current.next()) {
+        (Some(entry), None) => entry.number,
+        _ => return None,
+    };
+
+    let mut numbers = session
+        .buffers
+        .iter()
+        .map(|entry| entry.number)
+        .collect::<Vec<_>>();
+    numbers.sort_unstable();
+    let listed = |number| numbers.binary_search(&number).is_ok();
+    let window = session.windows.get(session.windows.current())?;
+
+    let valid = numbers.windows(2).all(|pair| pair[0] != pair[1])
+        && numbers.first() > Some(&0)
+        && session
+            .alternate_buffer
+            .is_none_or(|number| number != current && listed(number))
+        && window.buffer == current
+        && session.windows.is_consistent(&listed)
+        && session.tab_pages.is_consistent(&listed);
+    valid.then_some(current)
+}
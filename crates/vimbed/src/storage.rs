@@ -0,0 +1,166 @@
+use std::{borrow::Cow, ops::Range};
+
+use ropey::{Rope, RopeSlice};
+
+/// Text held by a buffer, addressed by byte offsets and `\n`-separated lines
+///
+/// The text after the last newline is a line of its own, so empty text has one line.
+/// Offsets passed in are clamped to the text and rounded down to a char boundary.
+pub trait TextStorage {
+    /// Length of the text in bytes
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn line_count(&self) -> usize;
+
+    /// Text of a line, without its newline
+    fn line(&self, row: usize) -> Option<Cow<'_, str>>;
+
+    /// Lines from `row` onward, without their newlines
+    fn lines_from(&self, row: usize) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_>;
+
+    /// Byte offset at which a line starts, or the text length past the last line
+    fn line_offset(&self, row: usize) -> usize;
+
+    /// Line containing a byte offset
+    fn offset_line(&self, offset: usize) -> usize;
+
+    /// Text in a byte range
+    fn slice(&self, range: Range<usize>) -> Cow<'_, str>;
+
+    /// Replace a byte range with `text`
+    fn splice(&mut self, range: Range<usize>, text: &str);
+
+    /// Whether a byte offset lies on a char boundary
+    fn is_char_boundary(&self, offset: usize) -> bool;
+}
+
+/// Round `offset` down to a char boundary of `text`, clamping it to the text
+fn floor_char_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+impl TextStorage for String {
+    fn len(&self) -> usize {
+        self.as_str().len()
+    }
+
+    fn line_count(&self) -> usize {
+        self.matches('\n').count() + 1
+    }
+
+    fn line(&self, row: usize) -> Option<Cow<'_, str>> {
+        self.split('\n').nth(row).map(Cow::Borrowed)
+    }
+
+    fn lines_from(&self, row: usize) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        Box::new(self.split('\n').skip(row).map(Cow::Borrowed))
+    }
+
+    fn line_offset(&self, row: usize) -> usize {
+        if row == 0 {
+            return 0;
+        }
+        self.match_indices('\n')
+            .nth(row - 1)
+            .map_or(self.as_str().len(), |(i, _)| i + 1)
+    }
+
+    fn offset_line(&self, offset: usize) -> usize {
+        let offset = floor_char_boundary(self, offset);
+        self[..offset].matches('\n').count()
+    }
+
+    fn slice(&self, range: Range<usize>) -> Cow<'_, str> {
+        let start = floor_char_boundary(self, range.start);
+        let end = floor_char_boundary(self, range.end).max(start);
+        Cow::Borrowed(&self[start..end])
+    }
+
+    fn splice(&mut self, range: Range<usize>, text: &str) {
+        let start = floor_char_boundary(self, range.start);
+        let end = floor_char_boundary(self, range.end).max(start);
+        self.replace_range(start..end, text);
+    }
+
+    fn is_char_boundary(&self, offset: usize) -> bool {
+        self.as_str().is_char_boundary(offset)
+    }
+}
+
+/// Borrow a rope slice as a string, copying it only if it spans chunks
+fn slice_str(slice: RopeSlice<'_>) -> Cow<'_, str> {
+    match slice.as_str() {
+        Some(text) => Cow::Borrowed(text),
+        None => Cow::Owned(slice.to_string()),
+    }
+}
+
+/// Strip the newline ending a line of a rope
+fn rope_line(line: RopeSlice<'_>) -> Cow<'_, str> {
+    let len = line.len_bytes();
+    if len > 0 && line.byte(len - 1) == b'\n' {
+        slice_str(line.byte_slice(..len - 1))
+    } else {
+        slice_str(line)
+    }
+}
+
+impl TextStorage for Rope {
+    fn len(&self) -> usize {
+        self.len_bytes()
+    }
+
+    fn line_count(&self) -> usize {
+        self.len_lines()
+    }
+
+    fn line(&self, row: usize) -> Option<Cow<'_, str>> {
+        (row < self.len_lines()).then(|| rope_line(self.line(row)))
+    }
+
+    fn lines_from(&self, row: usize) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        let row = row.min(self.len_lines());
+        Box::new(self.lines_at(row).map(rope_line))
+    }
+
+    fn line_offset(&self, row: usize) -> usize {
+        if row >= self.len_lines() {
+            self.len_bytes()
+        } else {
+            self.line_to_byte(row)
+        }
+    }
+
+    fn offset_line(&self, offset: usize) -> usize {
+        self.byte_to_line(offset.min(self.len_bytes()))
+    }
+
+    fn slice(&self, range: Range<usize>) -> Cow<'_, str> {
+        let start = self.byte_to_char(range.start.min(self.len_bytes()));
+        let end = self
+            .byte_to_char(range.end.min(self.len_bytes()))
+            .max(start);
+        slice_str(self.slice(start..end))
+    }
+
+    fn splice(&mut self, range: Range<usize>, text: &str) {
+        let start = self.byte_to_char(range.start.min(self.len_bytes()));
+        let end = self
+            .byte_to_char(range.end.min(self.len_bytes()))
+            .max(start);
+        self.remove(start..end);
+        self.insert(start, text);
+    }
+
+    fn is_char_boundary(&self, offset: usize) -> bool {
+        offset <= self.len_bytes() && self.char_to_byte(self.byte_to_char(offset)) == offset
+    }
+}
@@ -0,0 +1,114 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{grapheme, option::Options};
+
+/// Tab and indent settings taken from the buffer's options
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TabSettings {
+    /// 'tabstop': Columns a tab character advances to the next multiple of
+    pub tab_stop: usize,
+    /// 'softtabstop': Columns `<Tab>` and `<BS>` work in, 0 to use 'tabstop',
+    /// negative to use 'shiftwidth'
+    pub soft_tab_stop: i64,
+    /// 'shiftwidth': Columns per indent level, 0 to use 'tabstop'
+    pub shift_width: usize,
+    /// 'expandtab': Whether indent is inserted as spaces
+    pub expand_tab: bool,
+}
+
+impl Default for TabSettings {
+    fn default() -> Self {
+        TabSettings {
+            tab_stop: 8,
+            soft_tab_stop: 0,
+            shift_width: 8,
+            expand_tab: false,
+        }
+    }
+}
+
+impl From<&Options> for TabSettings {
+    fn from(options: &Options) -> Self {
+        let default = TabSettings::default();
+        TabSettings {
+            tab_stop: options
+                .number("tabstop")
+                .map(|n| n.max(1) as usize)
+                .unwrap_or(default.tab_stop),
+            soft_tab_stop: options
+                .number("softtabstop")
+                .unwrap_or(default.soft_tab_stop),
+            shift_width: options
+                .number("shiftwidth")
+                .map(|n| n.max(0) as usize)
+                .unwrap_or(default.shift_width),
+            expand_tab: options.bool("expandtab").unwrap_or(default.expand_tab),
+        }
+    }
+}
+
+impl TabSettings {
+    /// Effective columns per indent level
+    pub fn shift(&self) -> usize {
+        match self.shift_width {
+            0 => self.tab_stop,
+            width => width,
+        }
+    }
+
+    /// Effective columns per `<Tab>` or `<BS>` in insert mode
+    pub fn soft_tab(&self) -> usize {
+        match self.soft_tab_stop {
+            0 => self.tab_stop,
+            n if n < 0 => self.shift(),
+            n => n as usize,
+        }
+    }
+
+    /// Display width of `grapheme` when it starts at virtual column `column`
+    pub fn width(&self, grapheme: &str, column: usize) -> usize {
+        match grapheme {
+            "\t" => self.tab_stop - column % self.tab_stop,
+            g => grapheme::width(g),
+        }
+    }
+
+    /// Virtual column reached after displaying `text` from virtual column `column`
+    pub fn advance(&self, text: &str, column: usize) -> usize {
+        text.graphemes(true)
+            .fold(column, |column, g| column + self.width(g, column))
+    }
+
+    /// `text` with each tab replaced by the spaces it displays as
+    pub fn expand(&self, text: &str) -> String {
+        let mut out = String::new();
+        let mut column = 0;
+        for g in text.graphemes(true) {
+            let width = self.width(g, column);
+            match g {
+                "\t" => out.push_str(&" ".repeat(width)),
+                g => out.push_str(g),
+            }
+            column += width;
+        }
+        out
+    }
+
+    /// Whitespace spanning virtual columns `from` to `to`, using tabs unless 'expandtab'
+    pub fn indent(&self, from: usize, to: usize) -> String {
+        let mut out = String::new();
+        let mut column = from;
+        if !self.expand_tab {
+            loop {
+                let next = column + self.width("\t", column);
+                if next > to {
+                    break;
+                }
+                out.push('\t');
+                column = next;
+            }
+        }
+        out.push_str(&" ".repeat(to.saturating_sub(column)));
+        out
+    }
+}
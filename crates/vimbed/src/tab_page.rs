@@ -0,0 +1,168 @@
+use std::fmt::Display;
+
+use crate::window::{Rect, Windows};
+
+/// Tab pages, each with its own window layout, numbered from one in the order shown
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TabPages {
+    /// Layout of each tab page, held by the context's `windows` while the page is current
+    pages: Vec<Option<Windows>>,
+    current: usize,
+}
+
+impl Default for TabPages {
+    /// A single tab page, which is current
+    fn default() -> Self {
+        TabPages {
+            pages: vec![None],
+            current: 0,
+        }
+    }
+}
+
+impl TabPages {
+    /// Number of the tab page being edited in
+    pub fn current(&self) -> usize {
+        self.current + 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    /// Window layout of a tab page other than the current one
+    pub fn get(&self, number: usize) -> Option<&Windows> {
+        self.pages.get(number.checked_sub(1)?)?.as_ref()
+    }
+
+    pub(crate) fn get_mut(&mut self, number: usize) -> Option<&mut Windows> {
+        self.pages.get_mut(number.checked_sub(1)?)?.as_mut()
+    }
+
+    /// Number of the tab page `count` places after `number`, or before it if not `forward`,
+    /// wrapping around
+    pub fn step(&self, number: usize, count: usize, forward: bool) -> usize {
+        let len = self.pages.len();
+        let index = number.saturating_sub(1) % len;
+        let offset = count % len;
+        if forward {
+            (index + offset) % len + 1
+        } else {
+            (index + len - offset) % len + 1
+        }
+    }
+
+    /// Add a tab page after the current one, returning its number
+    pub(crate) fn add(&mut self, windows: Windows) -> usize {
+        self.pages.insert(self.current + 1, Some(windows));
+        self.current + 2
+    }
+
+    /// Make `number` current, swapping its layout with the one given, which is stored as
+    /// the previous page's, or return false if there is no such page
+    pub(crate) fn switch(&mut self, number: usize, windows: &mut Windows) -> bool {
+        let Some(next) = self.get_mut(number).map(std::mem::take) else {
+            return false;
+        };
+        let previous = std::mem::replace(windows, next);
+        self.pages[self.current] = Some(previous);
+        self.pages[number - 1] = None;
+        self.current = number - 1;
+        true
+    }
+
+    /// Remove a tab page other than the current one
+    pub(crate) fn remove(&mut self, number: usize) -> Option<Windows> {
+        if number == self.current() || number == 0 || number > self.pages.len() {
+            return None;
+        }
+        if number <= self.current {
+            self.current -= 1;
+        }
+        self.pages.remove(number - 1)
+    }
+
+    /// Move the current tab page to `index`, counting from zero among the others
+    pub(crate) fn move_current(&mut self, index: usize) {
+        let page = self.pages.remove(self.current);
+        self.current = index.min(self.pages.len());
+        self.pages.insert(self.current, page);
+    }
+
+    /// Whether only the current page's layout is held elsewhere, and the others are
+    /// consistent, as restored tab pages must be
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_consistent(&self, listed: &impl Fn(usize) -> bool) -> bool {
+        self.current < self.pages.len()
+            && self
+                .pages
+                .iter()
+                .enumerate()
+                .all(|(index, page)| match page {
+                    Some(windows) => index != self.current && windows.is_consistent(listed),
+                    None => index == self.current,
+                })
+    }
+
+    /// Share a new area between the windows of the pages that aren't current
+    pub(crate) fn set_area(&mut self, area: Rect) {
+        for windows in self.pages.iter_mut().flatten() {
+            windows.set_area(area);
+        }
+    }
+
+    /// Close the windows showing a buffer in the pages that aren't current, removing pages
+    /// left without any
+    pub(crate) fn close_buffer(&mut self, buffer: usize) {
+        let mut number = 1;
+        while number <= self.pages.len() {
+            let emptied = match self.get_mut(number) {
+                Some(windows) => {
+                    let showing = windows.iter().filter(|window| window.buffer == buffer);
+                    let ids = showing.map(|window| window.id).collect::<Vec<_>>();
+                    let emptied = ids.len() == windows.len();
+                    for id in ids.into_iter().filter(|_| !emptied) {
+                        windows.close(id);
+                    }
+                    emptied
+                }
+                None => false,
+            };
+            if emptied {
+                self.remove(number);
+            } else {
+                number += 1;
+            }
+        }
+    }
+}
+
+/// Label of a tab page in the tabline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabLabel {
+    pub number: usize,
+    pub current: bool,
+    /// Name of the buffer in the page's current window
+    pub name: String,
+    pub windows: usize,
+    /// Whether any window in the page shows a modified buffer
+    pub modified: bool,
+}
+
+impl Display for TabLabel {
+    /// Label as Vim draws it, with the window count if more than one and `+` if modified
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.windows > 1, self.modified) {
+            (true, true) => write!(f, "{}+ ", self.windows)?,
+            (true, false) => write!(f, "{} ", self.windows)?,
+            (false, true) => f.write_str("+ ")?,
+            (false, false) => (),
+        }
+        f.write_str(&self.name)
+    }
+}
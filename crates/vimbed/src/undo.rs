@@ -0,0 +1,108 @@
+use crate::buffer::Cursor;
+
+/// Single text replacement, at a byte offset into the text
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Edit {
+    pub offset: usize,
+    pub removed: String,
+    pub inserted: String,
+}
+
+/// Set of edits undone and redone as one change
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UndoGroup {
+    /// Identifies the group to change listeners, unique within its buffer
+    pub id: u64,
+    pub edits: Vec<Edit>,
+    /// Cursor position before the first edit, restored on undo
+    pub cursor: Cursor,
+}
+
+/// Linear undo / redo history
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct History {
+    undo: Vec<UndoGroup>,
+    redo: Vec<UndoGroup>,
+    pending: Option<UndoGroup>,
+    next_id: u64,
+}
+
+impl History {
+    /// Add an edit to the pending change, returning the id of its group
+    pub fn record(&mut self, edit: Edit, cursor: Cursor) -> u64 {
+        let id = match &self.pending {
+            Some(group) => group.id,
+            None => self.new_id(),
+        };
+        self.pending
+            .get_or_insert_with(|| UndoGroup {
+                id,
+                edits: vec![],
+                cursor,
+            })
+            .edits
+            .push(edit);
+        id
+    }
+
+    /// Allocate a group id for a change made outside the history
+    pub fn new_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Forget all changes, without reusing their ids
+    pub fn clear(&mut self) {
+        *self = History {
+            next_id: self.next_id,
+            ..Default::default()
+        };
+    }
+
+    /// Id of the change the text was last brought to by an edit, undo or redo, or zero
+    /// if every change has been undone
+    pub fn state(&self) -> u64 {
+        match &self.pending {
+            Some(group) => group.id,
+            None => self.undo.last().map_or(0, |group| group.id),
+        }
+    }
+
+    /// Close the pending change, making it the next to be undone
+    pub fn commit(&mut self) {
+        if let Some(group) = self.pending.take() {
+            self.undo.push(group);
+            self.redo.clear();
+        }
+    }
+
+    /// Reopen the last change so further edits undo with it, returning false if
+    /// a change was already pending or there is none
+    pub fn join(&mut self) -> bool {
+        if self.pending.is_some() {
+            return false;
+        }
+        self.pending = self.undo.pop();
+        self.pending.is_some()
+    }
+
+    pub fn pop_undo(&mut self) -> Option<UndoGroup> {
+        self.commit();
+        self.undo.pop()
+    }
+
+    pub fn push_undo(&mut self, group: UndoGroup) {
+        self.undo.push(group);
+    }
+
+    pub fn pop_redo(&mut self) -> Option<UndoGroup> {
+        self.redo.pop()
+    }
+
+    pub fn push_redo(&mut self, group: UndoGroup) {
+        self.redo.push(group);
+    }
+}
@@ -0,0 +1,560 @@
+use crate::buffer::Cursor;
+
+/// Area of the screen, in cells
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    /// Extent along the axis a split lays its children out on
+    fn extent(&self, direction: SplitDirection) -> usize {
+        match direction {
+            SplitDirection::Horizontal => self.height,
+            SplitDirection::Vertical => self.width,
+        }
+    }
+}
+
+/// How a split lays out its children
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SplitDirection {
+    /// Stacked top to bottom, as made by `:split`
+    Horizontal,
+    /// Side by side with separators between them, as made by `:vsplit`
+    Vertical,
+}
+
+impl SplitDirection {
+    /// Cells between neighbouring children
+    fn gap(self) -> usize {
+        match self {
+            SplitDirection::Horizontal => 0,
+            SplitDirection::Vertical => 1,
+        }
+    }
+}
+
+/// Direction to look for a neighbouring window in, as with `Ctrl-W h/j/k/l`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Neighbour {
+    Left,
+    Down,
+    Up,
+    Right,
+}
+
+/// View of a listed buffer, which other windows may also show
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Window {
+    pub id: usize,
+    /// Number of the listed buffer shown
+    pub buffer: usize,
+    /// Cursor in the buffer, held by the context's `buffer_edit` while the window is current
+    pub cursor: Cursor,
+    /// First line shown
+    pub top: usize,
+}
+
+/// Node of the layout tree, with the area it was last given
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Layout {
+    Window(usize, Rect),
+    Split(SplitDirection, Vec<Layout>, Rect),
+}
+
+impl Layout {
+    fn rect(&self) -> Rect {
+        match self {
+            Layout::Window(_, rect) | Layout::Split(_, _, rect) => *rect,
+        }
+    }
+
+    fn contains(&self, id: usize) -> bool {
+        match self {
+            Layout::Window(window, _) => *window == id,
+            Layout::Split(_, children, _) => children.iter().any(|child| child.contains(id)),
+        }
+    }
+
+    /// Whether every split has children
+    #[cfg(feature = "serde")]
+    fn is_valid(&self) -> bool {
+        match self {
+            Layout::Window(..) => true,
+            Layout::Split(_, children, _) => {
+                !children.is_empty() && children.iter().all(Layout::is_valid)
+            }
+        }
+    }
+
+    fn ids(&self, ids: &mut Vec<usize>) {
+        match self {
+            Layout::Window(id, _) => ids.push(*id),
+            Layout::Split(_, children, _) => children.iter().for_each(|child| child.ids(ids)),
+        }
+    }
+
+    /// Smallest extent the node fits in along `direction`, each window needing a line of
+    /// text and its status line
+    fn min_extent(&self, direction: SplitDirection) -> usize {
+        match self {
+            Layout::Window(..) => match direction {
+                SplitDirection::Horizontal => 2,
+                SplitDirection::Vertical => 1,
+            },
+            Layout::Split(split, children, _) => {
+                let extents = children.iter().map(|child| child.min_extent(direction));
+                if *split == direction {
+                    extents.sum::<usize>() + direction.gap() * (children.len() - 1)
+                } else {
+                    extents.max().unwrap_or_default()
+                }
+            }
+        }
+    }
+
+    /// Give the node `rect`, sharing it between children in proportion to their current
+    /// extents, or equally if `equal`
+    fn place(&mut self, rect: Rect, equal: bool) {
+        match self {
+            Layout::Window(_, area) => *area = rect,
+            Layout::Split(direction, children, area) => {
+                *area = rect;
+                let direction = *direction;
+                let weights = children
+                    .iter()
+                    .map(|child| match equal {
+                        true => 1,
+                        false => child.rect().extent(direction),
+                    })
+                    .collect::<Vec<_>>();
+                let total = weights.iter().sum::<usize>();
+                let gaps = direction.gap() * (children.len() - 1);
+                let available = rect.extent(direction).saturating_sub(gaps);
+
+                let mut position = 0;
+                let mut used = 0;
+                let last = children.len() - 1;
+                for (i, child) in children.iter_mut().enumerate() {
+                    let extent = match (i == last, total) {
+                        (true, _) => available.saturating_sub(used),
+                        (false, 0) => available / weights.len(),
+                        (false, total) => weights[i] * available / total,
+                    };
+                    let child_rect = match direction {
+                        SplitDirection::Horizontal => Rect {
+                            y: rect.y + position,
+                            height: extent,
+                            ..rect
+                        },
+                        SplitDirection::Vertical => Rect {
+                            x: rect.x + position,
+                            width: extent,
+                            ..rect
+                        },
+                    };
+                    child.place(child_rect, equal);
+                    used += extent;
+                    position += extent + direction.gap();
+                }
+            }
+        }
+    }
+
+    /// Split the window `id` in `direction`, putting `new` before it
+    fn split(&mut self, id: usize, new: usize, direction: SplitDirection) -> bool {
+        match self {
+            Layout::Window(window, rect) if *window == id => {
+                let rect = *rect;
+                *self = Layout::Split(
+                    direction,
+                    vec![Layout::Window(new, rect), Layout::Window(id, rect)],
+                    rect,
+                );
+                true
+            }
+            Layout::Window(..) => false,
+            Layout::Split(split, children, _) => {
+                let index = children
+                    .iter()
+                    .position(|child| matches!(child, Layout::Window(window, _) if *window == id));
+                match index {
+                    Some(index) if *split == direction => {
+                        let rect = children[index].rect();
+                        children.insert(index, Layout::Window(new, rect));
+                        true
+                    }
+                    _ => children
+                        .iter_mut()
+                        .any(|child| child.split(id, new, direction)),
+                }
+            }
+        }
+    }
+
+    /// Remove the window `id`, collapsing splits left with a single child into their parent
+    fn remove(&mut self, id: usize) {
+        let Layout::Split(direction, children, _) = self else {
+            return;
+        };
+        children.retain(|child| !matches!(child, Layout::Window(window, _) if *window == id));
+        for child in std::mem::take(children) {
+            let mut child = child;
+            child.remove(id);
+            match child {
+                Layout::Split(split, grandchildren, _) if split == *direction => {
+                    children.extend(grandchildren);
+                }
+                child => children.push(child),
+            }
+        }
+        if children.len() == 1 {
+            *self = children.remove(0);
+        }
+    }
+
+    /// Grow the window `id` by `delta` cells along `direction`, or shrink it if negative,
+    /// taking space from or giving it to the children of the nearest such split beside it
+    fn grow(&mut self, id: usize, direction: SplitDirection, delta: isize) -> bool {
+        let Layout::Split(split, children, rect) = self else {
+            return false;
+        };
+        let Some(index) = children.iter().position(|child| child.contains(id)) else {
+            return false;
+        };
+        if children[index].grow(id, direction, delta) {
+            return true;
+        }
+        if *split != direction {
+            return false;
+        }
+
+        let mut extents = children
+            .iter()
+            .map(|child| child.rect().extent(direction))
+            .collect::<Vec<_>>();
+        let mins = children
+            .iter()
+            .map(|child| child.min_extent(direction))
+            .collect::<Vec<_>>();
+
+        // Nearer children after the window give or take space first, then those before it
+        let others = (index + 1..children.len()).chain((0..index).rev());
+        if delta > 0 {
+            let mut wanted = delta as usize;
+            for i in others {
+                let taken = extents[i].saturating_sub(mins[i]).min(wanted);
+                extents[i] -= taken;
+                extents[index] += taken;
+                wanted -= taken;
+            }
+        } else {
+            let given = extents[index]
+                .saturating_sub(mins[index])
+                .min(delta.unsigned_abs());
+            extents[index] -= given;
+            if let Some(i) = others.take(1).next() {
+                extents[i] += given;
+            }
+        }
+
+        for (child, extent) in children.iter_mut().zip(extents) {
+            let mut child_rect = child.rect();
+            match direction {
+                SplitDirection::Horizontal => child_rect.height = extent,
+                SplitDirection::Vertical => child_rect.width = extent,
+            }
+            child.place(child_rect, false);
+        }
+        let rect = *rect;
+        self.place(rect, false);
+        true
+    }
+
+    fn rects(&self, rects: &mut Vec<(usize, Rect)>) {
+        match self {
+            Layout::Window(id, rect) => rects.push((*id, *rect)),
+            Layout::Split(_, children, _) => {
+                children.iter().for_each(|child| child.rects(rects));
+            }
+        }
+    }
+
+    fn separators(&self, separators: &mut Vec<Rect>) {
+        let Layout::Split(direction, children, _) = self else {
+            return;
+        };
+        for (i, child) in children.iter().enumerate() {
+            let rect = child.rect();
+            if *direction == SplitDirection::Vertical && i + 1 < children.len() {
+                separators.push(Rect {
+                    x: rect.x + rect.width,
+                    width: 1,
+                    ..rect
+                });
+            }
+            child.separators(separators);
+        }
+    }
+}
+
+/// Windows laid out on the screen, each showing a listed buffer
+///
+/// Every window's area ends in its status line, with the text shown in the rows above.
+/// Windows are equalized after each split and close, as with Vim's default 'equalalways'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Windows {
+    windows: Vec<Window>,
+    layout: Layout,
+    area: Rect,
+    current: usize,
+    next_id: usize,
+}
+
+impl Default for Windows {
+    /// A single window showing buffer 1 on an 80 by 24 screen
+    fn default() -> Self {
+        Windows::new(
+            1,
+            Rect {
+                x: 0,
+                y: 0,
+                width: 80,
+                height: 24,
+            },
+        )
+    }
+}
+
+impl Windows {
+    /// A single window showing `buffer` in `area`
+    pub(crate) fn new(buffer: usize, area: Rect) -> Self {
+        Windows {
+            windows: vec![Window {
+                id: 1,
+                buffer,
+                cursor: Default::default(),
+                top: 0,
+            }],
+            layout: Layout::Window(1, area),
+            area,
+            current: 1,
+            next_id: 2,
+        }
+    }
+
+    /// Id of the window being edited in
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Window> {
+        self.windows.iter().find(|window| window.id == id)
+    }
+
+    pub(crate) fn get_mut(&mut self, id: usize) -> Option<&mut Window> {
+        self.windows.iter_mut().find(|window| window.id == id)
+    }
+
+    /// Ids of the windows from the top left to the bottom right, as numbered by `{N}Ctrl-W w`
+    pub fn ids(&self) -> Vec<usize> {
+        let mut ids = vec![];
+        self.layout.ids(&mut ids);
+        ids
+    }
+
+    /// Windows from the top left to the bottom right
+    pub fn iter(&self) -> impl Iterator<Item = &Window> {
+        self.ids().into_iter().filter_map(|id| self.get(id))
+    }
+
+    /// Area shared by all windows
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    /// Area of each window from the top left to the bottom right, including its status line
+    pub fn rects(&self) -> Vec<(usize, Rect)> {
+        let mut rects = vec![];
+        self.layout.rects(&mut rects);
+        rects
+    }
+
+    /// Area of a window, including its status line
+    pub fn rect(&self, id: usize) -> Option<Rect> {
+        self.rects()
+            .into_iter()
+            .find_map(|(window, rect)| (window == id).then_some(rect))
+    }
+
+    /// Columns drawn between side by side windows
+    pub fn separators(&self) -> Vec<Rect> {
+        let mut separators = vec![];
+        self.layout.separators(&mut separators);
+        separators
+    }
+
+    /// Id of the window `count` places after `id` in layout order, wrapping around
+    pub fn step(&self, id: usize, count: usize, forward: bool) -> usize {
+        let ids = self.ids();
+        let len = ids.len();
+        let index = ids.iter().position(|&i| i == id).unwrap_or_default();
+        let offset = count % len;
+        if forward {
+            ids[(index + offset) % len]
+        } else {
+            ids[(index + len - offset) % len]
+        }
+    }
+
+    /// Id of the window beside `id` in `direction`, nearest the screen cell `(x, y)` of its cursor
+    pub fn neighbour(
+        &self,
+        id: usize,
+        direction: Neighbour,
+        (x, y): (usize, usize),
+    ) -> Option<usize> {
+        let rect = self.rect(id)?;
+        let candidates = self
+            .rects()
+            .into_iter()
+            .filter(|(_, other)| match direction {
+                Neighbour::Left => other.x + other.width + 1 == rect.x,
+                Neighbour::Right => rect.x + rect.width + 1 == other.x,
+                Neighbour::Up => other.y + other.height == rect.y,
+                Neighbour::Down => rect.y + rect.height == other.y,
+            })
+            .collect::<Vec<_>>();
+        let beside = |other: &Rect| match direction {
+            Neighbour::Left | Neighbour::Right => other.y <= y && y < other.y + other.height,
+            Neighbour::Up | Neighbour::Down => other.x <= x && x < other.x + other.width,
+        };
+        candidates
+            .iter()
+            .find(|(_, other)| beside(other))
+            .or_else(|| candidates.first())
+            .map(|(id, _)| *id)
+    }
+
+    /// Number of text lines shown in a window, above its status line
+    pub fn text_height(&self, id: usize) -> usize {
+        self.rect(id)
+            .map_or(0, |rect| rect.height.saturating_sub(1))
+    }
+
+    pub(crate) fn set_current(&mut self, id: usize) {
+        if self.get(id).is_some() {
+            self.current = id;
+        }
+    }
+
+    /// Whether the layout holds each window once, the current one among them, and each
+    /// window shows a buffer that is `listed`, as restored windows must
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_consistent(&self, listed: &impl Fn(usize) -> bool) -> bool {
+        let mut ids = self.ids();
+        ids.sort_unstable();
+        let mut windows = self
+            .windows
+            .iter()
+            .map(|window| window.id)
+            .collect::<Vec<_>>();
+        windows.sort_unstable();
+
+        self.layout.is_valid()
+            && ids == windows
+            && ids.windows(2).all(|pair| pair[0] != pair[1])
+            && ids.contains(&self.current)
+            && ids.iter().all(|&id| id < self.next_id)
+            && self.windows.iter().all(|window| listed(window.buffer))
+    }
+
+    /// Share a new area between the windows, keeping their proportions
+    pub(crate) fn set_area(&mut self, area: Rect) {
+        self.area = area;
+        self.layout.place(area, false);
+    }
+
+    /// Split the current window in `direction`, returning the id of the new window before it,
+    /// which shows the same buffer, or `None` if there isn't room
+    pub(crate) fn split(&mut self, direction: SplitDirection) -> Option<usize> {
+        let current = self.current;
+        let window = self.get(current)?.clone();
+        let id = self.next_id;
+
+        let mut layout = self.layout.clone();
+        layout.split(current, id, direction);
+        if layout.min_extent(SplitDirection::Horizontal) > self.area.height
+            || layout.min_extent(SplitDirection::Vertical) > self.area.width
+        {
+            return None;
+        }
+
+        self.next_id += 1;
+        self.layout = layout;
+        self.windows.push(Window { id, ..window });
+        self.equalize();
+        Some(id)
+    }
+
+    /// Remove a window other than the last one, returning it
+    ///
+    /// If it was current, the window before it becomes current, or the one after it if it
+    /// was first.
+    pub(crate) fn close(&mut self, id: usize) -> Option<Window> {
+        if self.windows.len() == 1 {
+            return None;
+        }
+        let index = self.windows.iter().position(|window| window.id == id)?;
+        if id == self.current {
+            self.current = self.step(id, 1, false);
+            if self.ids().first() == Some(&id) {
+                self.current = self.step(id, 1, true);
+            }
+        }
+        self.layout.remove(id);
+        self.equalize();
+        Some(self.windows.remove(index))
+    }
+
+    /// Give all windows the same size, as with `Ctrl-W =`
+    pub(crate) fn equalize(&mut self) {
+        self.layout.place(self.area, true);
+    }
+
+    /// Make the current window taller by `delta` lines, or shorter if negative
+    pub(crate) fn grow(&mut self, delta: isize) {
+        let current = self.current;
+        self.layout.grow(current, SplitDirection::Horizontal, delta);
+    }
+
+    /// Scroll the current window the least needed to show `row`
+    pub(crate) fn scroll_to(&mut self, row: usize) {
+        let height = self.text_height(self.current).max(1);
+        let current = self.current;
+        if let Some(window) = self.get_mut(current) {
+            if row < window.top {
+                window.top = row;
+            } else if row >= window.top + height {
+                window.top = row + 1 - height;
+            }
+        }
+    }
+}
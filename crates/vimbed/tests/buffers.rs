@@ -0,0 +1,69 @@
+use proptest::prelude::*;
+
+use vimbed::{context::Context, storage::TextStorage};
+
+/// Keys switching to another buffer
+const SWITCH: [&str; 6] = [
+    ":bn<CR>", ":bp<CR>", ":b 2<CR>", ":b#<CR>", "<C-^>", "3<C-^>",
+];
+
+/// Keys editing the current buffer, or switching to another
+fn keys() -> impl Strategy<Value = Vec<&'static str>> {
+    let key = prop_oneof![
+        Just("j"),
+        Just("w"),
+        Just("x"),
+        Just("dd"),
+        Just("p"),
+        Just("u"),
+        Just("<C-r>"),
+        Just("ix\n日<Esc>"),
+        Just(":%s/a/bb/g<CR>"),
+        Just(":bn<CR>"),
+        Just(":bp<CR>"),
+        Just(":b 2<CR>"),
+        Just(":b#<CR>"),
+        Just("<C-^>"),
+        Just("3<C-^>"),
+    ];
+    prop::collection::vec(key, 0..32)
+}
+
+proptest! {
+    /// Each buffer keeps its own text, cursor and undo history, so edits in a context
+    /// switching between them match those in separate contexts
+    #[test]
+    fn buffers_edit_independently(
+        texts in prop::collection::vec("[a-c \n]{0,16}", 3),
+        keys in keys(),
+    ) {
+        let mut ctx = Context::new().with_text(texts[0].clone());
+        ctx.add_buffer(texts[1].clone());
+        ctx.add_buffer(texts[2].clone());
+        ctx.ex("set hidden");
+
+        let mut models = texts.iter().map(|text| Context::new().with_text(text.clone())).collect::<Vec<_>>();
+        for key in keys {
+            let before = ctx.buffers.current();
+            ctx.input_keys(key);
+            let after = ctx.buffers.current();
+
+            if SWITCH.contains(&key) {
+                continue;
+            }
+            prop_assert_eq!(before, after);
+
+            // Registers are shared, so text yanked in one buffer can be put in another
+            let model = &mut models[after - 1];
+            model.registers = ctx.registers.clone();
+            model.input_keys(key);
+            ctx.registers = model.registers.clone();
+        }
+
+        for (i, model) in models.iter().enumerate() {
+            let buffer = ctx.listed_buffer(i + 1).unwrap();
+            prop_assert_eq!(buffer.text.slice(0..buffer.text.len()), model.text());
+            prop_assert_eq!(buffer.cursor, model.buffer_edit.cursor);
+        }
+    }
+}
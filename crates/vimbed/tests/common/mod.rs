@@ -0,0 +1,21 @@
+//! Helpers shared by the integration tests
+//!
+//! Each test crate uses only some of them.
+#![allow(dead_code)]
+
+use vimbed::context::Context;
+
+/// Text after typing `keys` in `text`
+pub fn run(text: &str, keys: &str) -> String {
+    let mut ctx = Context::new().with_text(text);
+    ctx.input_keys(keys);
+    ctx.text().into_owned()
+}
+
+/// Messages shown after typing `keys`
+pub fn messages(ctx: &mut Context, keys: &str) -> Vec<String> {
+    ctx.input_keys(keys);
+    ctx.drain_messages()
+        .map(|message| message.to_string())
+        .collect()
+}
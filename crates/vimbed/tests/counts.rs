@@ -0,0 +1,88 @@
+use vimbed::context::Context;
+
+/// Count too large to fit a `usize`, which saturates
+const HUGE: &str = "99999999999999999999";
+
+#[test]
+fn execute_stops_when_register_does_nothing() {
+    let mut ctx = Context::new().with_text("abc\ndef");
+    ctx.input_keys("qqxq");
+    ctx.input_keys(&format!("{}@q", HUGE));
+    assert_eq!(ctx.text(), "\ndef");
+
+    ctx.input_keys(&format!("{}@@", HUGE));
+    assert_eq!(ctx.text(), "\ndef");
+}
+
+#[test]
+fn execute_stops_at_error() {
+    let mut ctx = Context::new().with_text("abc");
+    ctx.input_keys(&format!("{}@@", HUGE));
+    let errors = ctx.messages.drain().filter(|message| message.is_error());
+    assert_eq!(errors.count(), 1);
+}
+
+#[test]
+fn execute_repeats_motions() {
+    let text = (0..10).map(|i| format!("{}\n", i)).collect::<String>();
+    let mut ctx = Context::new().with_text(text);
+    ctx.input_keys("qqjq3@q");
+    assert_eq!(ctx.buffer_edit.cursor.row, 4);
+}
+
+#[test]
+fn substitute_line_takes_count() {
+    let mut ctx = Context::new().with_text("a\n  b\nc\nd");
+    ctx.input_keys("j2Sx<Esc>");
+    assert_eq!(ctx.text(), "a\n  x\nd");
+    ctx.input_keys("u");
+    assert_eq!(ctx.text(), "a\n  b\nc\nd");
+    ctx.input_keys(&format!("{}Sy<Esc>", HUGE));
+    assert_eq!(ctx.text(), "a\n  y");
+}
+
+#[test]
+fn operator_and_motion_counts_multiply() {
+    let mut ctx = Context::new().with_text("a b c d e f g h");
+    ctx.input_keys("2d3w");
+    assert_eq!(ctx.text(), "g h");
+    ctx.input_keys("u3dw");
+    assert_eq!(ctx.text(), "d e f g h");
+    ctx.input_keys("d2w");
+    assert_eq!(ctx.text(), "f g h");
+}
+
+#[test]
+fn insert_repeats_text() {
+    let mut ctx = Context::new().with_text("xy");
+    ctx.input_keys("3ihi<Esc>");
+    assert_eq!(ctx.text(), "hihihixy");
+    assert_eq!(ctx.buffer_edit.cursor.column, 5);
+    ctx.input_keys("u");
+    assert_eq!(ctx.text(), "xy");
+    ctx.input_keys("2ahey<Esc>");
+    assert_eq!(ctx.text(), "xheyheyy");
+}
+
+#[test]
+fn open_line_repeats_lines() {
+    let mut ctx = Context::new().with_text("a\nb");
+    ctx.input_keys("3ohi<Esc>");
+    assert_eq!(ctx.text(), "a\nhi\nhi\nhi\nb");
+    assert_eq!(ctx.buffer_edit.cursor.row, 3);
+    ctx.input_keys("u");
+    assert_eq!(ctx.text(), "a\nb");
+    ctx.input_keys("2Oyo<Esc>");
+    assert_eq!(ctx.text(), "yo\nyo\na\nb");
+    ctx.input_keys("u3o<Esc>");
+    assert_eq!(ctx.text(), "a\n\n\n\nb");
+}
+
+#[test]
+fn delete_character_count() {
+    let mut ctx = Context::new().with_text("abcdef");
+    ctx.input_keys("3x");
+    assert_eq!(ctx.text(), "def");
+    ctx.input_keys("9x");
+    assert_eq!(ctx.text(), "");
+}
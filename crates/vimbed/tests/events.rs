@@ -0,0 +1,64 @@
+use std::{cell::RefCell, rc::Rc};
+
+use proptest::prelude::*;
+
+use vimbed::{
+    context::{BufferId, Context},
+    event::Event,
+};
+
+/// Keys editing the text through motions, operators, insert mode, undo and ex commands
+fn keys() -> impl Strategy<Value = Vec<&'static str>> {
+    let key = prop_oneof![
+        Just("j"),
+        Just("w"),
+        Just("x"),
+        Just("dw"),
+        Just("dd"),
+        Just("3J"),
+        Just("p"),
+        Just("u"),
+        Just("<C-r>"),
+        Just("ix\n日<Esc>"),
+        Just("2o<Esc>"),
+        Just(":%s/a/bb/g<CR>"),
+        Just(":g/b/d<CR>"),
+        Just(":sort<CR>"),
+        Just(":0put<CR>"),
+    ];
+    prop::collection::vec(key, 0..32)
+}
+
+proptest! {
+    #[test]
+    fn changes_replay_to_same_text(text in "[a-c \n]{0,32}", keys in keys()) {
+        let events = Rc::new(RefCell::new(vec![]));
+        let mut ctx = Context::new().with_text(text.clone());
+        ctx.subscribe({
+            let events = events.clone();
+            move |event| events.borrow_mut().push(event.clone())
+        });
+
+        let mut replayed = text;
+        let mut cursor = ctx.buffer_edit.cursor;
+        for key in keys {
+            ctx.input_keys(key);
+
+            for event in events.borrow_mut().drain(..) {
+                match event {
+                    Event::Change { buffer: BufferId::Edit, change } => {
+                        prop_assert_eq!(&replayed[change.range()], change.removed.as_str());
+                        replayed.replace_range(change.range(), &change.inserted);
+                    }
+                    Event::CursorMove { buffer: BufferId::Edit, old, new } => {
+                        prop_assert_eq!(old, cursor);
+                        cursor = new;
+                    }
+                    _ => (),
+                }
+            }
+            prop_assert_eq!(&replayed, &ctx.text());
+            prop_assert_eq!(cursor, ctx.buffer_edit.cursor);
+        }
+    }
+}
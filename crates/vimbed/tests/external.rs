@@ -0,0 +1,79 @@
+use proptest::prelude::*;
+
+use vimbed::{
+    buffer::{ExternalEdit, UndoStep},
+    context::Context,
+};
+
+/// Edits given as fractions of the text length, so they stay in range as it changes
+fn edits() -> impl Strategy<Value = Vec<(f64, f64, String)>> {
+    prop::collection::vec((0.0..=1.0, 0.0..=1.0, "[a日\n]{0,4}"), 0..8)
+}
+
+proptest! {
+    #[test]
+    fn external_edits_apply_and_undo(
+        text in "[a-c 日\n]{0,32}",
+        keys in "[jlw]{0,8}",
+        edits in edits(),
+    ) {
+        let mut ctx = Context::new().with_text(text.clone());
+        ctx.input_keys(&keys);
+        let cursor_char = ctx.text()[ctx.buffer_edit.cursor_offset()..].chars().next();
+
+        let mut expected = text.clone();
+        let mut batch = vec![];
+        // Whether every edit lies after the cursor, leaving the text from it alone
+        let mut after_cursor = true;
+        let cursor = ctx.buffer_edit.cursor_offset();
+        for (start, end, inserted) in edits {
+            let floor = |f: f64| {
+                let mut offset = (f * expected.len() as f64) as usize;
+                while !expected.is_char_boundary(offset) {
+                    offset -= 1;
+                }
+                offset
+            };
+            let (start, end) = (floor(start.min(end)), floor(start.max(end)));
+            expected.replace_range(start..end, &inserted);
+            after_cursor &= start > cursor;
+            batch.push(ExternalEdit { range: start..end, text: inserted });
+        }
+
+        ctx.apply_edits(&batch, UndoStep::Separate);
+        prop_assert_eq!(&ctx.text(), &expected);
+        let offset = ctx.buffer_edit.cursor_offset();
+        prop_assert!(ctx.text().is_char_boundary(offset));
+        if after_cursor {
+            prop_assert_eq!(ctx.text()[offset..].chars().next(), cursor_char);
+        }
+
+        ctx.input_keys("u");
+        prop_assert_eq!(&ctx.text(), &text);
+    }
+
+    #[test]
+    fn insert_before_cursor_keeps_it_on_its_character(
+        text in "[a-c]{1,8}\n[a-c]{2,8}",
+        column in 0usize..8,
+        inserted in "[a日]{1,4}",
+    ) {
+        let mut ctx = Context::new().with_text(text);
+        ctx.input_keys(&format!("j{}l", column + 1));
+        let before = ctx.buffer_edit.cursor;
+        prop_assume!(before.column > 0);
+        let offset = ctx.buffer_edit.line_offset(1);
+
+        ctx.apply_edits(
+            &[ExternalEdit { range: offset..offset, text: inserted.clone() }],
+            UndoStep::Separate,
+        );
+        let after = ctx.buffer_edit.cursor;
+        prop_assert_eq!(after.row, 1);
+        prop_assert_eq!(after.column, before.column + inserted.len());
+        prop_assert_eq!(
+            after.target_column,
+            before.target_column + ctx.buffer_edit.virtual_column(1, inserted.len())
+        );
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use vimbed::context::Context;
+
+/// Lines alternately matching `a` and not
+const TEXT: &str = "a1\nb2\na3\nb4";
+
+/// Text after typing `keys` in [`TEXT`]
+fn run(keys: &str) -> String {
+    common::run(TEXT, keys)
+}
+
+#[test]
+fn global_runs_on_matching_lines() {
+    assert_eq!(run(":g/a/d<CR>"), "b2\nb4");
+    assert_eq!(run(":g/a/s/a/x/<CR>"), "x1\nb2\nx3\nb4");
+    assert_eq!(run(":g/a/normal Az<CR>"), "a1z\nb2\na3z\nb4");
+    assert_eq!(run(":2,3g/a/d<CR>"), "a1\nb2\nb4");
+}
+
+#[test]
+fn vglobal_runs_on_other_lines() {
+    assert_eq!(run(":v/a/d<CR>"), "a1\na3");
+    assert_eq!(run(":g!/a/d<CR>"), "a1\na3");
+}
+
+#[test]
+fn marked_lines_follow_edits() {
+    // Each line is found again after those before it moved, were copied or joined
+    assert_eq!(run(":g/^/m0<CR>"), "b4\na3\nb2\na1");
+    assert_eq!(run(":g/a/t.<CR>"), "a1\na1\nb2\na3\na3\nb4");
+    assert_eq!(run(":g/a/j<CR>"), "a1 b2\na3 b4");
+}
+
+#[test]
+fn global_is_undone_at_once() {
+    assert_eq!(run(":g/a/d<CR>u"), TEXT);
+    assert_eq!(run(":g/./normal Ax<CR>u"), TEXT);
+
+    let mut ctx = Context::new().with_text(TEXT);
+    ctx.ex("g/./normal Ax");
+    assert_eq!(ctx.text(), "a1x\nb2x\na3x\nb4x");
+    ctx.input_keys("u");
+    assert_eq!(ctx.text(), TEXT);
+}
+
+#[test]
+fn no_match_is_reported() {
+    let mut ctx = Context::new().with_text(TEXT);
+    ctx.input_keys(":g/z/d<CR>");
+    assert_eq!(ctx.text(), TEXT);
+    assert_eq!(
+        ctx.drain_messages().last().unwrap().to_string(),
+        "Pattern not found: z"
+    );
+}
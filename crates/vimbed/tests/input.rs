@@ -0,0 +1,155 @@
+use proptest::prelude::*;
+
+use vimbed::context::Context;
+
+/// Keys from every mode, including counts, registers and ex command lines
+fn keys() -> impl Strategy<Value = Vec<String>> {
+    let key = prop_oneof![
+        "[ -~]",
+        "[0-9]{1,3}",
+        Just("<Esc>".to_owned()),
+        Just("<CR>".to_owned()),
+        Just("<BS>".to_owned()),
+        Just("<Tab>".to_owned()),
+        Just("<Del>".to_owned()),
+        Just("<C-r>".to_owned()),
+        Just("<Left>".to_owned()),
+        Just("<Right>".to_owned()),
+        Just("\n".to_owned()),
+        Just("日".to_owned()),
+        Just("e\u{301}".to_owned()),
+        Just("99999999999999999999".to_owned()),
+        Just(":%s/a/b/g<CR>".to_owned()),
+        Just(":g/./d<CR>".to_owned()),
+        Just(":0put<CR>".to_owned()),
+        Just(":$<CR>".to_owned()),
+        Just(":sort<CR>".to_owned()),
+        Just(":normal! ddp<CR>".to_owned()),
+        Just(":messages<CR>".to_owned()),
+        Just(":nosuchcommand<CR>".to_owned()),
+    ];
+    prop::collection::vec(key, 0..48)
+}
+
+/// Lines of short words, possibly empty
+fn text() -> impl Strategy<Value = String> {
+    prop::collection::vec("[a-c \t]{0,6}", 1..6).prop_map(|lines| lines.join("\n"))
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_input_does_not_panic(text in text(), keys in keys()) {
+        let mut edit = text;
+        let mut command = String::new();
+        let mut search = String::new();
+        let mut ctx = Context::borrowed(&mut edit, &mut command, &mut search);
+
+        for key in keys {
+            ctx.input_keys(&key);
+            ctx.drain_messages().for_each(drop);
+        }
+        ctx.input_keys_complete("", false);
+    }
+}
+
+/// Counted normal mode commands, with `{N}` where the count goes
+const COUNTED_NORMAL: &[&str] = &[
+    "{N}x",
+    "{N}dd",
+    "d{N}d",
+    "{N}J",
+    "{N}p",
+    "{N}P",
+    "{N}u",
+    "{N}<C-r>",
+    "{N}ihi<Esc>",
+    "{N}ahi<Esc>",
+    "{N}Ahi<Esc>",
+    "{N}Ihi<Esc>",
+    "{N}ohi<Esc>",
+    "{N}Ohi<Esc>",
+    "{N}shi<Esc>",
+    "{N}Shi<Esc>",
+    "{N}<C-^>",
+    "{N}gt",
+    "{N}gT",
+    "{N}<C-w>w",
+    "{N}<C-w>W",
+    "{N}<C-w>h",
+    "{N}<C-w>j",
+    "{N}<C-w>k",
+    "{N}<C-w>l",
+    "{N}<C-w>+",
+    "{N}<C-w>-",
+    "{N}<C-w>s",
+    "{N}@q",
+    "{N}@@",
+    "{N}dw",
+    "d{N}w",
+    "{N}d{N}w",
+    "{N}w",
+    "{N}b",
+    "{N}h",
+    "{N}j",
+    "{N}k",
+    "{N}l",
+    "{N}<Left>",
+    "{N}<Right>",
+];
+
+/// Counted ex commands, and ranges with counted offsets
+const COUNTED_EX: &[&str] = &[
+    ":d {N}",
+    ":y {N}",
+    ":> {N}",
+    ":< {N}",
+    ":j {N}",
+    ":s/b/x/ {N}",
+    ":& {N}",
+    ":&& {N}",
+    ":{N}pu",
+    ":m +{N}",
+    ":t .+{N}",
+    ":.+{N}d",
+    ":.+{N}+{N}d",
+    ":.-{N}-{N}d",
+    ":{N},{N}d",
+    ":retab {N}",
+    ":retab! {N}",
+    ":set ts={N}",
+    ":set sw={N}",
+    ":set sts={N}",
+    ":undo {N}",
+    ":normal {N}x",
+    ":b {N}",
+    ":{N}b",
+    ":bn {N}",
+    ":bp {N}",
+    ":bd {N}",
+    ":{N}wincmd w",
+    ":{N}wincmd +",
+    ":tabnext {N}",
+    ":{N}tabnext",
+    ":tabprevious {N}",
+    ":tabclose {N}",
+    ":tabmove {N}",
+    ":tabmove +{N}",
+    ":tabmove -{N}",
+];
+
+#[test]
+fn huge_counts_do_not_panic() {
+    for count in [
+        "18446744073709551615",
+        "9223372036854775808",
+        "99999999999999999999",
+    ] {
+        for command in COUNTED_NORMAL.iter().chain(COUNTED_EX) {
+            let mut ctx = Context::new().with_text("a\tb c\n\tb\nc");
+            ctx.input_keys("qqxqyy:tabnew<CR>gt<C-w>sj");
+            ctx.input_keys(&command.replace("{N}", count));
+            ctx.input_keys("<Esc><CR>u");
+            ctx.drain_messages().for_each(drop);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use proptest::prelude::*;
+
+use vimbed::{
+    context::{Context, KeyOutcome},
+    key::{keys, notation, Key, KeyCode, Modifiers},
+    mode::Mode,
+};
+
+/// Any key, with any modifiers held
+fn key() -> impl Strategy<Value = Key> {
+    let code = prop_oneof![
+        any::<char>().prop_map(KeyCode::Char),
+        Just(KeyCode::Escape),
+        Just(KeyCode::Enter),
+        Just(KeyCode::Tab),
+        Just(KeyCode::Backspace),
+        Just(KeyCode::PageDown),
+        (1..=37u8).prop_map(KeyCode::F),
+        Just(KeyCode::KeypadEnter),
+        prop::sample::select(vec!['0', '9', '+', '-', '*', '/', '.', ',', '='])
+            .prop_map(KeyCode::Keypad),
+    ];
+    let modifiers = (any::<bool>(), any::<bool>(), any::<bool>(), any::<bool>()).prop_map(
+        |(shift, ctrl, alt, meta)| Modifiers {
+            shift,
+            ctrl,
+            alt,
+            meta,
+        },
+    );
+    (code, modifiers).prop_map(|(code, modifiers)| Key::new(code, modifiers))
+}
+
+proptest! {
+    #[test]
+    fn notation_parses_back(keys_in in prop::collection::vec(key(), 0..8)) {
+        let written = notation(keys_in.clone());
+        prop_assert_eq!(keys(&written).collect::<Vec<_>>(), keys_in);
+    }
+}
+
+#[test]
+fn named_keys() {
+    assert_eq!("<Esc>".parse::<Key>().unwrap(), KeyCode::Escape.into());
+    assert_eq!("<cr>".parse::<Key>().unwrap(), KeyCode::Enter.into());
+    assert_eq!("<F12>".parse::<Key>().unwrap(), KeyCode::F(12).into());
+    assert_eq!("<k0>".parse::<Key>().unwrap(), KeyCode::Keypad('0').into());
+    assert_eq!("<lt>".parse::<Key>().unwrap(), Key::char('<'));
+    assert_eq!("<Space>".parse::<Key>().unwrap(), Key::char(' '));
+    assert!("<F38>".parse::<Key>().is_err());
+    assert!("<Nope>".parse::<Key>().is_err());
+}
+
+#[test]
+fn modifiers_are_normalized() {
+    assert_eq!("<C-W>".parse::<Key>().unwrap(), Key::ctrl('w'));
+    assert_eq!("<S-a>".parse::<Key>().unwrap(), Key::char('A'));
+    assert_eq!("<S-1>".parse::<Key>().unwrap(), Key::char('1'));
+    assert_eq!(
+        "<c-s-tab>".parse::<Key>().unwrap(),
+        Key::new(KeyCode::Tab, Modifiers::CTRL | Modifiers::SHIFT)
+    );
+    assert_eq!(
+        "<M-->".parse::<Key>().unwrap(),
+        Key::new(KeyCode::Char('-'), Modifiers::META)
+    );
+    assert_eq!(
+        "<C->>".parse::<Key>().unwrap(),
+        Key::new(KeyCode::Char('>'), Modifiers::CTRL)
+    );
+    assert_eq!(
+        Key::new(KeyCode::Char('ı'), Modifiers::CTRL | Modifiers::SHIFT),
+        Key::ctrl('i')
+    );
+}
+
+#[test]
+fn unmatched_angle_bracket_is_a_key() {
+    let parsed: Vec<_> = keys("<<Esc><x").collect();
+    assert_eq!(
+        parsed,
+        [
+            Key::char('<'),
+            KeyCode::Escape.into(),
+            Key::char('<'),
+            Key::char('x')
+        ]
+    );
+}
+
+#[test]
+fn keys_are_written_in_notation() {
+    let written = notation(keys("a<lt><c-w><S-Tab><A-x><kPlus>"));
+    assert_eq!(written, "a<lt><C-w><S-Tab><A-x><kPlus>");
+}
+
+#[test]
+fn input_str_accepts_any_spelling() {
+    let mut ctx = Context::new().with_text("abc");
+    for keys in ["<Delete>", "i", "<ESC>"] {
+        assert_eq!(ctx.input_str(keys), KeyOutcome::Consumed);
+    }
+    assert_eq!(ctx.mode, Mode::Normal);
+    assert_eq!(ctx.text(), "bc");
+}
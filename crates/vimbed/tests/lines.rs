@@ -0,0 +1,79 @@
+mod common;
+
+use vimbed::context::Context;
+
+/// Largest count that parses, which overflows when added to any line number
+const HUGE: &str = "18446744073709551615";
+
+/// Text after typing `keys` with the cursor on the second of three lines
+fn run(keys: &str) -> String {
+    common::run("a\nb\nc", &format!("j{}", keys))
+}
+
+#[test]
+fn delete_count_stops_at_last_line() {
+    assert_eq!(run(&format!(":d {}<CR>", HUGE)), "a");
+    assert_eq!(run(&format!("{}dd", HUGE)), "a");
+}
+
+#[test]
+fn yank_count_stops_at_last_line() {
+    assert_eq!(run(&format!(":y {}<CR>P", HUGE)), "a\nb\nc\nb\nc");
+}
+
+#[test]
+fn shift_count_stops_at_last_line() {
+    assert_eq!(run(&format!(":> {}<CR>", HUGE)), "a\n\tb\n\tc");
+}
+
+#[test]
+fn join_count_stops_at_last_line() {
+    assert_eq!(run(":j 2<CR>"), "a\nb c");
+    assert_eq!(run(&format!(":j {}<CR>", HUGE)), "a\nb c");
+    assert_eq!(run("3J"), "a\nb c");
+    assert_eq!(run(&format!("{}J", HUGE)), "a\nb c");
+}
+
+#[test]
+fn join_joins_at_least_two_lines() {
+    assert_eq!(run(":j 1<CR>"), "a\nb c");
+    assert_eq!(run(":1j 1<CR>"), "a b\nc");
+    assert_eq!(run(":1,1j<CR>"), "a b\nc");
+    assert_eq!(run(":3j 1<CR>"), "a\nb\nc");
+}
+
+#[test]
+fn move_lines() {
+    assert_eq!(run(":m0<CR>"), "b\na\nc");
+    assert_eq!(run(":2,3m0<CR>"), "b\nc\na");
+    assert_eq!(run(":m$<CR>"), "a\nc\nb");
+    assert_eq!(run(":m$<CR>u"), "a\nb\nc");
+}
+
+#[test]
+fn copy_lines() {
+    assert_eq!(run(":t.<CR>"), "a\nb\nb\nc");
+    assert_eq!(run(":t0<CR>"), "b\na\nb\nc");
+    assert_eq!(run(":1,2co$<CR>"), "a\nb\nc\na\nb");
+}
+
+#[test]
+fn put_lines() {
+    // Text from a register is always put as lines
+    assert_eq!(run(":1y<CR>:pu<CR>"), "a\nb\na\nc");
+    assert_eq!(run(":1y<CR>:0pu<CR>"), "a\na\nb\nc");
+    assert_eq!(run(":1y<CR>:$pu<CR>"), "a\nb\nc\na");
+    assert_eq!(run(":1y<CR>:pu!<CR>"), "a\na\nb\nc");
+    assert_eq!(run(":3y x<CR>:pu x<CR>"), "a\nb\nc\nc");
+    assert_eq!(run("x:pu<CR>"), "a\n\nb\nc");
+}
+
+#[test]
+fn each_ex_call_is_undone_separately() {
+    let mut ctx = Context::new().with_text("a\nb\nc");
+    ctx.ex("m$");
+    ctx.ex("1d");
+    assert_eq!(ctx.text(), "c\na");
+    ctx.input_keys("u");
+    assert_eq!(ctx.text(), "b\nc\na");
+}
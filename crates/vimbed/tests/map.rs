@@ -0,0 +1,109 @@
+mod common;
+
+use vimbed::{context::Context, map::MapMode};
+
+use common::{messages, run};
+
+#[test]
+fn map_is_remapped_and_noremap_is_not() {
+    assert_eq!(run("abc\ndef", ":nmap Q dd<CR>Q"), "def");
+    assert_eq!(run("abc\ndef", ":nnoremap x dd<CR>:nmap y x<CR>y"), "def");
+    assert_eq!(
+        run("abc\ndef", ":nnoremap y x<CR>:nmap x dd<CR>y"),
+        "bc\ndef"
+    );
+}
+
+#[test]
+fn mappings_apply_in_their_modes() {
+    assert_eq!(run("abc", ":imap jk <lt>Esc><CR>ihijkx"), "habc");
+    assert_eq!(run("abc", ":imap jk <lt>Esc><CR>jk"), "abc");
+    // `!` maps in insert and command line mode
+    assert_eq!(run("abc", ":map! a b<CR>ia<Esc>"), "babc");
+}
+
+#[test]
+fn leader_is_expanded_when_defining() {
+    let mut ctx = Context::new().with_text("abc\ndef");
+    ctx.input_keys(":set mapleader=,<CR>:nmap <Leader>d dd<CR>,d");
+    assert_eq!(ctx.text(), "def");
+    assert_eq!(messages(&mut ctx, ":nmap<CR>"), ["n  ,d           dd"]);
+}
+
+#[test]
+fn listing_groups_modes() {
+    let mut ctx = Context::new();
+    ctx.input_keys(":map Q x<CR>:map! Z a<CR>:vmap W b<CR>");
+    assert_eq!(
+        messages(&mut ctx, ":map<CR>"),
+        ["   Q            x\nv  W            b"]
+    );
+    assert_eq!(messages(&mut ctx, ":map!<CR>"), ["!  Z            a"]);
+
+    ctx.input_keys(":nnoremap Q y<CR>");
+    assert_eq!(
+        messages(&mut ctx, ":map<CR>"),
+        ["n  Q           *y\nov Q            x\nv  W            b"]
+    );
+}
+
+#[test]
+fn unmap_removes_mapping() {
+    let mut ctx = Context::new().with_text("abc\ndef");
+    ctx.input_keys(":nmap Q dd<CR>:nunmap Q<CR>Q");
+    assert_eq!(ctx.text(), "abc\ndef");
+    assert_eq!(
+        messages(&mut ctx, ":nunmap Q<CR>"),
+        ["E31: No such mapping"]
+    );
+}
+
+#[test]
+fn silent_hides_messages() {
+    let mut ctx = Context::new();
+    assert!(messages(&mut ctx, ":nmap <silent> Z :set ts?<lt>CR><CR>Z").is_empty());
+    assert_eq!(
+        messages(&mut ctx, ":nmap Y :set ts?<lt>CR><CR>Y"),
+        ["  tabstop=8"]
+    );
+}
+
+#[test]
+fn silent_hides_messages_with_full_history() {
+    let mut ctx = Context::new().with_text("a");
+    assert_eq!(
+        messages(&mut ctx, ":set mhi=2<CR>:g/z/d<CR>u"),
+        ["Pattern not found: z", "Already at oldest change"]
+    );
+    assert!(messages(&mut ctx, ":nnoremap <silent> Q u<CR>Q").is_empty());
+    assert_eq!(
+        messages(&mut ctx, ":messages<CR>"),
+        ["Pattern not found: z\nAlready at oldest change"]
+    );
+}
+
+#[test]
+fn invalid_mappings_are_refused() {
+    let mut ctx = Context::new().with_text("abc");
+    for (keys, error) in [
+        (":nmap a b<CR>:nmap b a<CR>a", "E223: Recursive mapping"),
+        (":imap! a b<CR>", "E477: No ! allowed"),
+        (
+            ":nmap <expr> Y x<CR>",
+            "E474: Invalid argument: <expr> is not supported",
+        ),
+    ] {
+        assert_eq!(messages(&mut ctx, keys), [error]);
+    }
+    assert_eq!(ctx.text(), "abc");
+}
+
+#[test]
+fn callback_mapping() {
+    let mut ctx = Context::new().with_text("abc");
+    ctx.map_callback(&[MapMode::Normal], "<Leader>x", |ctx| {
+        ctx.set_text("called")
+    });
+    ctx.input_keys("\\x");
+    assert_eq!(ctx.text(), "called");
+}
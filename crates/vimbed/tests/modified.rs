@@ -0,0 +1,102 @@
+use proptest::prelude::*;
+
+use vimbed::{context::Context, message::Message};
+
+/// Keys editing the text through operators, insert mode, undo, redo and ex commands
+fn keys() -> impl Strategy<Value = Vec<&'static str>> {
+    let key = prop_oneof![
+        Just("j"),
+        Just("w"),
+        Just("x"),
+        Just("dd"),
+        Just("yyp"),
+        Just("u"),
+        Just("<C-r>"),
+        Just("ix<Esc>"),
+        Just(":%s/a/b/g<CR>"),
+        Just(":sort<CR>"),
+        Just(":undo<CR>"),
+    ];
+    prop::collection::vec(key, 0..24)
+}
+
+proptest! {
+    #[test]
+    fn unmodified_text_is_saved_text(
+        text in "[a-c \n]{1,32}",
+        before in keys(),
+        after in keys(),
+    ) {
+        let mut ctx = Context::new().with_text(text);
+        for key in before {
+            ctx.input_keys(key);
+        }
+        ctx.buffer_edit.set_saved();
+        let saved = ctx.text().into_owned();
+
+        // Walk the history back and forth past the saved state
+        let undo_redo = std::iter::repeat_n("u", 30).chain(std::iter::repeat_n("<C-r>", 30));
+        for key in after.into_iter().chain(undo_redo) {
+            let tick = ctx.buffer_edit.change_tick();
+            let text = ctx.text().into_owned();
+            ctx.input_keys(key);
+            if ctx.text() != text {
+                prop_assert!(ctx.buffer_edit.change_tick() > tick);
+            }
+            if !ctx.buffer_edit.modified() {
+                prop_assert_eq!(&ctx.text(), &saved);
+            }
+        }
+
+        ctx.buffer_edit.set_saved();
+        ctx.input_keys("ox<Esc>");
+        prop_assert!(ctx.buffer_edit.modified());
+        ctx.input_keys("u");
+        prop_assert!(!ctx.buffer_edit.modified());
+    }
+
+    #[test]
+    fn nomodifiable_rejects_edits(text in "[a-c \n]{0,32}", keys in keys()) {
+        let mut ctx = Context::new().with_text(text.clone());
+        ctx.ex("set nomodifiable");
+        for key in keys {
+            ctx.input_keys(key);
+            for message in ctx.drain_messages() {
+                if let Message::Error(error) = message {
+                    prop_assert_eq!(error.code, 21);
+                }
+            }
+        }
+        prop_assert_eq!(&ctx.text(), &text);
+        prop_assert!(!ctx.buffer_edit.modified());
+        prop_assert_eq!(ctx.buffer_edit.change_tick(), 0);
+    }
+}
+
+#[test]
+fn empty_edit_changes_nothing() {
+    let mut ctx = Context::new().with_text("\nabc");
+    ctx.input_keys("x");
+    assert!(!ctx.buffer_edit.modified());
+    assert_eq!(ctx.buffer_edit.change_tick(), 0);
+    ctx.input_keys("u");
+    assert_eq!(
+        ctx.drain_messages().last().unwrap().to_string(),
+        "Already at oldest change"
+    );
+}
+
+#[test]
+fn nomodifiable_allows_counting_matches() {
+    let mut ctx = Context::new().with_text("a b\nb");
+    ctx.ex("set nomodifiable");
+    ctx.input_keys(":%s/b/x/n<CR>");
+    ctx.input_keys(":%&&<CR>");
+    let messages = ctx.drain_messages().map(|message| message.to_string());
+    assert_eq!(messages.collect::<Vec<_>>(), ["2 matches on 2 lines"; 2]);
+
+    ctx.input_keys(":%s/b/x/<CR>");
+    let message = ctx.drain_messages().next().unwrap();
+    assert!(matches!(message, Message::Error(error) if error.code == 21));
+    assert_eq!(ctx.text(), "a b\nb");
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use vimbed::context::Context;
+
+use common::run;
+
+#[test]
+fn normal_runs_on_each_line() {
+    let text = "a b\nc d\ne f";
+    assert_eq!(run(text, ":normal Ax<CR>"), "a bx\nc d\ne f");
+    assert_eq!(run(text, ":%normal Ax<CR>"), "a bx\nc dx\ne fx");
+    assert_eq!(run(text, ":2,3norm! dw<CR>"), "a b\nd\nf");
+    assert_eq!(run(text, ":%norm 0x<CR>"), " b\n d\n f");
+}
+
+#[test]
+fn unfinished_insert_is_ended() {
+    let mut ctx = Context::new().with_text("a b\nc d");
+    ctx.input_keys(":%norm ihi<CR>");
+    assert_eq!(ctx.text(), "hia b\nhic d");
+    assert_eq!(ctx.buffer_edit.cursor.column, 1);
+    ctx.input_keys("x");
+    assert_eq!(ctx.text(), "hia b\nhc d");
+}
+
+#[test]
+fn normal_is_undone_at_once() {
+    assert_eq!(run("a\nb", ":%normal Ax<CR>u"), "a\nb");
+    assert_eq!(run("abc", ":normal xx<CR>u"), "abc");
+
+    let mut ctx = Context::new().with_text("a\nb\nc");
+    ctx.ex("%normal Ax");
+    ctx.ex("normal xx");
+    assert_eq!(ctx.text(), "ax\nbx\n");
+    ctx.input_keys("u");
+    assert_eq!(ctx.text(), "ax\nbx\ncx");
+    ctx.input_keys("u");
+    assert_eq!(ctx.text(), "a\nb\nc");
+}
+
+#[test]
+fn bang_ignores_mappings() {
+    assert_eq!(run("a b\nc d", ":nmap x dd<CR>:norm x<CR>"), "c d");
+    assert_eq!(run("a b\nc d", ":nmap x dd<CR>:norm! x<CR>"), " b\nc d");
+}
+
+#[test]
+fn keys_are_required() {
+    let mut ctx = Context::new().with_text("a");
+    ctx.input_keys(":norm<CR>");
+    assert_eq!(
+        ctx.drain_messages().last().unwrap().to_string(),
+        "E471: Argument required"
+    );
+}
@@ -0,0 +1,66 @@
+use std::time::Instant;
+
+use vimbed::{
+    context::Context,
+    key::Key,
+    mode::{CursorShape, Mode},
+    operator::OperatorVariant,
+};
+
+#[test]
+fn operator_waits_for_motion() {
+    let mut ctx = Context::new().with_text("abc def ghi");
+    ctx.input_key(Key::char('2'), Instant::now());
+    ctx.input_key(Key::char('d'), Instant::now());
+    assert_eq!(
+        ctx.mode,
+        Mode::OperatorPending {
+            operator: OperatorVariant::Delete,
+            count: Some(2),
+        }
+    );
+    assert_eq!(ctx.cursor_shape(), CursorShape::Underline);
+
+    ctx.input_key(Key::char('w'), Instant::now());
+    assert_eq!(ctx.mode, Mode::Normal);
+    assert_eq!(ctx.cursor_shape(), CursorShape::Block);
+    assert_eq!(ctx.text(), "ghi");
+}
+
+#[test]
+fn escape_cancels_operator() {
+    let mut ctx = Context::new().with_text("abc def");
+    ctx.input_keys("d<Esc>");
+    assert_eq!(ctx.mode, Mode::Normal);
+    ctx.input_keys("x");
+    assert_eq!(ctx.text(), "bc def");
+}
+
+#[test]
+fn operator_pending_mappings() {
+    let mut ctx = Context::new().with_text("abc def");
+    ctx.input_keys(":onoremap p $<CR>ldp");
+    assert_eq!(ctx.text(), "a");
+
+    // The mapping doesn't apply in normal mode
+    ctx.input_keys("xp");
+    assert_eq!(ctx.text(), "a");
+}
+
+#[test]
+fn unfinished_operator_is_dropped() {
+    let mut ctx = Context::new().with_text("abc def");
+    ctx.input_keys("d");
+    assert_eq!(ctx.mode, Mode::Normal);
+    ctx.input_keys("w");
+    assert_eq!(ctx.text(), "abc def");
+}
+
+#[test]
+fn omap_is_remapped_in_operator_pending_mode_only() {
+    let mut ctx = Context::new().with_text("a b c");
+    ctx.input_keys(":omap p w<CR>:onoremap w $<CR>dp");
+    assert_eq!(ctx.text(), "");
+    ctx.input_keys("uw");
+    assert_eq!(ctx.buffer_edit.cursor.column, 2);
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "serde")]
+
+use proptest::prelude::*;
+
+use vimbed::{context::Context, session::Session};
+
+/// Keys editing the text, yanking into registers, searching, setting options, and switching
+/// buffers, windows and tab pages
+fn keys() -> impl Strategy<Value = Vec<&'static str>> {
+    let key = prop_oneof![
+        Just("j"),
+        Just("w"),
+        Just("x"),
+        Just("\"add"),
+        Just("yyp"),
+        Just("u"),
+        Just("<C-r>"),
+        Just("ix\n日<Esc>"),
+        Just("a"),
+        Just(":"),
+        Just("<Esc>"),
+        Just("/b<CR>"),
+        Just(":%s/a/bb/g<CR>"),
+        Just(":g/b/d<CR>"),
+        Just(":set ts=4 et<CR>"),
+        Just(":setlocal sw=2<CR>"),
+        Just(":b2<CR>"),
+        Just("<C-^>"),
+        Just("<C-w>s"),
+        Just("<C-w>w"),
+        Just(":tabnew<CR>"),
+        Just("gt"),
+    ];
+    prop::collection::vec(key, 0..24)
+}
+
+proptest! {
+    #[test]
+    fn restored_session_behaves_the_same(
+        text in "[a-c \n]{0,32}",
+        before in keys(),
+        after in keys(),
+    ) {
+        let mut ctx = Context::new().with_text(text);
+        ctx.add_buffer(String::from("one\ntwo"));
+        ctx.ex("set hidden");
+        for key in before {
+            ctx.input_keys(key);
+        }
+
+        let session = ctx.session();
+        let json = serde_json::to_string(&session).unwrap();
+        let mut restored = Context::new();
+        restored
+            .restore(serde_json::from_str::<Session>(&json).unwrap())
+            .unwrap();
+        prop_assert_eq!(&restored.session(), &session);
+
+        for key in after.into_iter().chain(["100u"]) {
+            ctx.input_keys(key);
+            restored.input_keys(key);
+            prop_assert_eq!(&restored.text(), &ctx.text());
+            prop_assert_eq!(restored.buffer_edit.cursor, ctx.buffer_edit.cursor);
+            prop_assert_eq!(restored.buffer_edit.modified(), ctx.buffer_edit.modified());
+            prop_assert_eq!(&restored.session(), &ctx.session());
+        }
+    }
+}
+
+#[test]
+fn unknown_version_is_refused() {
+    let ctx = Context::new().with_text("abc");
+    let mut session = ctx.session();
+    session.version += 1;
+
+    let mut restored = Context::new();
+    let error = restored.restore(session).unwrap_err();
+    assert_eq!(error.code, 474);
+    assert_eq!(restored.text(), "");
+}
+
+#[test]
+fn every_buffer_is_restored() {
+    let mut ctx = Context::new().with_text("one");
+    ctx.add_buffer(String::from("two"));
+    ctx.input_keys(":set hidden<CR>Ax<Esc>:b2<CR>:setlocal ts=4<CR>Ay<Esc>:b1<CR>");
+
+    let json = serde_json::to_string(&ctx.session()).unwrap();
+    let mut restored = Context::new();
+    restored
+        .restore(serde_json::from_str::<Session>(&json).unwrap())
+        .unwrap();
+    assert_eq!(restored.buffers.len(), 2);
+    assert_eq!(restored.text(), "onex");
+
+    restored.input_keys(":b2<CR>");
+    assert_eq!(restored.text(), "twoy");
+    assert_eq!(restored.options.number("tabstop"), Some(4));
+    assert!(restored.buffer_edit.modified());
+    restored.input_keys("u<C-^>u");
+    assert_eq!(restored.text(), "one");
+    restored.input_keys("<C-^>");
+    assert_eq!(restored.text(), "two");
+}
+
+#[test]
+fn mismatched_buffers_are_refused() {
+    let mut ctx = Context::new();
+    ctx.input_keys(":tabnew<CR>");
+    let mut session = ctx.session();
+    session.buffers.remove(0);
+
+    let mut restored = Context::new().with_text("abc");
+    assert!(restored.restore(session).is_err());
+    assert_eq!(restored.text(), "abc");
+    assert_eq!(restored.tab_pages.len(), 1);
+}
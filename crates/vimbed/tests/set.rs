@@ -0,0 +1,88 @@
+mod common;
+
+use vimbed::context::Context;
+
+use common::messages;
+
+#[test]
+fn set_and_query_values() {
+    let mut ctx = Context::new();
+    assert_eq!(messages(&mut ctx, ":set ts?<CR>"), ["  tabstop=8"]);
+    ctx.input_keys(":set ts=4 sw+=2 et<CR>");
+    assert_eq!(
+        messages(&mut ctx, ":set ts? sw? et?<CR>"),
+        ["  tabstop=4  shiftwidth=10  expandtab"]
+    );
+    ctx.input_keys(":set invet ts&<CR>");
+    assert_eq!(
+        messages(&mut ctx, ":set et? ts?<CR>"),
+        ["  noexpandtab  tabstop=8"]
+    );
+}
+
+#[test]
+fn list_values_are_added_and_removed() {
+    let mut ctx = Context::new();
+    ctx.input_keys(":set ve+=block ve+=all<CR>");
+    assert_eq!(ctx.options.list("virtualedit").unwrap(), ["block", "all"]);
+    ctx.input_keys(":set ve-=block<CR>");
+    assert_eq!(ctx.options.list("virtualedit").unwrap(), ["all"]);
+}
+
+#[test]
+fn invalid_values_are_refused() {
+    let mut ctx = Context::new();
+    for (keys, error) in [
+        (":set ts=0<CR>", "E487: Argument must be positive"),
+        (":set foo<CR>", "E518: Unknown option: foo"),
+        (":set noic=1<CR>", "E474: Invalid argument: noic=1"),
+        (":set ts=x<CR>", "E521: Number required after =: tabstop=x"),
+    ] {
+        assert_eq!(messages(&mut ctx, keys), [error]);
+    }
+    assert_eq!(ctx.options.number("tabstop"), Some(8));
+}
+
+#[test]
+fn overflowing_numbers_are_refused() {
+    let mut ctx = Context::new();
+    ctx.input_keys(":set report=9223372036854775807 mhi=9223372036854775807<CR>");
+    for (keys, error) in [
+        (
+            ":set report+=1<CR>",
+            "E521: Number required after =: report+=1",
+        ),
+        (":set mhi^=2<CR>", "E521: Number required after =: mhi^=2"),
+        (
+            ":set report-=-1<CR>",
+            "E521: Number required after =: report-=-1",
+        ),
+    ] {
+        assert_eq!(messages(&mut ctx, keys), [error]);
+    }
+    assert_eq!(ctx.options.number("report"), Some(i64::MAX));
+    assert_eq!(ctx.options.number("msghistory"), Some(i64::MAX));
+}
+
+#[test]
+fn local_and_global_values() {
+    let mut ctx = Context::new();
+    ctx.input_keys(":setlocal ts=4<CR>:setglobal ts=2<CR>");
+    assert_eq!(messages(&mut ctx, ":set ts?<CR>"), ["  tabstop=4"]);
+    assert_eq!(messages(&mut ctx, ":setglobal ts?<CR>"), ["  tabstop=2"]);
+    assert_eq!(
+        messages(&mut ctx, ":set<CR>"),
+        ["--- Options ---\n  tabstop=4"]
+    );
+}
+
+#[test]
+fn nomodifiable_refuses_changes() {
+    let mut ctx = Context::new().with_text("a");
+    assert_eq!(
+        messages(&mut ctx, ":set noma<CR>x"),
+        ["E21: Cannot make changes, 'modifiable' is off"]
+    );
+    ctx.input_keys(":set ma<CR>x");
+    assert_eq!(ctx.text(), "");
+}
@@ -0,0 +1,60 @@
+use std::time::Instant;
+
+use vimbed::{
+    context::{Context, KeyOutcome},
+    key::keys,
+};
+
+/// Outcome of typing each key of `notation`, with 'showcmd' text after each
+fn type_keys(ctx: &mut Context, notation: &str) -> Vec<(KeyOutcome, String)> {
+    let now = Instant::now();
+    keys(notation)
+        .map(|key| (ctx.input_key(key, now), ctx.showcmd()))
+        .collect()
+}
+
+#[test]
+fn pending_command_is_shown() {
+    let mut ctx = Context::new().with_text("a b c d e f g h");
+    assert_eq!(
+        type_keys(&mut ctx, "2d3w"),
+        [
+            (KeyOutcome::Pending, "2".to_owned()),
+            (KeyOutcome::Pending, "2d".to_owned()),
+            (KeyOutcome::Pending, "2d3".to_owned()),
+            (KeyOutcome::Consumed, String::new()),
+        ]
+    );
+    assert_eq!(ctx.text(), "g h");
+}
+
+#[test]
+fn keys_held_for_a_mapping_are_shown() {
+    let mut ctx = Context::new().with_text("a\nb\nc");
+    ctx.input_keys(":nmap QQ dd<CR>");
+    assert_eq!(
+        type_keys(&mut ctx, "2Q"),
+        [
+            (KeyOutcome::Pending, "2".to_owned()),
+            (KeyOutcome::Pending, "2Q".to_owned()),
+        ]
+    );
+    assert_eq!(
+        type_keys(&mut ctx, "Q"),
+        [(KeyOutcome::Consumed, String::new())]
+    );
+    assert_eq!(ctx.text(), "c");
+}
+
+#[test]
+fn invalid_command_is_rejected() {
+    let mut ctx = Context::new().with_text("abc");
+    assert_eq!(
+        type_keys(&mut ctx, "dZ"),
+        [
+            (KeyOutcome::Pending, "d".to_owned()),
+            (KeyOutcome::Rejected, String::new()),
+        ]
+    );
+    assert_eq!(ctx.text(), "abc");
+}
@@ -0,0 +1,38 @@
+use vimbed::context::Context;
+
+/// Text after running `:sort` with `args` over the whole of `text`
+fn sort(text: &str, args: &str) -> String {
+    let mut ctx = Context::new().with_text(text);
+    ctx.input_keys(&format!(":sort{}<CR>", args));
+    ctx.text().into_owned()
+}
+
+#[test]
+fn sort_numbers() {
+    let text = "b10\na2\nc\nx1\n-3";
+    assert_eq!(sort(text, " n"), "c\n-3\nx1\na2\nb10");
+    assert_eq!(sort(text, "! n"), "b10\na2\nx1\n-3\nc");
+}
+
+#[test]
+fn sort_unique_ignoring_case() {
+    // The first of each run of equal lines is kept
+    assert_eq!(sort("b\nA\na\nB\nb\nc", " ui"), "A\nb\nc");
+    assert_eq!(sort("b\nA\na\nB\nb\nc", " u"), "A\nB\na\nb\nc");
+}
+
+#[test]
+fn sort_by_pattern() {
+    let text = "x3 b\ny1 c\nz2 a\nnone";
+    // Lines without a match keep their order before the others
+    assert_eq!(sort(text, r" /\d /"), "none\nz2 a\nx3 b\ny1 c");
+    assert_eq!(sort(text, r" /\d/ r"), "none\ny1 c\nz2 a\nx3 b");
+    assert_eq!(sort(text, " /[a-z]/ n"), "none\ny1 c\nz2 a\nx3 b");
+}
+
+#[test]
+fn sort_range() {
+    let mut ctx = Context::new().with_text("d\nc\nb\na");
+    ctx.input_keys(":2,3sort<CR>");
+    assert_eq!(ctx.text(), "d\nb\nc\na");
+}
@@ -0,0 +1,131 @@
+use proptest::prelude::*;
+
+use vimbed::{context::Context, line_index::LineIndex, ropey::Rope, storage::TextStorage};
+
+/// Lines of words, including multi-byte characters and carriage returns
+fn text() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        "[a-c ]{1,4}",
+        Just("\n".to_owned()),
+        Just("\r\n".to_owned()),
+        Just("日本".to_owned()),
+        Just("e\u{301}".to_owned()),
+    ];
+    prop::collection::vec(piece, 0..24).prop_map(|pieces| pieces.concat())
+}
+
+/// Keys that move over and edit the text in normal, insert and command line mode
+fn keys() -> impl Strategy<Value = Vec<&'static str>> {
+    let key = prop_oneof![
+        Just("j"),
+        Just("k"),
+        Just("l"),
+        Just("3w"),
+        Just("w"),
+        Just("b"),
+        Just("2b"),
+        Just("G"),
+        Just("x"),
+        Just("dw"),
+        Just("dd"),
+        Just("J"),
+        Just("p"),
+        Just("u"),
+        Just("<C-r>"),
+        Just("ix\n日<Esc>"),
+        Just(":%s/a/bb/g<CR>"),
+        Just(":sort<CR>"),
+    ];
+    prop::collection::vec(key, 0..32)
+}
+
+/// Round `offset` down to a char boundary of `text`
+fn floor_char_boundary(text: &str, offset: usize) -> usize {
+    (0..=offset.min(text.len()))
+        .rev()
+        .find(|&offset| text.is_char_boundary(offset))
+        .unwrap_or_default()
+}
+
+proptest! {
+    #[test]
+    fn rope_matches_string(text in text(), start in 0..64usize, len in 0..16usize) {
+        let rope = Rope::from_str(&text);
+
+        prop_assert_eq!(rope.line_count(), text.line_count());
+        prop_assert!(rope.lines_from(0).eq(text.lines_from(0)));
+        for row in 0..=text.line_count() {
+            prop_assert_eq!(rope.line_offset(row), text.line_offset(row));
+            prop_assert_eq!(TextStorage::line(&rope, row), text.line(row));
+        }
+        for offset in (0..=text.len()).filter(|&offset| text.is_char_boundary(offset)) {
+            prop_assert!(TextStorage::is_char_boundary(&rope, offset));
+            prop_assert_eq!(rope.offset_line(offset), text.offset_line(offset));
+        }
+
+        let start = floor_char_boundary(&text, start);
+        let range = start..floor_char_boundary(&text, start + len);
+        prop_assert_eq!(TextStorage::slice(&rope, range.clone()), TextStorage::slice(&text, range.clone()));
+
+        let (mut rope, mut text) = (rope, text);
+        TextStorage::splice(&mut rope, range.clone(), "日\n");
+        TextStorage::splice(&mut text, range, "日\n");
+        prop_assert_eq!(rope.to_string(), text);
+    }
+
+    #[test]
+    fn rope_buffer_edits_like_string(text in text(), keys in keys()) {
+        let mut string = Context::new().with_text(text.clone());
+        let mut rope = Context::new().with_text(Rope::from_str(&text));
+
+        for key in keys {
+            string.input_keys(key);
+            rope.input_keys(key);
+            prop_assert_eq!(rope.text(), string.text());
+            prop_assert_eq!(rope.buffer_edit.cursor, string.buffer_edit.cursor);
+        }
+    }
+
+    #[test]
+    fn line_index_follows_edits(text in text(), keys in keys()) {
+        let mut ctx = Context::new().with_text(text);
+
+        for key in keys {
+            ctx.input_keys(key);
+            let buffer = &ctx.buffer_edit;
+            prop_assert_eq!(buffer.line_count(), buffer.text.line_count());
+            for row in 0..=buffer.line_count() {
+                prop_assert_eq!(buffer.line_offset(row), buffer.text.line_offset(row));
+                prop_assert_eq!(buffer.line(row), TextStorage::line(&buffer.text, row));
+            }
+        }
+    }
+
+    #[test]
+    fn line_index_follows_splices(
+        text in "[ab\n]{0,2000}",
+        edits in prop::collection::vec((0..2000usize, 0..400usize, "[ab\n]{0,400}"), 0..8),
+    ) {
+        // Long enough to be held in several chunks
+        let mut text = text;
+        let mut index = LineIndex::new(&text);
+        for (start, len, insert) in edits {
+            let start = start.min(text.len());
+            let range = start..(start + len).min(text.len());
+            index.splice(range.clone(), &insert);
+            text.replace_range(range, &insert);
+
+            // The index of the whole text is checked against the text by the tests above
+            let expected = LineIndex::new(&text);
+            prop_assert_eq!(&index, &expected);
+            prop_assert_eq!(index.line_count(), expected.line_count());
+            for row in 0..=expected.line_count() {
+                prop_assert_eq!(index.line_offset(row), expected.line_offset(row));
+                prop_assert_eq!(index.line_end(row), expected.line_end(row));
+            }
+            for offset in 0..=text.len() {
+                prop_assert_eq!(index.offset_line(offset), expected.offset_line(offset));
+            }
+        }
+    }
+}
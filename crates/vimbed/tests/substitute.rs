@@ -0,0 +1,68 @@
+mod common;
+
+use vimbed::context::Context;
+
+use common::run;
+
+#[test]
+fn trailing_count_stops_at_last_line() {
+    assert_eq!(run("a\nb\nb", "j:s/b/x/ 5<CR>"), "a\nx\nx");
+    assert_eq!(
+        run("a\nb\nb", "j:s/b/x/ 18446744073709551615<CR>"),
+        "a\nx\nx"
+    );
+}
+
+#[test]
+fn trailing_count_must_be_positive() {
+    let mut ctx = Context::new().with_text("a");
+    ctx.input_keys(":s/a/x/ 0<CR>");
+    assert_eq!(ctx.text(), "a");
+    assert_eq!(
+        ctx.drain_messages().last().unwrap().to_string(),
+        "E939: Positive count required"
+    );
+}
+
+#[test]
+fn confirm_asks_for_each_match() {
+    assert_eq!(run("a a a\na", ":%s/a/b/gc<CR>ynl"), "b a b\na");
+    assert_eq!(run("a a a\na", ":%s/a/b/gc<CR>a"), "b b b\nb");
+    assert_eq!(run("a a a\na", ":%s/a/b/gc<CR>nq"), "a a a\na");
+    assert_eq!(run("a a a\na", ":%s/a/b/gc<CR>y<Esc>"), "b a a\na");
+}
+
+#[test]
+fn confirm_prompt_lists_answers() {
+    let mut ctx = Context::new().with_text("a");
+    ctx.input_keys(":s/a/b/c<CR>");
+    let substitution = ctx.substitute_confirm.as_ref().unwrap();
+    assert_eq!(substitution.prompt(), "replace with b (y/n/a/q/l)?");
+}
+
+#[test]
+fn carriage_return_splits_line() {
+    assert_eq!(run("a,b,c", r":s/,/\r/g<CR>"), "a\nb\nc");
+}
+
+#[test]
+fn ampersand_is_whole_match() {
+    assert_eq!(run("ab ab", ":s/ab/[&]/g<CR>"), "[ab] [ab]");
+    assert_eq!(run("ab", r":s/ab/\&/<CR>"), "&");
+}
+
+#[test]
+fn tilde_is_previous_replacement() {
+    assert_eq!(run("a b c", ":s/a/x/<CR>:s/b/~y/<CR>"), "x xy c");
+    assert_eq!(run("a b", r":s/a/x/<CR>:s/b/\~/<CR>"), "x ~");
+}
+
+#[test]
+fn repeat_with_flags() {
+    // `g&` repeats the last substitute on every line with its flags
+    assert_eq!(run("a a\na a", ":s/a/x/g<CR>g&"), "x x\nx x");
+    // `:&&` keeps the flags, while `:&` drops them
+    assert_eq!(run("a a\na a", ":s/a/x/g<CR>j:&&<CR>"), "x x\nx x");
+    assert_eq!(run("a a\na a", ":s/a/x/g<CR>j:&<CR>"), "x x\nx a");
+    assert_eq!(run("a a\na a", ":s/a/x/<CR>:%&&<CR>"), "x x\nx a");
+}
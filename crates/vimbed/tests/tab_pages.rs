@@ -0,0 +1,106 @@
+use proptest::prelude::*;
+
+use vimbed::context::Context;
+
+/// Keys opening, closing, moving or going to tab pages, or editing in one
+fn keys() -> impl Strategy<Value = Vec<&'static str>> {
+    let key = prop_oneof![
+        Just(":tabnew<CR>"),
+        Just(":tabclose<CR>"),
+        Just("gt"),
+        Just("gT"),
+        Just("2gt"),
+        Just(":tabmove<CR>"),
+        Just(":tabmove 0<CR>"),
+        Just(":tabmove 1<CR>"),
+        Just(":tabmove +1<CR>"),
+        Just(":tabmove -1<CR>"),
+        Just("ix<Esc>"),
+    ];
+    prop::collection::vec(key, 0..32)
+}
+
+proptest! {
+    /// Tab pages keep the order they are opened and moved into, each showing its own buffer
+    #[test]
+    fn tab_pages_match_model(keys in keys()) {
+        let mut ctx = Context::new();
+        // Buffer shown by each tab page, and the index of the current one
+        let mut model = vec![1];
+        let mut current = 0;
+
+        for key in keys {
+            ctx.input_keys(key);
+
+            let last = model.len() - 1;
+            match key {
+                ":tabnew<CR>" => {
+                    current += 1;
+                    model.insert(current, ctx.buffers.current());
+                }
+                ":tabclose<CR>" if model.len() > 1 => {
+                    model.remove(current);
+                    current = current.min(model.len() - 1);
+                }
+                "gt" => current = (current + 1) % model.len(),
+                "gT" => current = (current + model.len() - 1) % model.len(),
+                "2gt" if model.len() >= 2 => current = 1,
+                ":tabmove<CR>" => {
+                    let page = model.remove(current);
+                    current = last;
+                    model.insert(current, page);
+                }
+                ":tabmove 0<CR>" => {
+                    let page = model.remove(current);
+                    current = 0;
+                    model.insert(current, page);
+                }
+                ":tabmove 1<CR>" => {
+                    let page = model.remove(current);
+                    // Moving the first tab page after itself leaves it in place
+                    current = current.min(1);
+                    model.insert(current, page);
+                }
+                ":tabmove +1<CR>" if current < last => {
+                    model.swap(current, current + 1);
+                    current += 1;
+                }
+                ":tabmove -1<CR>" if current > 0 => {
+                    model.swap(current, current - 1);
+                    current -= 1;
+                }
+                _ => (),
+            }
+
+            prop_assert_eq!(ctx.tab_pages.len(), model.len());
+            prop_assert_eq!(ctx.tab_pages.current(), current + 1);
+            prop_assert_eq!(ctx.buffers.current(), model[current]);
+
+            let tabline = ctx.tabline();
+            prop_assert_eq!(tabline.len(), model.len());
+            for (i, label) in tabline.iter().enumerate() {
+                prop_assert_eq!(label.number, i + 1);
+                prop_assert_eq!(label.current, i == current);
+                let windows = match label.current {
+                    true => &ctx.windows,
+                    false => ctx.tab_pages.get(label.number).unwrap(),
+                };
+                prop_assert!(windows.iter().all(|window| window.buffer == model[i]));
+                let modified = ctx.listed_buffer(model[i]).unwrap().modified();
+                prop_assert_eq!(label.modified, modified);
+            }
+        }
+    }
+}
+
+#[test]
+fn tabmove_past_the_end_is_invalid() {
+    let mut ctx = Context::new();
+    ctx.input_keys(":tabnew<CR>");
+    for offset in ["+1", "+18446744073709551615"] {
+        ctx.input_keys(&format!(":tabmove {}<CR>", offset));
+        assert_eq!(ctx.tab_pages.current(), 2);
+        let message = ctx.messages.drain().next_back().unwrap().to_string();
+        assert!(message.starts_with("E474"), "{}", message);
+    }
+}
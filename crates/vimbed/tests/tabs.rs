@@ -0,0 +1,33 @@
+mod common;
+
+use vimbed::context::Context;
+
+use common::run;
+
+/// Lines mixing tabs and spaces in their indent and between words
+const TEXT: &str = "\tx\n        y\n    \tz  \t w";
+
+#[test]
+fn retab_changes_only_whitespace_with_tabs() {
+    assert_eq!(run(TEXT, ":retab<CR>"), "\tx\n        y\n\tz\t w");
+    assert_eq!(
+        run(TEXT, ":set et<CR>:retab<CR>"),
+        "        x\n        y\n        z        w"
+    );
+}
+
+#[test]
+fn retab_bang_changes_spaces_too() {
+    assert_eq!(run(TEXT, ":retab!<CR>"), "\tx\n\ty\n\tz\t w");
+    assert_eq!(run("  x\n    y", ":set ts=4<CR>:retab!<CR>"), "  x\n\ty");
+}
+
+#[test]
+fn retab_bang_with_new_tabstop() {
+    let mut ctx = Context::new().with_text(TEXT);
+    ctx.input_keys(":retab! 4<CR>");
+    assert_eq!(ctx.text(), "\t\tx\n\t\ty\n\t\tz\t\t w");
+    assert_eq!(ctx.options.number("tabstop"), Some(4));
+
+    assert_eq!(run(TEXT, ":2retab! 4<CR>"), "\tx\n\t\ty\n    \tz  \t w");
+}
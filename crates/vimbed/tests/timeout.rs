@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+use vimbed::{
+    context::{Context, KeyOutcome},
+    key::{keys, Key, KeyCode},
+    mode::Mode,
+};
+
+/// Context with `jk` mapped to `<Esc>` in insert mode, inserting
+fn insert_with_jk() -> Context<'static> {
+    let mut ctx = Context::new().with_text("abc");
+    ctx.input_keys(":imap jk <lt>Esc><CR>i");
+    ctx
+}
+
+/// Type each key of `notation` at `now`
+fn type_keys(ctx: &mut Context, notation: &str, now: Instant) -> Vec<KeyOutcome> {
+    keys(notation).map(|key| ctx.input_key(key, now)).collect()
+}
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn key_completing_mapping_is_used() {
+    let mut ctx = insert_with_jk();
+    let now = Instant::now();
+    assert_eq!(
+        type_keys(&mut ctx, "jk", now),
+        [KeyOutcome::Pending, KeyOutcome::Consumed]
+    );
+    assert_eq!(ctx.text(), "abc");
+    assert_eq!(ctx.mode, Mode::Normal);
+}
+
+#[test]
+fn held_key_times_out_as_itself() {
+    let mut ctx = insert_with_jk();
+    let now = Instant::now();
+    type_keys(&mut ctx, "j", now);
+    assert_eq!(ctx.next_tick(), Some(now + ms(1000)));
+
+    ctx.tick(now + ms(999));
+    assert_eq!(ctx.text(), "abc");
+    ctx.tick(now + ms(1000));
+    assert_eq!(ctx.text(), "jabc");
+    assert_eq!(ctx.next_tick(), None);
+
+    // A key after the timeout no longer completes the mapping
+    ctx.input_key(Key::char('k'), now + ms(1001));
+    assert_eq!(ctx.text(), "jkabc");
+}
+
+#[test]
+fn late_key_resolves_held_keys_first() {
+    let mut ctx = insert_with_jk();
+    let now = Instant::now();
+    type_keys(&mut ctx, "j", now);
+    type_keys(&mut ctx, "k", now + ms(2000));
+    assert_eq!(ctx.text(), "jkabc");
+}
+
+#[test]
+fn timeoutlen_sets_the_wait() {
+    let mut ctx = insert_with_jk();
+    ctx.options.set("timeoutlen", 100i64).unwrap();
+    let now = Instant::now();
+    type_keys(&mut ctx, "j", now);
+    assert_eq!(ctx.next_tick(), Some(now + ms(100)));
+
+    ctx.options.set("timeout", false).unwrap();
+    assert_eq!(ctx.next_tick(), None);
+    ctx.tick(now + ms(100_000));
+    assert_eq!(ctx.text(), "abc");
+    type_keys(&mut ctx, "k", now + ms(100_000));
+    assert_eq!(ctx.text(), "abc");
+}
+
+#[test]
+fn escape_waits_for_ttimeoutlen() {
+    let mut ctx = Context::new().with_text("abc");
+    ctx.input_keys(":imap <lt>Esc>x y<CR>i");
+    ctx.options.set("ttimeoutlen", 10i64).unwrap();
+    let now = Instant::now();
+    ctx.input_key(KeyCode::Escape.into(), now);
+    assert_eq!(ctx.next_tick(), Some(now + ms(10)));
+
+    // 'ttimeout' keeps the wait for key codes when 'timeout' is off
+    ctx.options.set("timeout", false).unwrap();
+    assert_eq!(ctx.next_tick(), None);
+    ctx.options.set("ttimeout", true).unwrap();
+    assert_eq!(ctx.next_tick(), Some(now + ms(10)));
+}
@@ -0,0 +1,147 @@
+use proptest::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
+
+use vimbed::{context::Context, grapheme};
+
+/// Text mixing ASCII with wide, combining, joined and multi-byte characters
+fn text() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        "[a-z ]{1,4}",
+        Just("\n".to_owned()),
+        Just("\t".to_owned()),
+        Just("日本".to_owned()),
+        Just("e\u{301}".to_owned()),
+        Just("a\u{308}\u{323}".to_owned()),
+        Just("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}".to_owned()),
+        Just("\u{1F1EF}\u{1F1F5}".to_owned()),
+        Just("\u{FF21}".to_owned()),
+        Just("\u{200B}".to_owned()),
+        Just("\r\n".to_owned()),
+        any::<char>().prop_map(|c| c.to_string()),
+    ];
+    prop::collection::vec(piece, 0..24).prop_map(|pieces| pieces.concat())
+}
+
+/// Normal mode keys that move the cursor or edit the buffer
+fn keys() -> impl Strategy<Value = Vec<&'static str>> {
+    let key = prop_oneof![
+        Just("h"),
+        Just("j"),
+        Just("k"),
+        Just("l"),
+        Just("w"),
+        Just("b"),
+        Just("$"),
+        Just("^"),
+        Just("x"),
+        Just("u"),
+        Just("<C-r>"),
+        Just("i日<ESC>"),
+        Just("ae\u{301}<BS><ESC>"),
+        Just("A<Tab>x<ESC>"),
+        Just("o<ESC>"),
+        Just("dd"),
+    ];
+    prop::collection::vec(key, 0..32)
+}
+
+/// Check the cursor rests on a cluster boundary within its line
+fn assert_cursor_valid(ctx: &Context) {
+    let buffer = &ctx.buffer_edit;
+    let line = buffer
+        .line(buffer.cursor.row)
+        .expect("cursor row out of range");
+    let column = buffer.cursor.column;
+    assert!(column <= line.len(), "column {} past line end", column);
+    assert_eq!(
+        grapheme::floor_boundary(&line, column),
+        column,
+        "column {} splits a cluster in {:?}",
+        column,
+        line
+    );
+}
+
+proptest! {
+    #[test]
+    fn cursor_stays_on_cluster_boundaries(text in text(), keys in keys()) {
+        let mut edit = text;
+        let mut command = String::new();
+        let mut search = String::new();
+        let mut ctx = Context::borrowed(&mut edit, &mut command, &mut search);
+
+        for key in keys {
+            ctx.input_keys(key);
+            assert_cursor_valid(&ctx);
+        }
+    }
+
+    #[test]
+    fn undo_restores_text(text in text(), keys in keys()) {
+        let original = text.clone();
+        let mut edit = text;
+        let mut command = String::new();
+        let mut search = String::new();
+        let mut ctx = Context::borrowed(&mut edit, &mut command, &mut search);
+
+        for key in keys.into_iter().filter(|key| *key != "u" && *key != "<C-r>") {
+            ctx.input_keys(key);
+        }
+        while ctx.buffer_edit.undo() {}
+
+        prop_assert_eq!(ctx.text(), original.as_str());
+    }
+
+    #[test]
+    fn right_steps_over_each_cluster(line in "[^\n]{0,24}") {
+        let clusters = line.graphemes(true).count();
+        let mut edit = line.clone();
+        let mut command = String::new();
+        let mut search = String::new();
+        let mut ctx = Context::borrowed(&mut edit, &mut command, &mut search);
+
+        let mut steps = 0;
+        loop {
+            let before = ctx.buffer_edit.cursor.column;
+            ctx.input_keys("l");
+            if ctx.buffer_edit.cursor.column == before {
+                break;
+            }
+            steps += 1;
+        }
+
+        prop_assert_eq!(steps, clusters.saturating_sub(1));
+    }
+
+    #[test]
+    fn virtual_columns_round_trip(text in text()) {
+        let mut edit = text;
+        let mut command = String::new();
+        let mut search = String::new();
+        let ctx = Context::borrowed(&mut edit, &mut command, &mut search);
+        let buffer = &ctx.buffer_edit;
+
+        for (row, line) in buffer.lines().enumerate() {
+            let mut previous = 0;
+            for (column, g) in line.grapheme_indices(true) {
+                let virtual_column = buffer.virtual_column(row, column);
+                prop_assert!(virtual_column >= previous);
+                previous = virtual_column;
+
+                if buffer.tabs.width(g, virtual_column) > 0 {
+                    prop_assert_eq!(buffer.column_at_virtual(row, virtual_column), column);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn display_width_counts_wide_and_zero_width(
+        wide in prop::collection::vec(prop_oneof![Just("日"), Just("\u{FF21}")], 0..8),
+        narrow in "[a-z]{0,8}",
+    ) {
+        let text = wide.concat() + "e\u{301}" + &narrow;
+        let width = text.graphemes(true).map(grapheme::width).sum::<usize>();
+        prop_assert_eq!(width, wide.len() * 2 + 1 + narrow.len());
+    }
+}
@@ -0,0 +1,95 @@
+use proptest::prelude::*;
+
+use vimbed::{context::Context, window::Rect};
+
+/// Keys editing or moving in the current window, or arranging windows
+fn keys() -> impl Strategy<Value = Vec<&'static str>> {
+    let key = prop_oneof![
+        Just("j"),
+        Just("5j"),
+        Just("G"),
+        Just("gg"),
+        Just("dd"),
+        Just("p"),
+        Just("u"),
+        Just("ix\n<Esc>"),
+        Just("<C-w>s"),
+        Just("<C-w>v"),
+        Just("<C-w>w"),
+        Just("<C-w>W"),
+        Just("2<C-w>w"),
+        Just("<C-w>h"),
+        Just("<C-w>j"),
+        Just("<C-w>k"),
+        Just("<C-w>l"),
+        Just("<C-w>c"),
+        Just("<C-w>o"),
+        Just("<C-w>="),
+        Just("3<C-w>+"),
+        Just("<C-w>-"),
+        Just(":q<CR>"),
+        Just(":b2<CR>"),
+        Just(":b1<CR>"),
+    ];
+    prop::collection::vec(key, 0..32)
+}
+
+proptest! {
+    /// Windows and their separators tile the screen, and the current window shows its cursor
+    #[test]
+    fn windows_tile_the_screen(
+        text in "[a-c \n]{0,64}",
+        (width, height) in (8..100usize, 6..40usize),
+        keys in keys(),
+    ) {
+        let mut ctx = Context::new().with_text(text);
+        ctx.add_buffer(String::from("one\ntwo\nthree"));
+        ctx.ex("set hidden");
+        let area = Rect { x: 0, y: 0, width, height };
+        ctx.set_window_area(area);
+
+        for key in keys {
+            ctx.input_keys(key);
+
+            let rects = ctx.windows.rects();
+            prop_assert_eq!(rects.len(), ctx.windows.len());
+
+            let mut cells = vec![0; width * height];
+            let separators = ctx.windows.separators();
+            for rect in rects.iter().map(|(_, rect)| rect).chain(&separators) {
+                prop_assert!(rect.x + rect.width <= width && rect.y + rect.height <= height);
+                for y in rect.y..rect.y + rect.height {
+                    for x in rect.x..rect.x + rect.width {
+                        cells[y * width + x] += 1;
+                    }
+                }
+            }
+            prop_assert!(cells.iter().all(|&count| count == 1));
+
+            let current = ctx.windows.get(ctx.windows.current()).unwrap();
+            prop_assert_eq!(current.buffer, ctx.buffers.current());
+            let row = ctx.buffer_edit.cursor.row;
+            let text_height = ctx.windows.text_height(current.id);
+            prop_assert!(current.top <= row && row < current.top + text_height.max(1));
+        }
+    }
+
+    /// Each window keeps its own cursor in a shared buffer
+    #[test]
+    fn windows_keep_their_cursors(
+        lines in 2..40usize,
+        first in 0..40usize,
+        second in 0..40usize,
+    ) {
+        let text = (0..lines).map(|i| format!("line {}\n", i)).collect::<String>();
+        let mut ctx = Context::new().with_text(text);
+        let (first, second) = (first % lines, second % lines);
+
+        ctx.input_keys(&format!("{}G<C-w>s{}G", first + 1, second + 1));
+        prop_assert_eq!(ctx.buffer_edit.cursor.row, second);
+        ctx.input_keys("<C-w>j");
+        prop_assert_eq!(ctx.buffer_edit.cursor.row, first);
+        ctx.input_keys("<C-w>k");
+        prop_assert_eq!(ctx.buffer_edit.cursor.row, second);
+    }
+}